@@ -0,0 +1,420 @@
+use crate::errors::ImageError;
+use crate::image::{luma, ChannelLayout, Image};
+
+use std::fmt;
+
+
+// a comparison passes when every measurement is on the right side of its tolerance
+// pixel_threshold decides which pixels count as differing, by their largest channel difference
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerances {
+    pub max_abs_diff:f32,
+    pub max_mean_abs_diff:f32,
+    pub min_psnr:f32,
+    pub min_ssim:f32,
+    pub pixel_threshold:f32,
+    pub max_differing_fraction:f32,
+}
+
+impl Tolerances {
+    // exact match, any difference fails
+    pub fn exact() -> Tolerances {
+        Tolerances {
+            max_abs_diff:0.0,
+            max_mean_abs_diff:0.0,
+            min_psnr:f32::INFINITY,
+            min_ssim:1.0,
+            pixel_threshold:0.0,
+            max_differing_fraction:0.0,
+        }
+    }
+
+    // allows u8 rounding and a sprinkle of noisy pixels, suitable for most render comparisons
+    pub fn new() -> Tolerances {
+        Tolerances {
+            max_abs_diff:0.1,
+            max_mean_abs_diff:0.005,
+            min_psnr:40.0,
+            min_ssim:0.99,
+            pixel_threshold:2.0/255.0,
+            max_differing_fraction:0.001,
+        }
+    }
+}
+
+impl Default for Tolerances {
+    fn default() -> Tolerances {
+        Tolerances::new()
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ComparisonReport {
+    pub width:usize,
+    pub height:usize,
+    pub max_abs_diff:f32,
+    pub worst_pixel:(usize, usize),
+    pub mean_abs_diff:f32,
+    pub mse:f32,
+    pub psnr:f32,
+    pub ssim:f32,
+    pub differing_pixels:usize,
+}
+
+impl ComparisonReport {
+    pub fn differing_fraction(&self) -> f32 {
+        self.differing_pixels as f32 / (self.width * self.height) as f32
+    }
+
+    // lists every tolerance that was broken
+    pub fn failures(&self, tolerances:&Tolerances) -> Vec<String> {
+        let mut failures = vec![];
+        if self.max_abs_diff > tolerances.max_abs_diff {
+            failures.push(format!("max abs diff {} at {:?} > {}", self.max_abs_diff, self.worst_pixel, tolerances.max_abs_diff));
+        }
+        if self.mean_abs_diff > tolerances.max_mean_abs_diff {
+            failures.push(format!("mean abs diff {} > {}", self.mean_abs_diff, tolerances.max_mean_abs_diff));
+        }
+        if self.psnr < tolerances.min_psnr {
+            failures.push(format!("psnr {} dB < {} dB", self.psnr, tolerances.min_psnr));
+        }
+        if self.ssim < tolerances.min_ssim {
+            failures.push(format!("ssim {} < {}", self.ssim, tolerances.min_ssim));
+        }
+        if self.differing_fraction() > tolerances.max_differing_fraction {
+            failures.push(format!("{} pixels ({:.4}%) differ by more than {}, allowed {:.4}%",
+                self.differing_pixels, 100.0*self.differing_fraction(),
+                tolerances.pixel_threshold, 100.0*tolerances.max_differing_fraction));
+        }
+        failures
+    }
+
+    pub fn passes(&self, tolerances:&Tolerances) -> bool {
+        self.failures(tolerances).is_empty()
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}x{} image comparison", self.width, self.height)?;
+        writeln!(f, "    max abs diff  {} at {:?}", self.max_abs_diff, self.worst_pixel)?;
+        writeln!(f, "    mean abs diff {}", self.mean_abs_diff)?;
+        writeln!(f, "    mse           {}", self.mse)?;
+        writeln!(f, "    psnr          {} dB", self.psnr)?;
+        writeln!(f, "    ssim          {}", self.ssim)?;
+        write!(f, "    differing     {} pixels ({:.4}%)", self.differing_pixels, 100.0*self.differing_fraction())
+    }
+}
+
+
+fn check_dimensions(a:&Image, b:&Image) -> Result<(), ImageError> {
+    match (a.width, a.height) == (b.width, b.height) {
+        true => Ok(()),
+        false => Err(ImageError::DimensionMismatch(((a.width, a.height), (b.width, b.height)))),
+    }
+}
+
+// alpha only takes part when one of the images has it
+fn compared_channels(a:&Image, b:&Image) -> usize {
+    match a.layout.has_alpha() || b.layout.has_alpha() {
+        true => 4,
+        false => 3,
+    }
+}
+
+
+// per channel |a - b| as an f32 image, with alpha when either input has it
+pub fn abs_diff(a:&Image, b:&Image) -> Result<Image, ImageError> {
+    check_dimensions(a, b)?;
+    let channels = compared_channels(a, b);
+    let layout = if channels == 4 {ChannelLayout::Rgba} else {ChannelLayout::Rgb};
+
+    let mut diff = Image::new_f32(a.width, a.height, layout);
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let (pa, pb) = (a.rgba(x, y), b.rgba(x, y));
+            let values : Vec<f32> = (0..channels).map(|c| (pa[c] - pb[c]).abs()).collect();
+            diff.set_pixel(x, y, &values);
+        }
+    }
+    Ok(diff)
+}
+
+pub fn mse(a:&Image, b:&Image) -> Result<f32, ImageError> {
+    check_dimensions(a, b)?;
+    let channels = compared_channels(a, b);
+    let mut sum = 0.0f64;
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let (pa, pb) = (a.rgba(x, y), b.rgba(x, y));
+            for c in 0..channels {
+                sum += ((pa[c] - pb[c]) as f64).powi(2);
+            }
+        }
+    }
+    Ok((sum / (a.width * a.height * channels) as f64) as f32)
+}
+
+// peak signal is 1.0, identical images give infinity
+pub fn psnr(a:&Image, b:&Image) -> Result<f32, ImageError> {
+    Ok(psnr_from_mse(mse(a, b)?))
+}
+
+fn psnr_from_mse(mse:f32) -> f32 {
+    match mse {
+        0.0 => f32::INFINITY,
+        mse => -10.0 * mse.log10(),
+    }
+}
+
+
+// mean structural similarity of the luma planes, with the usual 11x11 gaussian window (sigma 1.5)
+pub fn ssim(a:&Image, b:&Image) -> Result<f32, ImageError> {
+    check_dimensions(a, b)?;
+    let (width, height) = (a.width, a.height);
+
+    let luma_plane = |image:&Image| -> Vec<f32> {
+        (0..height).flat_map(|y| (0..width).map(move |x| (y, x)))
+            .map(|(y, x)| luma(image.rgb(x, y)))
+            .collect()
+    };
+    let la = luma_plane(a);
+    let lb = luma_plane(b);
+
+    let product = |p:&[f32], q:&[f32]| -> Vec<f32> { p.iter().zip(q).map(|(p, q)| p*q).collect() };
+
+    let mu_a = gaussian_blur(&la, width, height);
+    let mu_b = gaussian_blur(&lb, width, height);
+    let aa = gaussian_blur(&product(&la, &la), width, height);
+    let bb = gaussian_blur(&product(&lb, &lb), width, height);
+    let ab = gaussian_blur(&product(&la, &lb), width, height);
+
+    let c1 = (0.01f32).powi(2);
+    let c2 = (0.03f32).powi(2);
+
+    let mut sum = 0.0f64;
+    for i in 0..width*height {
+        let var_a = aa[i] - mu_a[i]*mu_a[i];
+        let var_b = bb[i] - mu_b[i]*mu_b[i];
+        let covariance = ab[i] - mu_a[i]*mu_b[i];
+        let numerator = (2.0*mu_a[i]*mu_b[i] + c1) * (2.0*covariance + c2);
+        let denominator = (mu_a[i]*mu_a[i] + mu_b[i]*mu_b[i] + c1) * (var_a + var_b + c2);
+        sum += (numerator / denominator) as f64;
+    }
+    Ok((sum / (width*height) as f64) as f32)
+}
+
+// separable blur, edges are clamped
+fn gaussian_blur(plane:&[f32], width:usize, height:usize) -> Vec<f32> {
+    let radius = 5i64;
+    let sigma = 1.5f32;
+    let mut kernel : Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i*i) as f32) / (2.0*sigma*sigma)).exp())
+        .collect();
+    let total : f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|k| *k /= total);
+
+    let clamp = |i:i64, n:usize| -> usize { i.clamp(0, n as i64 - 1) as usize };
+
+    let mut horizontal = vec![0.0; plane.len()];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y*width + x] = kernel.iter().enumerate()
+                .map(|(k, weight)| weight * plane[y*width + clamp(x as i64 + k as i64 - radius, width)])
+                .sum();
+        }
+    }
+    let mut blurred = vec![0.0; plane.len()];
+    for y in 0..height {
+        for x in 0..width {
+            blurred[y*width + x] = kernel.iter().enumerate()
+                .map(|(k, weight)| weight * horizontal[clamp(y as i64 + k as i64 - radius, height)*width + x])
+                .sum();
+        }
+    }
+    blurred
+}
+
+
+pub fn compare_images(a:&Image, b:&Image, tolerances:&Tolerances) -> Result<ComparisonReport, ImageError> {
+    let diff = abs_diff(a, b)?;
+
+    let mut max_abs_diff = 0.0;
+    let mut worst_pixel = (0, 0);
+    let mut sum = 0.0f64;
+    let mut differing_pixels = 0;
+    for y in 0..diff.height {
+        for x in 0..diff.width {
+            let pixel = diff.pixel(x, y);
+            let largest = pixel.iter().copied().fold(0.0, f32::max);
+            if largest > max_abs_diff {
+                max_abs_diff = largest;
+                worst_pixel = (x, y);
+            }
+            if largest > tolerances.pixel_threshold {
+                differing_pixels += 1;
+            }
+            sum += pixel.iter().map(|v| *v as f64).sum::<f64>();
+        }
+    }
+    let mean_abs_diff = (sum / (diff.width * diff.height * diff.channels()) as f64) as f32;
+    let mse = mse(a, b)?;
+
+    Ok(ComparisonReport {
+        width:a.width,
+        height:a.height,
+        max_abs_diff,
+        worst_pixel,
+        mean_abs_diff,
+        mse,
+        psnr:psnr_from_mse(mse),
+        ssim:ssim(a, b)?,
+        differing_pixels,
+    })
+}
+
+
+// largest channel difference per pixel, mapped black -> blue -> red -> yellow -> white
+// differences at or above full_scale saturate to white
+pub fn diff_heatmap(a:&Image, b:&Image, full_scale:f32) -> Result<Image, ImageError> {
+    let diff = abs_diff(a, b)?;
+    let mut heatmap = Image::new_u8(diff.width, diff.height, ChannelLayout::Rgb);
+    for y in 0..diff.height {
+        for x in 0..diff.width {
+            let largest = diff.pixel(x, y).into_iter().fold(0.0, f32::max);
+            heatmap.set_rgb(x, y, heat_colour(largest / full_scale));
+        }
+    }
+    Ok(heatmap)
+}
+
+// per channel |a - b| / full_scale as an opaque u8 image, for looking at differences too small to see directly
+pub fn scaled_abs_diff(a:&Image, b:&Image, full_scale:f32) -> Result<Image, ImageError> {
+    let diff = abs_diff(a, b)?;
+    let mut scaled = Image::new_u8(diff.width, diff.height, ChannelLayout::Rgb);
+    for y in 0..diff.height {
+        for x in 0..diff.width {
+            let values : Vec<f32> = diff.pixel(x, y).into_iter().map(|v| v / full_scale).collect();
+            scaled.set_pixel(x, y, &values);
+        }
+    }
+    Ok(scaled)
+}
+
+// luma(b) - luma(a) in false colour, red where b is brighter and blue where a is, black where they agree
+pub fn signed_diff_false_colour(a:&Image, b:&Image, full_scale:f32) -> Result<Image, ImageError> {
+    check_dimensions(a, b)?;
+    let mut false_colour = Image::new_u8(a.width, a.height, ChannelLayout::Rgb);
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let t = (luma(b.rgb(x, y)) - luma(a.rgb(x, y))) / full_scale;
+            false_colour.set_rgb(x, y, [t.max(0.0), 0.0, (-t).max(0.0)]);
+        }
+    }
+    Ok(false_colour)
+}
+
+pub fn heat_colour(t:f32) -> [f32; 3] {
+    let stops = [
+        [0.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [1.0, 1.0, 1.0],
+    ];
+    let t = if t.is_nan() {1.0} else {t.clamp(0.0, 1.0)} * (stops.len() - 1) as f32;
+    let i = (t as usize).min(stops.len() - 2);
+    let f = t - i as f32;
+    [0, 1, 2].map(|c| stops[i][c] + (stops[i+1][c] - stops[i][c]) * f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat(width:usize, height:usize, value:f32) -> Image {
+        let mut image = Image::new_f32(width, height, ChannelLayout::Rgb);
+        for y in 0..height {
+            for x in 0..width {
+                image.set_rgb(x, y, [value; 3]);
+            }
+        }
+        image
+    }
+
+    fn close(a:f32, b:f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+
+    #[test]
+    fn identical_images_match_exactly() {
+        let mut image = flat(16, 16, 0.3);
+        image.set_rgb(3, 5, [0.9, 0.1, 0.4]);
+        let report = compare_images(&image, &image, &Tolerances::exact()).expect("the sizes match");
+        assert_eq!(report.psnr, f32::INFINITY);
+        assert!(close(report.ssim, 1.0), "ssim {}", report.ssim);
+        assert_eq!((report.mse, report.max_abs_diff, report.differing_pixels), (0.0, 0.0, 0));
+        assert!(report.passes(&Tolerances::exact()));
+    }
+
+    // a mean squared error of 0.01 is 20 dB
+    #[test]
+    fn a_constant_offset_gives_the_expected_error() {
+        let (a, b) = (flat(16, 16, 0.25), flat(16, 16, 0.35));
+        assert!(close(mse(&a, &b).expect("the sizes match"), 0.01));
+        assert!(close(psnr(&a, &b).expect("the sizes match"), 20.0));
+        let report = compare_images(&a, &b, &Tolerances::new()).expect("the sizes match");
+        assert!(close(report.mean_abs_diff, 0.1) && close(report.max_abs_diff, 0.1));
+        assert_eq!(report.differing_pixels, 16 * 16);
+    }
+
+    #[test]
+    fn the_worst_pixel_is_found() {
+        let a = flat(8, 4, 0.5);
+        let mut b = a.clone();
+        b.set_rgb(6, 1, [0.5, 0.9, 0.5]);
+        let report = compare_images(&a, &b, &Tolerances::new()).expect("the sizes match");
+        assert_eq!((report.worst_pixel, report.differing_pixels), ((6, 1), 1));
+        assert!(close(report.max_abs_diff, 0.4));
+    }
+
+    // an offset of 0.2 between flat images breaks the mean difference and ssim tolerances and nothing else
+    #[test]
+    fn failures_list_exactly_the_broken_tolerances() {
+        let report = compare_images(&flat(16, 16, 0.25), &flat(16, 16, 0.45), &Tolerances::new()).expect("the sizes match");
+        let tolerances = Tolerances {
+            max_abs_diff:0.5,
+            max_mean_abs_diff:0.1,
+            min_psnr:10.0,
+            min_ssim:0.9,
+            pixel_threshold:0.1,
+            max_differing_fraction:1.0,
+        };
+        let failures = report.failures(&tolerances);
+        assert_eq!(failures.len(), 2, "{:?}", failures);
+        assert!(failures[0].starts_with("mean abs diff") && failures[1].starts_with("ssim"), "{:?}", failures);
+        assert!(!report.passes(&tolerances));
+        assert_eq!(report.failures(&Tolerances::new()).len(), 5);
+    }
+
+    #[test]
+    fn heatmaps_are_the_size_of_their_inputs() {
+        let heatmap = diff_heatmap(&flat(7, 3, 0.0), &flat(7, 3, 1.0), 0.25).expect("the sizes match");
+        assert_eq!((heatmap.width, heatmap.height, heatmap.layout), (7, 3, ChannelLayout::Rgb));
+        // differences past full scale saturate to white
+        assert_eq!(heatmap.rgb(0, 0), [1.0, 1.0, 1.0]);
+        assert_eq!(diff_heatmap(&flat(7, 3, 0.5), &flat(7, 3, 0.5), 0.25).expect("the sizes match").rgb(6, 2), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn different_sizes_are_errors() {
+        let (a, b) = (flat(4, 4, 0.0), flat(4, 3, 0.0));
+        let mismatch = |result:Result<(), ImageError>| matches!(result, Err(ImageError::DimensionMismatch(((4, 4), (4, 3)))));
+        assert!(mismatch(compare_images(&a, &b, &Tolerances::new()).map(|_| ())));
+        assert!(mismatch(mse(&a, &b).map(|_| ())));
+        assert!(mismatch(ssim(&a, &b).map(|_| ())));
+        assert!(mismatch(diff_heatmap(&a, &b, 1.0).map(|_| ())));
+    }
+}
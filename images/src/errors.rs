@@ -0,0 +1,68 @@
+use png::{BitDepth, ColorType, DecodingError, EncodingError};
+
+
+#[derive(Debug)]
+pub enum NetpbmError {
+    InvalidMagic([u8; 2]),
+    InvalidToken(String),
+    InvalidDimensions((usize, usize)),
+    InvalidMaxval(usize),
+    SampleOutOfRange((usize, u16)),
+    MissingRasterSeparator(u8),
+    TruncatedRaster((usize, usize)),
+    UnexpectedEof(usize),
+}
+
+#[derive(Debug)]
+pub enum HdrError {
+    InvalidHeader(String),
+    UnsupportedFormat(String),
+    UnsupportedOrientation(String),
+    InvalidScale(String),
+    InvalidScanline(usize),
+    TruncatedData((usize, usize)),
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    FileError(std::io::Error),
+    NetpbmError(NetpbmError),
+    HdrError(HdrError),
+    InvalidDimensions((usize, usize)),
+    DataLengthError(usize),
+    DimensionMismatch(((usize, usize), (usize, usize))),
+    RowLengthError((usize, usize)),
+    TooManyRows(usize),
+    IncompleteImage((usize, usize)),
+    UnknownFormat([u8; 2]),
+    PngDecodingError(DecodingError),
+    PngEncodingError(EncodingError),
+    UnsupportedPngColourType(ColorType),
+    UnsupportedPngBitDepth(BitDepth),
+}
+
+impl From<std::io::Error> for ImageError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FileError(value)
+    }
+}
+impl From<NetpbmError> for ImageError {
+    fn from(value: NetpbmError) -> Self {
+        Self::NetpbmError(value)
+    }
+}
+impl From<HdrError> for ImageError {
+    fn from(value: HdrError) -> Self {
+        Self::HdrError(value)
+    }
+}
+impl From<DecodingError> for ImageError {
+    fn from(value: DecodingError) -> Self {
+        Self::PngDecodingError(value)
+    }
+}
+impl From<EncodingError> for ImageError {
+    fn from(value: EncodingError) -> Self {
+        Self::PngEncodingError(value)
+    }
+}
\ No newline at end of file
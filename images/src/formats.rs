@@ -0,0 +1,44 @@
+use crate::errors::ImageError;
+use crate::image::Image;
+use crate::netpbm;
+use crate::pfm;
+use crate::png;
+use crate::radiance;
+
+use std::fs;
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ImageFormat {
+    Netpbm,
+    Png,
+    Radiance,
+    Pfm,
+}
+
+// decoders are chosen by the first bytes of the file, never by its extension
+pub fn detect_format(bytes:&[u8]) -> Result<ImageFormat, ImageError> {
+    match bytes {
+        [b'P', b'1'..=b'6', ..] => Ok(ImageFormat::Netpbm),
+        _ if bytes.starts_with(&png::PNG_SIGNATURE) => Ok(ImageFormat::Png),
+        _ if radiance::is_radiance(bytes) => Ok(ImageFormat::Radiance),
+        _ if pfm::is_pfm(bytes) => Ok(ImageFormat::Pfm),
+        [a, b, ..] => Err(ImageError::UnknownFormat([*a, *b])),
+        [a] => Err(ImageError::UnknownFormat([*a, 0])),
+        [] => Err(ImageError::UnknownFormat([0, 0])),
+    }
+}
+
+pub fn decode_image(bytes:&[u8]) -> Result<Image, ImageError> {
+    match detect_format(bytes)? {
+        ImageFormat::Netpbm => netpbm::parse_netpbm(bytes),
+        ImageFormat::Png => png::decode_png(bytes),
+        ImageFormat::Radiance => radiance::parse_radiance(bytes),
+        ImageFormat::Pfm => pfm::parse_pfm(bytes),
+    }
+}
+
+pub fn load_image(path:&str) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    decode_image(&bytes)
+}
\ No newline at end of file
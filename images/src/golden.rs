@@ -0,0 +1,156 @@
+use crate::compare::{compare_images, diff_heatmap, ComparisonReport, Tolerances};
+use crate::errors::ImageError;
+use crate::formats;
+use crate::image::{Image, ImageData};
+use crate::pfm;
+use crate::png;
+use crate::radiance;
+
+use std::env;
+use std::path::Path;
+
+
+// set to rewrite reference images from the current renders instead of comparing against them
+pub const UPDATE_REFERENCES_VAR : &str = "UPDATE_REFERENCE_IMAGES";
+
+
+// foo/bar.png -> foo/bar.<suffix>.png
+fn sibling_path(reference_path:&str, suffix:&str) -> String {
+    let path = Path::new(reference_path);
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => String::from("reference"),
+    };
+    path.with_file_name(format!("{}.{}.png", stem, suffix)).to_string_lossy().into_owned()
+}
+
+// .pfm and .hdr references keep float renders as they are, anything else is written as png
+fn save_reference(render:&Image, reference_path:&str) -> Result<(), ImageError> {
+    let extension = Path::new(reference_path).extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "pfm" => pfm::save_pfm(render, reference_path),
+        "hdr" => radiance::save_radiance(render, reference_path),
+        _ => png::save_png(render, reference_path),
+    }
+}
+
+// a float render against an 8 or 16 bit reference is quantised first, the same way saving the reference did,
+// so a reference written from a render matches it even where the render goes past 1
+fn stored_like(render:&Image, reference:&Image) -> Image {
+    match (&render.data, &reference.data) {
+        (ImageData::F32(_), ImageData::U8(_)) => render.to_u8(),
+        (ImageData::F32(_), ImageData::U16(_)) => render.to_u16(),
+        _ => render.clone(),
+    }
+}
+
+pub fn compare_to_reference(render:&Image, reference_path:&str, tolerances:&Tolerances) -> Result<ComparisonReport, ImageError> {
+    let reference = formats::load_image(reference_path)?;
+    compare_images(&stored_like(render, &reference), &reference, tolerances)
+}
+
+
+// panics with the full comparison report when the render is out of tolerance
+// the failing render and a heatmap of the difference are written next to the reference for inspection
+pub fn assert_matches_reference(render:&Image, reference_path:&str, tolerances:&Tolerances) {
+    check_reference(render, reference_path, tolerances, env::var_os(UPDATE_REFERENCES_VAR).is_some());
+}
+
+fn check_reference(render:&Image, reference_path:&str, tolerances:&Tolerances, update:bool) {
+    if update {
+        if let Err(error) = save_reference(render, reference_path) {
+            panic!("could not update reference image {}: {:?}", reference_path, error);
+        }
+        return;
+    }
+
+    if !Path::new(reference_path).exists() {
+        let actual_path = sibling_path(reference_path, "actual");
+        let _ = png::save_png(render, &actual_path);
+        panic!("reference image {} does not exist, the render was written to {}\n\
+                rerun with {} set to accept it", reference_path, actual_path, UPDATE_REFERENCES_VAR);
+    }
+
+    let reference = match formats::load_image(reference_path) {
+        Ok(reference) => reference,
+        Err(error) => panic!("could not load reference image {}: {:?}", reference_path, error),
+    };
+    let render = stored_like(render, &reference);
+    let report = match compare_images(&render, &reference, tolerances) {
+        Ok(report) => report,
+        Err(error) => panic!("could not compare against {}: {:?}", reference_path, error),
+    };
+
+    let failures = report.failures(tolerances);
+    if !failures.is_empty() {
+        let actual_path = sibling_path(reference_path, "actual");
+        let diff_path = sibling_path(reference_path, "diff");
+        let _ = png::save_png(&render, &actual_path);
+        if let Ok(heatmap) = diff_heatmap(&render, &reference, 0.25) {
+            let _ = png::save_png(&heatmap, &diff_path);
+        }
+        panic!("render does not match {}\n{}\nfailed:\n    {}\nrender written to {}, difference heatmap to {}",
+            reference_path, report, failures.join("\n    "), actual_path, diff_path);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::ChannelLayout;
+
+    use std::panic;
+
+    // a float render with a pixel far past 1, like a light in view
+    fn render() -> Image {
+        let mut image = Image::new_f32(4, 4, ChannelLayout::Rgb);
+        for y in 0..4 {
+            for x in 0..4 {
+                image.set_rgb(x, y, [0.1 * x as f32, 0.2 * y as f32, 0.5]);
+            }
+        }
+        image.set_rgb(1, 2, [3.0, 0.5, 0.25]);
+        image
+    }
+
+    fn reference_path(name:&str) -> String {
+        env::temp_dir().join(format!("images_golden_{}", name)).to_string_lossy().into_owned()
+    }
+
+
+    // a reference accepted from a render matches that render straight after, whatever format holds it
+    #[test]
+    fn accepted_references_match_their_render() {
+        for name in ["accepted.png", "accepted.pfm", "accepted.hdr"] {
+            let path = reference_path(name);
+            check_reference(&render(), &path, &Tolerances::new(), true);
+            check_reference(&render(), &path, &Tolerances::new(), false);
+            let report = compare_to_reference(&render(), &path, &Tolerances::new()).expect("the reference loads");
+            assert!(report.passes(&Tolerances::new()), "{}\n{}", name, report);
+        }
+        // float references keep what is past 1
+        let reference = formats::load_image(&reference_path("accepted.pfm")).expect("the reference loads");
+        assert_eq!(reference.rgb(1, 2), [3.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn changed_renders_fail_and_are_written_for_inspection() {
+        let path = reference_path("changed.pfm");
+        check_reference(&render(), &path, &Tolerances::new(), true);
+        let mut changed = render();
+        changed.set_rgb(0, 0, [1.0, 1.0, 1.0]);
+        let result = panic::catch_unwind(|| check_reference(&changed, &path, &Tolerances::new(), false));
+        assert!(result.is_err(), "a changed pixel should fail");
+        assert!(Path::new(&sibling_path(&path, "actual")).exists());
+        assert!(Path::new(&sibling_path(&path, "diff")).exists());
+    }
+
+    #[test]
+    fn missing_references_fail() {
+        let path = reference_path("missing.png");
+        let _ = std::fs::remove_file(&path);
+        assert!(panic::catch_unwind(|| check_reference(&render(), &path, &Tolerances::new(), false)).is_err());
+        assert!(Path::new(&sibling_path(&path, "actual")).exists());
+    }
+}
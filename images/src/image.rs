@@ -0,0 +1,200 @@
+use crate::errors::ImageError;
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChannelLayout {
+    Grey,
+    GreyAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl ChannelLayout {
+    pub fn channels(&self) -> usize {
+        match self {
+            ChannelLayout::Grey => 1,
+            ChannelLayout::GreyAlpha => 2,
+            ChannelLayout::Rgb => 3,
+            ChannelLayout::Rgba => 4,
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(self, ChannelLayout::GreyAlpha | ChannelLayout::Rgba)
+    }
+}
+
+
+// u8 and u16 samples are normalised against their full range, f32 samples are stored as is
+#[derive(Debug, PartialEq, Clone)]
+pub enum ImageData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+impl ImageData {
+    pub fn len(&self) -> usize {
+        match self {
+            ImageData::U8(data) => data.len(),
+            ImageData::U16(data) => data.len(),
+            ImageData::F32(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+
+// samples are stored row by row from the top left, channel interleaved
+#[derive(Debug, PartialEq, Clone)]
+pub struct Image {
+    pub width:usize,
+    pub height:usize,
+    pub layout:ChannelLayout,
+    pub data:ImageData,
+}
+
+impl Image {
+    pub fn new_u8(width:usize, height:usize, layout:ChannelLayout) -> Image {
+        Image { width, height, layout, data:ImageData::U8(vec![0; width*height*layout.channels()]) }
+    }
+    pub fn new_u16(width:usize, height:usize, layout:ChannelLayout) -> Image {
+        Image { width, height, layout, data:ImageData::U16(vec![0; width*height*layout.channels()]) }
+    }
+    pub fn new_f32(width:usize, height:usize, layout:ChannelLayout) -> Image {
+        Image { width, height, layout, data:ImageData::F32(vec![0.0; width*height*layout.channels()]) }
+    }
+
+    pub fn from_data(width:usize, height:usize, layout:ChannelLayout, data:ImageData) -> Result<Image, ImageError> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::InvalidDimensions((width, height)));
+        }
+        match data.len() == width*height*layout.channels() {
+            true => Ok(Image { width, height, layout, data }),
+            false => Err(ImageError::DataLengthError(data.len())),
+        }
+    }
+
+    pub fn channels(&self) -> usize { self.layout.channels() }
+
+    pub fn bit_depth(&self) -> usize {
+        match self.data {
+            ImageData::U8(_) => 8,
+            ImageData::U16(_) => 16,
+            ImageData::F32(_) => 32,
+        }
+    }
+
+    pub fn in_bounds(&self, x:usize, y:usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn index(&self, x:usize, y:usize, channel:usize) -> usize {
+        (y*self.width + x)*self.channels() + channel
+    }
+
+    pub fn get(&self, x:usize, y:usize, channel:usize) -> f32 {
+        let i = self.index(x, y, channel);
+        match &self.data {
+            ImageData::U8(data) => data[i] as f32 / u8::MAX as f32,
+            ImageData::U16(data) => data[i] as f32 / u16::MAX as f32,
+            ImageData::F32(data) => data[i],
+        }
+    }
+
+    // integer storage clamps to 0..=1 and rounds to the nearest level
+    pub fn set(&mut self, x:usize, y:usize, channel:usize, value:f32) {
+        let i = self.index(x, y, channel);
+        match &mut self.data {
+            ImageData::U8(data) => data[i] = quantise(value, u8::MAX as u32) as u8,
+            ImageData::U16(data) => data[i] = quantise(value, u16::MAX as u32) as u16,
+            ImageData::F32(data) => data[i] = value,
+        }
+    }
+
+    pub fn pixel(&self, x:usize, y:usize) -> Vec<f32> {
+        (0..self.channels()).map(|c| self.get(x, y, c)).collect()
+    }
+
+    pub fn set_pixel(&mut self, x:usize, y:usize, values:&[f32]) {
+        for (c, value) in values.iter().take(self.channels()).enumerate() {
+            self.set(x, y, c, *value);
+        }
+    }
+
+    // greys are spread over r, g and b, missing alpha is opaque
+    pub fn rgba(&self, x:usize, y:usize) -> [f32; 4] {
+        match self.layout {
+            ChannelLayout::Grey => {
+                let g = self.get(x, y, 0);
+                [g, g, g, 1.0]
+            },
+            ChannelLayout::GreyAlpha => {
+                let g = self.get(x, y, 0);
+                [g, g, g, self.get(x, y, 1)]
+            },
+            ChannelLayout::Rgb => [self.get(x, y, 0), self.get(x, y, 1), self.get(x, y, 2), 1.0],
+            ChannelLayout::Rgba => [self.get(x, y, 0), self.get(x, y, 1), self.get(x, y, 2), self.get(x, y, 3)],
+        }
+    }
+
+    pub fn rgb(&self, x:usize, y:usize) -> [f32; 3] {
+        let [r, g, b, _] = self.rgba(x, y);
+        [r, g, b]
+    }
+
+    // rgb is reduced to rec. 709 luma when writing into a grey layout
+    pub fn set_rgba(&mut self, x:usize, y:usize, rgba:[f32; 4]) {
+        let [r, g, b, a] = rgba;
+        match self.layout {
+            ChannelLayout::Grey => self.set_pixel(x, y, &[luma([r, g, b])]),
+            ChannelLayout::GreyAlpha => self.set_pixel(x, y, &[luma([r, g, b]), a]),
+            ChannelLayout::Rgb => self.set_pixel(x, y, &[r, g, b]),
+            ChannelLayout::Rgba => self.set_pixel(x, y, &[r, g, b, a]),
+        }
+    }
+
+    pub fn set_rgb(&mut self, x:usize, y:usize, rgb:[f32; 3]) {
+        self.set_rgba(x, y, [rgb[0], rgb[1], rgb[2], 1.0]);
+    }
+
+    fn converted(&self, layout:ChannelLayout, empty:fn(usize, usize, ChannelLayout) -> Image) -> Image {
+        let mut image = empty(self.width, self.height, layout);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match layout == self.layout {
+                    true => image.set_pixel(x, y, &self.pixel(x, y)),
+                    false => image.set_rgba(x, y, self.rgba(x, y)),
+                }
+            }
+        }
+        image
+    }
+
+    pub fn to_u8(&self) -> Image { self.converted(self.layout, Image::new_u8) }
+    pub fn to_u16(&self) -> Image { self.converted(self.layout, Image::new_u16) }
+    pub fn to_f32(&self) -> Image { self.converted(self.layout, Image::new_f32) }
+
+    pub fn with_layout(&self, layout:ChannelLayout) -> Image {
+        match &self.data {
+            ImageData::U8(_) => self.converted(layout, Image::new_u8),
+            ImageData::U16(_) => self.converted(layout, Image::new_u16),
+            ImageData::F32(_) => self.converted(layout, Image::new_f32),
+        }
+    }
+}
+
+
+pub fn quantise(value:f32, maxval:u32) -> u32 {
+    match value.is_nan() {
+        true => 0,
+        false => (value.clamp(0.0, 1.0) * maxval as f32).round() as u32,
+    }
+}
+
+pub fn luma(rgb:[f32; 3]) -> f32 {
+    0.2126*rgb[0] + 0.7152*rgb[1] + 0.0722*rgb[2]
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+pub mod errors;
+
+// in-memory image shared by the ray tracer and the viewers
+pub mod image;
+
+// file formats, picked by signature in formats
+pub mod formats;
+pub mod netpbm;
+pub mod pfm;
+pub mod png;
+pub mod radiance;
+
+// tone mapping of float images for display
+pub mod tone_map;
+
+// image comparison and reference image checks for regression tests
+pub mod compare;
+pub mod golden;
\ No newline at end of file
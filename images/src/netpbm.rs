@@ -0,0 +1,583 @@
+use crate::errors::{ImageError, NetpbmError};
+use crate::image::{quantise, ChannelLayout, Image, ImageData};
+
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+
+// the six netpbm variants, P1-P3 are ASCII and P4-P6 are their binary counterparts
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NetpbmFormat {
+    BitmapAscii,   // P1
+    GreymapAscii,  // P2
+    PixmapAscii,   // P3
+    BitmapBinary,  // P4
+    GreymapBinary, // P5
+    PixmapBinary,  // P6
+}
+
+impl NetpbmFormat {
+    pub fn from_magic(magic:[u8; 2]) -> Result<NetpbmFormat, NetpbmError> {
+        match magic {
+            [b'P', b'1'] => Ok(NetpbmFormat::BitmapAscii),
+            [b'P', b'2'] => Ok(NetpbmFormat::GreymapAscii),
+            [b'P', b'3'] => Ok(NetpbmFormat::PixmapAscii),
+            [b'P', b'4'] => Ok(NetpbmFormat::BitmapBinary),
+            [b'P', b'5'] => Ok(NetpbmFormat::GreymapBinary),
+            [b'P', b'6'] => Ok(NetpbmFormat::PixmapBinary),
+            _ => Err(NetpbmError::InvalidMagic(magic)),
+        }
+    }
+
+    pub fn magic(&self) -> &'static str {
+        match self {
+            NetpbmFormat::BitmapAscii => "P1",
+            NetpbmFormat::GreymapAscii => "P2",
+            NetpbmFormat::PixmapAscii => "P3",
+            NetpbmFormat::BitmapBinary => "P4",
+            NetpbmFormat::GreymapBinary => "P5",
+            NetpbmFormat::PixmapBinary => "P6",
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        match self {
+            NetpbmFormat::PixmapAscii | NetpbmFormat::PixmapBinary => 3,
+            _ => 1,
+        }
+    }
+
+    pub fn is_bitmap(&self) -> bool {
+        matches!(self, NetpbmFormat::BitmapAscii | NetpbmFormat::BitmapBinary)
+    }
+
+    pub fn is_binary(&self) -> bool {
+        matches!(self, NetpbmFormat::BitmapBinary | NetpbmFormat::GreymapBinary | NetpbmFormat::PixmapBinary)
+    }
+}
+
+
+pub fn load_netpbm(path:&str) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    parse_netpbm(&bytes)
+}
+
+pub fn save_netpbm(image:&Image, path:&str, format:NetpbmFormat) -> Result<(), ImageError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_netpbm(image, &mut file, format)?;
+    file.flush()?;
+    Ok(())
+}
+
+
+// maxvals up to 255 are rescaled into u8 storage, anything larger into u16
+// bitmaps are inverted on read (in the file 1 is black) so they come out as black and white greys
+pub fn parse_netpbm(bytes:&[u8]) -> Result<Image, ImageError> {
+    let mut reader = NetpbmReader { bytes, position:0 };
+
+    let format = NetpbmFormat::from_magic([reader.next_byte()?, reader.next_byte()?])?;
+
+    let width = reader.header_value()?;
+    let height = reader.header_value()?;
+    if width == 0 || height == 0 {
+        return Err(NetpbmError::InvalidDimensions((width, height)).into());
+    }
+
+    let maxval = match format.is_bitmap() {
+        true => 1,
+        false => match reader.header_value()? {
+            m @ 1..=65535 => m as u16,
+            m => return Err(NetpbmError::InvalidMaxval(m).into()),
+        },
+    };
+
+    let n_samples = match width.checked_mul(height).and_then(|n| n.checked_mul(format.channels())) {
+        Some(n) => n,
+        None => return Err(NetpbmError::InvalidDimensions((width, height)).into()),
+    };
+
+    let samples = match format {
+        NetpbmFormat::BitmapAscii => reader.ascii_bits(n_samples)?,
+        NetpbmFormat::GreymapAscii | NetpbmFormat::PixmapAscii => reader.ascii_samples(n_samples, maxval)?,
+        NetpbmFormat::BitmapBinary => {
+            reader.single_whitespace()?;
+            reader.binary_bits(width, height)?
+        },
+        NetpbmFormat::GreymapBinary | NetpbmFormat::PixmapBinary => {
+            reader.single_whitespace()?;
+            reader.binary_samples(n_samples, maxval)?
+        },
+    };
+
+    let layout = match format.channels() {
+        3 => ChannelLayout::Rgb,
+        _ => ChannelLayout::Grey,
+    };
+    let data = match maxval {
+        255 => ImageData::U8(samples.into_iter().map(|s| s as u8).collect()),
+        65535 => ImageData::U16(samples),
+        m if m < 255 => ImageData::U8(samples.into_iter().map(|s| rescale(s, m, 255) as u8).collect()),
+        m => ImageData::U16(samples.into_iter().map(|s| rescale(s, m, 65535)).collect()),
+    };
+
+    Image::from_data(width, height, layout, data)
+}
+
+fn rescale(sample:u16, from_maxval:u16, to_maxval:u16) -> u16 {
+    ((sample as u32 * to_maxval as u32 + from_maxval as u32 / 2) / from_maxval as u32) as u16
+}
+
+
+// u16 images are written with maxval 65535, everything else with 255
+// rgb is reduced to luma for greymaps, and bitmaps are thresholded at half intensity
+pub fn write_netpbm<W:Write>(image:&Image, writer:&mut W, format:NetpbmFormat) -> Result<(), ImageError> {
+    let maxval = match image.data {
+        ImageData::U16(_) => 65535,
+        _ => 255,
+    };
+
+    let layout = match format.channels() {
+        3 => ChannelLayout::Rgb,
+        _ => ChannelLayout::Grey,
+    };
+    let image = match image.layout == layout {
+        true => image.clone(),
+        false => image.with_layout(layout),
+    };
+
+    let mut netpbm_writer = NetpbmWriter::new(writer, image.width, image.height, format, maxval)?;
+    let mut row = Vec::with_capacity(image.width * layout.channels());
+    for y in 0..image.height {
+        row.clear();
+        for x in 0..image.width {
+            row.extend(image.pixel(x, y));
+        }
+        netpbm_writer.write_row(&row)?;
+    }
+    netpbm_writer.finish()?;
+
+    Ok(())
+}
+
+
+// streams an image to disk a row at a time, so renders never have to be held as text
+// rows are normalised floats, clamped to 0..=1 and rounded to the nearest of maxval levels
+pub struct NetpbmWriter<W:Write> {
+    writer:BufWriter<W>,
+    format:NetpbmFormat,
+    width:usize,
+    height:usize,
+    maxval:u32,
+    rows_written:usize,
+    row_bytes:Vec<u8>,
+}
+
+impl NetpbmWriter<File> {
+    pub fn create(path:&str, width:usize, height:usize, format:NetpbmFormat, maxval:u16) -> Result<NetpbmWriter<File>, ImageError> {
+        NetpbmWriter::new(File::create(path)?, width, height, format, maxval)
+    }
+}
+
+impl<W:Write> NetpbmWriter<W> {
+    // the maxval is ignored for bitmaps, anything above 255 writes two bytes per binary sample
+    pub fn new(writer:W, width:usize, height:usize, format:NetpbmFormat, maxval:u16) -> Result<NetpbmWriter<W>, ImageError> {
+        if width == 0 || height == 0 {
+            return Err(NetpbmError::InvalidDimensions((width, height)).into());
+        }
+        let maxval = match (format.is_bitmap(), maxval) {
+            (true, _) => 1,
+            (false, 0) => return Err(NetpbmError::InvalidMaxval(0).into()),
+            (false, m) => m as u32,
+        };
+
+        let mut writer = BufWriter::new(writer);
+        write!(writer, "{}\n{} {}\n", format.magic(), width, height)?;
+        if !format.is_bitmap() {
+            writeln!(writer, "{}", maxval)?;
+        }
+
+        Ok(NetpbmWriter { writer, format, width, height, maxval, rows_written:0, row_bytes:Vec::new() })
+    }
+
+    pub fn rows_left(&self) -> usize { self.height - self.rows_written }
+
+    pub fn write_row(&mut self, row:&[f32]) -> Result<(), ImageError> {
+        let channels = self.format.channels();
+        if row.len() != self.width * channels {
+            return Err(ImageError::RowLengthError((row.len(), self.width * channels)));
+        }
+        if self.rows_written == self.height {
+            return Err(ImageError::TooManyRows(self.height));
+        }
+
+        let is_bitmap = self.format.is_bitmap();
+        let maxval = self.maxval;
+        let sample = |value:f32| -> u32 {
+            match is_bitmap {
+                true => (value < 0.5) as u32,
+                false => quantise(value, maxval),
+            }
+        };
+
+        self.row_bytes.clear();
+        match self.format {
+            NetpbmFormat::BitmapBinary => {
+                self.row_bytes.resize(self.width.div_ceil(8), 0);
+                for (x, value) in row.iter().enumerate() {
+                    self.row_bytes[x / 8] |= (sample(*value) as u8) << (7 - x % 8);
+                }
+            },
+            NetpbmFormat::GreymapBinary | NetpbmFormat::PixmapBinary => {
+                for value in row {
+                    match maxval > 255 {
+                        true => self.row_bytes.extend_from_slice(&(sample(*value) as u16).to_be_bytes()),
+                        false => self.row_bytes.push(sample(*value) as u8),
+                    }
+                }
+            },
+            // the spec asks ASCII lines to stay under 70 characters, so every pixel gets its own line
+            NetpbmFormat::BitmapAscii | NetpbmFormat::GreymapAscii | NetpbmFormat::PixmapAscii => {
+                for pixel in row.chunks_exact(channels) {
+                    let values : Vec<String> = pixel.iter()
+                        .map(|value| sample(*value).to_string())
+                        .collect();
+                    self.row_bytes.extend_from_slice(values.join(" ").as_bytes());
+                    self.row_bytes.push(b'\n');
+                }
+            },
+        }
+
+        self.writer.write_all(&self.row_bytes)?;
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<W, ImageError> {
+        if self.rows_written != self.height {
+            return Err(ImageError::IncompleteImage((self.rows_written, self.height)));
+        }
+        match self.writer.into_inner() {
+            Ok(writer) => Ok(writer),
+            Err(error) => Err(ImageError::FileError(error.into_error())),
+        }
+    }
+}
+
+
+struct NetpbmReader<'l> {
+    bytes:&'l [u8],
+    position:usize,
+}
+
+impl NetpbmReader<'_> {
+    fn next_byte(&mut self) -> Result<u8, NetpbmError> {
+        match self.bytes.get(self.position) {
+            Some(byte) => {
+                self.position += 1;
+                Ok(*byte)
+            },
+            None => Err(NetpbmError::UnexpectedEof(self.position)),
+        }
+    }
+
+    // whitespace and comments may appear anywhere between header tokens and ASCII samples
+    // a comment runs from '#' to the end of the line (\n or \r, so CRLF files work too)
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(byte) = self.bytes.get(self.position) {
+            match byte {
+                b'#' => {
+                    while let Some(byte) = self.bytes.get(self.position) {
+                        if *byte == b'\n' || *byte == b'\r' { break; }
+                        self.position += 1;
+                    }
+                },
+                b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<&[u8], NetpbmError> {
+        self.skip_whitespace_and_comments();
+        let start = self.position;
+        while let Some(byte) = self.bytes.get(self.position) {
+            if byte.is_ascii_whitespace() || *byte == 0x0b || *byte == b'#' { break; }
+            self.position += 1;
+        }
+        match self.position == start {
+            true => Err(NetpbmError::UnexpectedEof(self.position)),
+            false => Ok(&self.bytes[start..self.position]),
+        }
+    }
+
+    fn unsigned_token(&mut self) -> Result<usize, NetpbmError> {
+        let token = self.token()?;
+        let text = String::from_utf8_lossy(token).into_owned();
+        match token.iter().all(|byte| byte.is_ascii_digit()) {
+            true => text.parse().map_err(|_| NetpbmError::InvalidToken(text)),
+            false => Err(NetpbmError::InvalidToken(text)),
+        }
+    }
+
+    fn header_value(&mut self) -> Result<usize, NetpbmError> {
+        self.unsigned_token()
+    }
+
+    // binary rasters begin after exactly one whitespace byte following the last header value
+    fn single_whitespace(&mut self) -> Result<(), NetpbmError> {
+        let byte = self.next_byte()?;
+        match byte.is_ascii_whitespace() || byte == 0x0b {
+            true => Ok(()),
+            false => Err(NetpbmError::MissingRasterSeparator(byte)),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    // every ascii sample takes at least a byte, so no more are reserved than could be left in the file
+    fn ascii_samples(&mut self, n_samples:usize, maxval:u16) -> Result<Vec<u16>, NetpbmError> {
+        let mut samples = Vec::with_capacity(n_samples.min(self.remaining()));
+        for _ in 0..n_samples {
+            let value = self.unsigned_token()?;
+            match value <= maxval as usize {
+                true => samples.push(value as u16),
+                false => return Err(NetpbmError::SampleOutOfRange((value, maxval))),
+            }
+        }
+        Ok(samples)
+    }
+
+    // P1 bits do not need to be separated, "0 1 1" and "011" are both valid
+    fn ascii_bits(&mut self, n_samples:usize) -> Result<Vec<u16>, NetpbmError> {
+        let mut samples = Vec::with_capacity(n_samples.min(self.remaining()));
+        for _ in 0..n_samples {
+            self.skip_whitespace_and_comments();
+            match self.next_byte()? {
+                b'0' => samples.push(1),
+                b'1' => samples.push(0),
+                byte => return Err(NetpbmError::InvalidToken(String::from_utf8_lossy(&[byte]).into_owned())),
+            }
+        }
+        Ok(samples)
+    }
+
+    fn binary_samples(&mut self, n_samples:usize, maxval:u16) -> Result<Vec<u16>, NetpbmError> {
+        let bytes_per_sample = if maxval > 255 {2} else {1};
+        // a raster too big to count can't be in the file
+        let n_bytes = n_samples.checked_mul(bytes_per_sample).ok_or(NetpbmError::TruncatedRaster((usize::MAX, self.remaining())))?;
+        let raster = self.raster(n_bytes)?;
+
+        let mut samples = Vec::with_capacity(n_samples);
+        for chunk in raster.chunks_exact(bytes_per_sample) {
+            let value = match bytes_per_sample {
+                2 => u16::from_be_bytes([chunk[0], chunk[1]]),
+                _ => chunk[0] as u16,
+            };
+            match value <= maxval {
+                true => samples.push(value),
+                false => return Err(NetpbmError::SampleOutOfRange((value as usize, maxval))),
+            }
+        }
+        Ok(samples)
+    }
+
+    // P4 packs 8 pixels per byte, most significant bit first, and pads every row to a whole byte
+    fn binary_bits(&mut self, width:usize, height:usize) -> Result<Vec<u16>, NetpbmError> {
+        let row_bytes = width.div_ceil(8);
+        let n_bytes = row_bytes.checked_mul(height).ok_or(NetpbmError::InvalidDimensions((width, height)))?;
+        let raster = self.raster(n_bytes)?;
+
+        let mut samples = Vec::with_capacity(width * height);
+        for row in raster.chunks_exact(row_bytes) {
+            for x in 0..width {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                samples.push(1 - bit as u16);
+            }
+        }
+        Ok(samples)
+    }
+
+    fn raster(&mut self, n_bytes:usize) -> Result<&[u8], NetpbmError> {
+        let remaining = self.remaining();
+        match remaining >= n_bytes {
+            true => {
+                let start = self.position;
+                self.position += n_bytes;
+                Ok(&self.bytes[start..self.position])
+            },
+            false => Err(NetpbmError::TruncatedRaster((n_bytes, remaining))),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // headers asking for gigabytes come back as errors rather than allocations
+    #[test]
+    fn huge_headers_are_errors() {
+        for source in ["P2 60000 60000 255\n1 2 3", "P1 60000 60000\n0 1", "P3 60000 60000 255 "] {
+            assert!(matches!(parse_netpbm(source.as_bytes()), Err(ImageError::NetpbmError(NetpbmError::UnexpectedEof(_)))), "{}", source);
+        }
+        let overflowing = format!("P5 {} 3 65535\n", usize::MAX / 4);
+        assert!(matches!(parse_netpbm(overflowing.as_bytes()), Err(ImageError::NetpbmError(NetpbmError::TruncatedRaster(_)))));
+        let wide_bitmap = format!("P4 {} {}\n", usize::MAX - 7, usize::MAX / 2);
+        assert!(matches!(parse_netpbm(wide_bitmap.as_bytes()), Err(ImageError::NetpbmError(NetpbmError::InvalidDimensions(_)))));
+    }
+
+    #[test]
+    fn small_images_still_parse() {
+        let image = parse_netpbm(b"P2 2 1 255\n0 255").expect("a valid greymap");
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.get(1, 0, 0), 1.0);
+    }
+
+    fn u8_samples(image:&Image) -> Vec<u8> {
+        match &image.data {
+            ImageData::U8(data) => data.clone(),
+            other => panic!("expected u8 samples, got {:?}", other),
+        }
+    }
+
+    fn u16_samples(image:&Image) -> Vec<u16> {
+        match &image.data {
+            ImageData::U16(data) => data.clone(),
+            other => panic!("expected u16 samples, got {:?}", other),
+        }
+    }
+
+    // in bitmaps 1 is black, so they come back inverted
+    #[test]
+    fn ascii_formats_decode() {
+        let bitmap = parse_netpbm(b"P1\n3 2\n0 1 0\n101").expect("a valid bitmap");
+        assert_eq!((bitmap.width, bitmap.height, bitmap.layout), (3, 2, ChannelLayout::Grey));
+        assert_eq!(u8_samples(&bitmap), vec![255, 0, 255, 0, 255, 0]);
+        let greymap = parse_netpbm(b"P2\n2 2\n255\n0 64\n128 255\n").expect("a valid greymap");
+        assert_eq!(u8_samples(&greymap), vec![0, 64, 128, 255]);
+        let pixmap = parse_netpbm(b"P3\n2 1\n255\n255 0 0\n0 0 255\n").expect("a valid pixmap");
+        assert_eq!((pixmap.layout, u8_samples(&pixmap)), (ChannelLayout::Rgb, vec![255, 0, 0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn binary_formats_decode() {
+        let bitmap = parse_netpbm(b"P4\n3 2\n\xa0\x40").expect("a valid bitmap");
+        assert_eq!(u8_samples(&bitmap), vec![0, 255, 0, 255, 0, 255]);
+        let greymap = parse_netpbm(b"P5\n2 2\n255\n\x00\x40\x80\xff").expect("a valid greymap");
+        assert_eq!(u8_samples(&greymap), vec![0, 64, 128, 255]);
+        let pixmap = parse_netpbm(b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff").expect("a valid pixmap");
+        assert_eq!((pixmap.layout, u8_samples(&pixmap)), (ChannelLayout::Rgb, vec![255, 0, 0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn comments_may_come_between_header_tokens() {
+        let image = parse_netpbm(b"P2 # a greymap\n2# width\n#height next\n1 # \n255\n# samples\n10 20").expect("a commented greymap");
+        assert_eq!((image.width, image.height, u8_samples(&image)), (2, 1, vec![10, 20]));
+        let binary = parse_netpbm(b"P5\n# made by hand\n2 1\n255\n\x0a\x14").expect("a commented binary greymap");
+        assert_eq!(u8_samples(&binary), vec![10, 20]);
+    }
+
+    #[test]
+    fn samples_may_share_lines_and_end_in_crlf() {
+        let lf = parse_netpbm(b"P3\n2 2\n255\n1 2 3 4 5 6 7 8 9\n10 11 12\n").expect("an lf pixmap");
+        let crlf = parse_netpbm(b"P3\r\n2 2\r\n255\r\n1 2 3 4 5 6 7 8 9\r\n10 11 12\r\n").expect("a crlf pixmap");
+        let one_line = parse_netpbm(b"P3 2 2 255 1 2 3 4 5 6 7 8 9 10 11 12").expect("a one line pixmap");
+        assert_eq!(u8_samples(&lf), (1..=12).collect::<Vec<u8>>());
+        assert_eq!(lf, crlf);
+        assert_eq!(lf, one_line);
+    }
+
+    #[test]
+    fn maxvals_below_255_are_rescaled() {
+        let image = parse_netpbm(b"P2\n4 1\n15\n0 7 8 15\n").expect("a 4 bit greymap");
+        assert_eq!(u8_samples(&image), vec![0, 119, 136, 255]);
+        let binary = parse_netpbm(b"P5\n2 1\n1\n\x00\x01").expect("a 1 bit greymap");
+        assert_eq!(u8_samples(&binary), vec![0, 255]);
+        assert!(matches!(parse_netpbm(b"P2\n1 1\n15\n16\n"), Err(ImageError::NetpbmError(NetpbmError::SampleOutOfRange((16, 15))))));
+    }
+
+    // samples of maxvals above 255 are two bytes, most significant first
+    #[test]
+    fn sixteen_bit_maxvals_are_read() {
+        let full = parse_netpbm(b"P5\n2 1\n65535\n\x12\x34\xff\xff").expect("a 16 bit greymap");
+        assert_eq!(u16_samples(&full), vec![0x1234, 65535]);
+        let ascii = parse_netpbm(b"P2\n2 1\n65535\n4660 65535\n").expect("a 16 bit ascii greymap");
+        assert_eq!(ascii, full);
+        let partial = parse_netpbm(b"P2\n3 1\n1000\n0 500 1000\n").expect("a 10 bit greymap");
+        assert_eq!(u16_samples(&partial), vec![0, 32768, 65535]);
+        assert!(matches!(parse_netpbm(b"P2\n1 1\n65536\n0\n"), Err(ImageError::NetpbmError(NetpbmError::InvalidMaxval(65536)))));
+    }
+
+    // 10 pixels take two bytes a row, and the 6 bits of padding are ignored whatever they hold
+    #[test]
+    fn bitmap_rows_are_padded_to_a_byte() {
+        let image = parse_netpbm(b"P4\n10 2\n\x80\x40\x00\x3f").expect("a padded bitmap");
+        let mut expected = vec![255; 20];
+        expected[0] = 0;
+        expected[9] = 0;
+        assert_eq!(u8_samples(&image), expected);
+        assert!(matches!(parse_netpbm(b"P4\n10 2\n\x80\x40\x00"), Err(ImageError::NetpbmError(NetpbmError::TruncatedRaster((4, 3))))));
+    }
+
+    fn written(image:&Image, format:NetpbmFormat) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_netpbm(image, &mut bytes, format).expect("the image writes");
+        bytes
+    }
+
+    fn gradient(width:usize, height:usize, layout:ChannelLayout, empty:fn(usize, usize, ChannelLayout) -> Image) -> Image {
+        let mut image = empty(width, height, layout);
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..layout.channels() {
+                    image.set(x, y, c, ((x + 3*y + 5*c) % 11) as f32 / 10.0);
+                }
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn written_images_parse_back_the_same() {
+        let rgb = gradient(5, 3, ChannelLayout::Rgb, Image::new_u8);
+        assert_eq!(parse_netpbm(&written(&rgb, NetpbmFormat::PixmapBinary)).expect("the P6 parses"), rgb);
+        assert_eq!(parse_netpbm(&written(&rgb, NetpbmFormat::PixmapAscii)).expect("the P3 parses"), rgb);
+        let grey = gradient(5, 3, ChannelLayout::Grey, Image::new_u8);
+        assert_eq!(parse_netpbm(&written(&grey, NetpbmFormat::GreymapBinary)).expect("the P5 parses"), grey);
+        let deep = gradient(5, 3, ChannelLayout::Rgb, Image::new_u16);
+        let bytes = written(&deep, NetpbmFormat::PixmapBinary);
+        assert!(bytes.starts_with(b"P6\n5 3\n65535\n"));
+        assert_eq!(parse_netpbm(&bytes).expect("the 16 bit P6 parses"), deep);
+    }
+
+    // samples are rounded to the nearest of 256 levels and clamped, never scaled by 256 or written as fractions
+    #[test]
+    fn samples_are_quantised_to_maxval() {
+        let mut image = Image::new_f32(5, 1, ChannelLayout::Grey);
+        for (x, value) in [1.0, 2.5, 0.5, -1.0, 0.25].into_iter().enumerate() {
+            image.set(x, 0, 0, value);
+        }
+        assert_eq!(written(&image, NetpbmFormat::GreymapBinary), b"P5\n5 1\n255\n\xff\xff\x80\x00\x40");
+        assert_eq!(written(&image, NetpbmFormat::GreymapAscii), b"P2\n5 1\n255\n255\n255\n128\n0\n64\n");
+    }
+
+    #[test]
+    fn streamed_rows_match_the_whole_image() {
+        let image = gradient(7, 4, ChannelLayout::Rgb, Image::new_f32);
+        for format in [NetpbmFormat::PixmapBinary, NetpbmFormat::PixmapAscii, NetpbmFormat::GreymapBinary, NetpbmFormat::BitmapBinary] {
+            let layout = if format.channels() == 3 {ChannelLayout::Rgb} else {ChannelLayout::Grey};
+            let converted = image.with_layout(layout);
+            let mut writer = NetpbmWriter::new(vec![], image.width, image.height, format, 255).expect("the header writes");
+            for y in 0..image.height {
+                let row : Vec<f32> = (0..image.width).flat_map(|x| converted.pixel(x, y)).collect();
+                writer.write_row(&row).expect("the row writes");
+            }
+            assert_eq!(writer.rows_left(), 0);
+            assert_eq!(writer.finish().expect("every row was written"), written(&image, format), "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::errors::{HdrError, ImageError};
+use crate::image::{ChannelLayout, Image, ImageData};
+
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+
+pub fn load_pfm(path:&str) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    parse_pfm(&bytes)
+}
+
+pub fn save_pfm(image:&Image, path:&str) -> Result<(), ImageError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_pfm(image, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+
+pub fn is_pfm(bytes:&[u8]) -> bool {
+    bytes.starts_with(b"PF") || bytes.starts_with(b"Pf")
+}
+
+
+// PF is rgb and Pf is grey, a negative scale means little endian samples
+// rows are stored bottom up in the file and flipped to top down here, the scale is not applied
+pub fn parse_pfm(bytes:&[u8]) -> Result<Image, ImageError> {
+    let mut position = 2;
+    let layout = match bytes.get(0..2) {
+        Some(b"PF") => ChannelLayout::Rgb,
+        Some(b"Pf") => ChannelLayout::Grey,
+        _ => return Err(HdrError::InvalidHeader(String::from_utf8_lossy(&bytes[..bytes.len().min(2)]).into_owned()).into()),
+    };
+
+    let token = |position:&mut usize| -> Result<String, ImageError> {
+        while *position < bytes.len() && bytes[*position].is_ascii_whitespace() { *position += 1; }
+        let start = *position;
+        while *position < bytes.len() && !bytes[*position].is_ascii_whitespace() { *position += 1; }
+        match *position == start {
+            true => Err(HdrError::TruncatedData((*position, bytes.len())).into()),
+            false => Ok(String::from_utf8_lossy(&bytes[start..*position]).into_owned()),
+        }
+    };
+
+    let width_token = token(&mut position)?;
+    let height_token = token(&mut position)?;
+    let (width, height) : (usize, usize) = match (width_token.parse(), height_token.parse()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+        _ => return Err(HdrError::InvalidHeader(format!("{} {}", width_token, height_token)).into()),
+    };
+    let scale_token = token(&mut position)?;
+    let little_endian = match scale_token.parse::<f32>() {
+        Ok(scale) if scale < 0.0 => true,
+        Ok(scale) if scale > 0.0 => false,
+        _ => return Err(HdrError::InvalidScale(scale_token).into()),
+    };
+    // exactly one whitespace byte separates the header from the raster
+    position += 1;
+
+    let channels = layout.channels();
+    let n_bytes = match width.checked_mul(height).and_then(|pixels| pixels.checked_mul(channels * 4)) {
+        Some(n_bytes) => n_bytes,
+        None => return Err(HdrError::InvalidHeader(format!("{} {}", width, height)).into()),
+    };
+    if bytes.len().saturating_sub(position) < n_bytes {
+        return Err(HdrError::TruncatedData((bytes.len().saturating_sub(position), n_bytes)).into());
+    }
+    let raster = &bytes[position..position + n_bytes];
+
+    let row_len = width * channels;
+    let mut data = vec![0.0f32; width * height * channels];
+    for (file_row, row) in raster.chunks_exact(row_len * 4).enumerate() {
+        let y = height - 1 - file_row;
+        for (i, sample) in row.chunks_exact(4).enumerate() {
+            let sample = [sample[0], sample[1], sample[2], sample[3]];
+            data[y*row_len + i] = match little_endian {
+                true => f32::from_le_bytes(sample),
+                false => f32::from_be_bytes(sample),
+            };
+        }
+    }
+
+    Image::from_data(width, height, layout, ImageData::F32(data))
+}
+
+
+// greys are written as Pf, everything else as PF with alpha dropped, always little endian
+pub fn write_pfm<W:Write>(image:&Image, writer:&mut W) -> Result<(), ImageError> {
+    let (magic, layout) = match image.layout {
+        ChannelLayout::Grey | ChannelLayout::GreyAlpha => ("Pf", ChannelLayout::Grey),
+        ChannelLayout::Rgb | ChannelLayout::Rgba => ("PF", ChannelLayout::Rgb),
+    };
+    write!(writer, "{}\n{} {}\n-1.0\n", magic, image.width, image.height)?;
+
+    let mut row = Vec::with_capacity(image.width * layout.channels() * 4);
+    for y in (0..image.height).rev() {
+        row.clear();
+        for x in 0..image.width {
+            for c in 0..layout.channels() {
+                row.extend_from_slice(&image.get(x, y, c).to_le_bytes());
+            }
+        }
+        writer.write_all(&row)?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_headers_are_errors() {
+        let overflowing = format!("PF\n{} 3\n-1.0\n", usize::MAX / 4);
+        assert!(matches!(parse_pfm(overflowing.as_bytes()), Err(ImageError::HdrError(HdrError::InvalidHeader(_)))));
+        assert!(matches!(parse_pfm(b"Pf\n60000 60000\n-1.0\n\0\0\0\0"), Err(ImageError::HdrError(HdrError::TruncatedData(_)))));
+    }
+
+    #[test]
+    fn small_images_still_parse() {
+        let mut bytes = b"Pf\n2 1\n-1.0\n".to_vec();
+        bytes.extend(0.25f32.to_le_bytes());
+        bytes.extend(2.0f32.to_le_bytes());
+        let image = parse_pfm(&bytes).expect("a valid greymap");
+        assert_eq!(image.get(1, 0, 0), 2.0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,159 @@
+use crate::errors::ImageError;
+use crate::image::{ChannelLayout, Image, ImageData};
+
+use ::png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+
+pub const PNG_SIGNATURE : [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+
+pub fn load_png(path:&str) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    decode_png(&bytes)
+}
+
+pub fn save_png(image:&Image, path:&str) -> Result<(), ImageError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_png(image, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+
+// palettes and sub-byte depths are expanded to 8 bits, 16 bit files stay 16 bit
+pub fn decode_png(bytes:&[u8]) -> Result<Image, ImageError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer)?;
+    buffer.truncate(frame.buffer_size());
+
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let layout = match frame.color_type {
+        ColorType::Grayscale => ChannelLayout::Grey,
+        ColorType::GrayscaleAlpha => ChannelLayout::GreyAlpha,
+        ColorType::Rgb => ChannelLayout::Rgb,
+        ColorType::Rgba => ChannelLayout::Rgba,
+        ColorType::Indexed => return Err(ImageError::UnsupportedPngColourType(ColorType::Indexed)),
+    };
+
+    // rows are tightly packed once expanded, but the line size says so explicitly
+    let row_bytes = width * layout.channels() * if frame.bit_depth == BitDepth::Sixteen {2} else {1};
+    if frame.line_size != row_bytes {
+        return Err(ImageError::DataLengthError(frame.line_size));
+    }
+
+    let data = match frame.bit_depth {
+        BitDepth::Sixteen => ImageData::U16(
+            buffer.chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect()
+        ),
+        BitDepth::Eight => ImageData::U8(buffer),
+        depth => return Err(ImageError::UnsupportedPngBitDepth(depth)),
+    };
+
+    Image::from_data(width, height, layout, data)
+}
+
+
+// u16 images are written as 16 bit, u8 and f32 images as 8 bit
+pub fn write_png<W:Write>(image:&Image, writer:W) -> Result<(), ImageError> {
+    let mut encoder = Encoder::new(writer, image.width as u32, image.height as u32);
+    encoder.set_color(match image.layout {
+        ChannelLayout::Grey => ColorType::Grayscale,
+        ChannelLayout::GreyAlpha => ColorType::GrayscaleAlpha,
+        ChannelLayout::Rgb => ColorType::Rgb,
+        ChannelLayout::Rgba => ColorType::Rgba,
+    });
+
+    let data = match &image.data {
+        ImageData::U8(data) => {
+            encoder.set_depth(BitDepth::Eight);
+            data.clone()
+        },
+        ImageData::U16(data) => {
+            encoder.set_depth(BitDepth::Sixteen);
+            data.iter().flat_map(|sample| sample.to_be_bytes()).collect()
+        },
+        ImageData::F32(_) => {
+            encoder.set_depth(BitDepth::Eight);
+            match image.to_u8().data {
+                ImageData::U8(data) => data,
+                _ => unreachable!(),
+            }
+        },
+    };
+
+    let mut png_writer = encoder.write_header()?;
+    png_writer.write_image_data(&data)?;
+    png_writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::{detect_format, load_image, ImageFormat};
+
+    const LAYOUTS : [ChannelLayout; 4] = [ChannelLayout::Grey, ChannelLayout::GreyAlpha, ChannelLayout::Rgb, ChannelLayout::Rgba];
+
+    // a different value in every sample, with alpha running from transparent to opaque
+    fn pattern(layout:ChannelLayout, empty:fn(usize, usize, ChannelLayout) -> Image) -> Image {
+        let mut image = empty(4, 3, layout);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                for c in 0..layout.channels() {
+                    image.set(x, y, c, ((x + 4*y) * layout.channels() + c) as f32 / 47.0);
+                }
+            }
+        }
+        image
+    }
+
+    fn round_trip(image:&Image) -> Image {
+        let mut bytes = vec![];
+        write_png(image, &mut bytes).expect("the png writes");
+        decode_png(&bytes).expect("the png decodes")
+    }
+
+
+    #[test]
+    fn eight_and_sixteen_bit_images_round_trip() {
+        for layout in LAYOUTS {
+            for empty in [Image::new_u8, Image::new_u16] {
+                let image = pattern(layout, empty);
+                let decoded = round_trip(&image);
+                assert_eq!(decoded, image, "{:?} at {} bits", layout, image.bit_depth());
+                if layout.has_alpha() {
+                    assert_eq!(decoded.rgba(0, 0)[3], image.rgba(0, 0)[3]);
+                    assert_eq!(decoded.rgba(3, 2)[3], image.rgba(3, 2)[3]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn float_images_are_written_as_eight_bit() {
+        let image = pattern(ChannelLayout::Rgba, Image::new_f32);
+        assert_eq!(round_trip(&image), image.to_u8());
+    }
+
+    // a png named like a netpbm file still decodes as a png
+    #[test]
+    fn pngs_are_found_by_signature() {
+        let image = pattern(ChannelLayout::Rgb, Image::new_u8);
+        let mut bytes = vec![];
+        write_png(&image, &mut bytes).expect("the png writes");
+        assert_eq!(detect_format(&bytes).expect("the signature is known"), ImageFormat::Png);
+        let path = std::env::temp_dir().join("images_png_signature.ppm");
+        std::fs::write(&path, &bytes).expect("the file writes");
+        assert_eq!(load_image(&path.to_string_lossy()).expect("the png loads"), image);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::errors::{HdrError, ImageError};
+use crate::image::{ChannelLayout, Image, ImageData};
+
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+
+pub fn load_radiance(path:&str) -> Result<Image, ImageError> {
+    let bytes = fs::read(path)?;
+    parse_radiance(&bytes)
+}
+
+pub fn save_radiance(image:&Image, path:&str) -> Result<(), ImageError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write_radiance(image, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+
+// the most pixels a byte of scanline data can decode to, a little over new style run lengths' 127 in 8 bytes
+// old style repeats can pack more, but only pathological files do
+const MAX_PIXELS_PER_BYTE : usize = 16;
+
+
+pub fn is_radiance(bytes:&[u8]) -> bool {
+    bytes.starts_with(b"#?")
+}
+
+
+// reads 32-bit_rle_rgbe files into linear rgb f32, flat, old style and new style run lengths are all accepted
+// only the standard -Y h +X w (top down) and +Y h +X w (bottom up) orientations are supported
+pub fn parse_radiance(bytes:&[u8]) -> Result<Image, ImageError> {
+    let mut position = 0;
+    let next_line = |position:&mut usize| -> Result<String, ImageError> {
+        match bytes[*position..].iter().position(|byte| *byte == b'\n') {
+            Some(end) => {
+                let line = String::from_utf8_lossy(&bytes[*position..*position+end]).trim_end_matches('\r').to_owned();
+                *position += end + 1;
+                Ok(line)
+            },
+            None => Err(HdrError::TruncatedData((*position, bytes.len())).into()),
+        }
+    };
+
+    let magic = next_line(&mut position)?;
+    if !magic.starts_with("#?") {
+        return Err(HdrError::InvalidHeader(magic).into());
+    }
+
+    // header variables run until the first empty line, only FORMAT matters here
+    loop {
+        let line = next_line(&mut position)?;
+        if line.is_empty() { break; }
+        if let Some(format) = line.strip_prefix("FORMAT=") && format.trim() != "32-bit_rle_rgbe" {
+            return Err(HdrError::UnsupportedFormat(format.trim().to_owned()).into());
+        }
+    }
+
+    let resolution = next_line(&mut position)?;
+    let tokens : Vec<&str> = resolution.split_whitespace().collect();
+    let (flip, height, width) = match tokens.as_slice() {
+        ["-Y", h, "+X", w] => (false, h, w),
+        ["+Y", h, "+X", w] => (true, h, w),
+        _ => return Err(HdrError::UnsupportedOrientation(resolution).into()),
+    };
+    let (width, height) : (usize, usize) = match (width.parse(), height.parse()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => (w, h),
+        _ => return Err(HdrError::UnsupportedOrientation(resolution).into()),
+    };
+
+    // every scanline takes at least 4 bytes and run lengths pack at most 127 pixels into 8,
+    // so a header asking for more than the file could hold is refused before allocating for it
+    let remaining = bytes.len() - position;
+    let n_samples = match width.checked_mul(height) {
+        Some(pixels) if pixels <= remaining.saturating_mul(MAX_PIXELS_PER_BYTE) && height <= remaining / 4 => pixels * 3,
+        _ => return Err(HdrError::InvalidHeader(resolution).into()),
+    };
+    let mut data = vec![0.0f32; n_samples];
+    let mut scanline = vec![[0u8; 4]; width];
+    for row in 0..height {
+        position = read_scanline(bytes, position, &mut scanline, row)?;
+        let y = if flip {height - 1 - row} else {row};
+        for (x, rgbe) in scanline.iter().enumerate() {
+            let i = (y*width + x)*3;
+            data[i..i+3].copy_from_slice(&rgbe_to_rgb(*rgbe));
+        }
+    }
+
+    Image::from_data(width, height, ChannelLayout::Rgb, ImageData::F32(data))
+}
+
+fn read_scanline(bytes:&[u8], mut position:usize, scanline:&mut [[u8; 4]], row:usize) -> Result<usize, ImageError> {
+    let width = scanline.len();
+    let byte_at = |position:usize| -> Result<u8, ImageError> {
+        match bytes.get(position) {
+            Some(byte) => Ok(*byte),
+            None => Err(HdrError::TruncatedData((position, bytes.len())).into()),
+        }
+    };
+
+    // new style run lengths: 2 2 hi lo, then each channel encoded separately
+    let new_style = (8..=0x7fff).contains(&width)
+        && byte_at(position)? == 2 && byte_at(position + 1)? == 2
+        && byte_at(position + 2)? & 0x80 == 0;
+    if new_style {
+        let encoded_width = ((byte_at(position + 2)? as usize) << 8) | byte_at(position + 3)? as usize;
+        if encoded_width != width {
+            return Err(HdrError::InvalidScanline(row).into());
+        }
+        position += 4;
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = byte_at(position)? as usize;
+                position += 1;
+                match count > 128 {
+                    true => {
+                        let run = count - 128;
+                        if x + run > width { return Err(HdrError::InvalidScanline(row).into()); }
+                        let value = byte_at(position)?;
+                        position += 1;
+                        for pixel in &mut scanline[x..x+run] { pixel[channel] = value; }
+                        x += run;
+                    },
+                    false => {
+                        if count == 0 || x + count > width { return Err(HdrError::InvalidScanline(row).into()); }
+                        for pixel in &mut scanline[x..x+count] {
+                            pixel[channel] = byte_at(position)?;
+                            position += 1;
+                        }
+                        x += count;
+                    },
+                }
+            }
+        }
+        return Ok(position);
+    }
+
+    // flat pixels, where 1 1 1 n repeats the previous pixel (n shifted by 8 for each consecutive repeat)
+    let mut x = 0;
+    let mut shift = 0;
+    while x < width {
+        let rgbe = [byte_at(position)?, byte_at(position + 1)?, byte_at(position + 2)?, byte_at(position + 3)?];
+        position += 4;
+        match rgbe[..3] == [1, 1, 1] {
+            true => {
+                if x == 0 { return Err(HdrError::InvalidScanline(row).into()); }
+                let run = (rgbe[3] as usize) << shift;
+                if x + run > width { return Err(HdrError::InvalidScanline(row).into()); }
+                let previous = scanline[x - 1];
+                for pixel in &mut scanline[x..x+run] { *pixel = previous; }
+                x += run;
+                shift += 8;
+            },
+            false => {
+                scanline[x] = rgbe;
+                x += 1;
+                shift = 0;
+            },
+        }
+    }
+    Ok(position)
+}
+
+
+// non f32 images are written from their normalised values
+pub fn write_radiance<W:Write>(image:&Image, writer:&mut W) -> Result<(), ImageError> {
+    write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", image.height, image.width)?;
+
+    let mut scanline = Vec::with_capacity(image.width);
+    let mut encoded = Vec::with_capacity(image.width * 4);
+    for y in 0..image.height {
+        scanline.clear();
+        scanline.extend((0..image.width).map(|x| rgb_to_rgbe(image.rgb(x, y))));
+
+        encoded.clear();
+        match (8..=0x7fff).contains(&image.width) {
+            true => encode_scanline(&scanline, &mut encoded),
+            false => encoded.extend(scanline.iter().flatten()),
+        }
+        writer.write_all(&encoded)?;
+    }
+    Ok(())
+}
+
+// new style run lengths, runs of 4 or more identical bytes are packed, everything else is copied literally
+fn encode_scanline(scanline:&[[u8; 4]], encoded:&mut Vec<u8>) {
+    let width = scanline.len();
+    encoded.extend([2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+
+    for channel in 0..4 {
+        let values : Vec<u8> = scanline.iter().map(|pixel| pixel[channel]).collect();
+        let mut x = 0;
+        while x < width {
+            let mut run = 1;
+            while x + run < width && run < 127 && values[x + run] == values[x] { run += 1; }
+            if run >= 4 {
+                encoded.extend([128 + run as u8, values[x]]);
+                x += run;
+                continue;
+            }
+
+            // literal block until the next run of 4 starts
+            let start = x;
+            while x < width && x - start < 128 {
+                let mut ahead = 1;
+                while x + ahead < width && ahead < 4 && values[x + ahead] == values[x] { ahead += 1; }
+                if ahead >= 4 { break; }
+                x += 1;
+            }
+            encoded.push((x - start) as u8);
+            encoded.extend_from_slice(&values[start..x]);
+        }
+    }
+}
+
+
+pub fn rgbe_to_rgb(rgbe:[u8; 4]) -> [f32; 3] {
+    match rgbe[3] {
+        0 => [0.0; 3],
+        e => {
+            let scale = f32::powi(2.0, e as i32 - (128 + 8));
+            [0, 1, 2].map(|c| (rgbe[c] as f32 + 0.5) * scale)
+        },
+    }
+}
+
+pub fn rgb_to_rgbe(rgb:[f32; 3]) -> [u8; 4] {
+    let rgb = rgb.map(|c| if c.is_finite() {c.max(0.0)} else {0.0});
+    let largest = rgb[0].max(rgb[1]).max(rgb[2]);
+    if largest < 1e-32 {
+        return [0; 4];
+    }
+    // largest = mantissa * 2^exponent with the mantissa in [0.5, 1)
+    let mut exponent = largest.log2().floor() as i32 + 1;
+    if largest / f32::powi(2.0, exponent) >= 1.0 { exponent += 1; }
+    if largest / f32::powi(2.0, exponent) < 0.5 { exponent -= 1; }
+    let scale = 256.0 / f32::powi(2.0, exponent);
+    let [r, g, b] = rgb.map(|c| (c * scale).min(255.0) as u8);
+    [r, g, b, (exponent + 128).clamp(0, 255) as u8]
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(resolution:&str) -> Vec<u8> {
+        format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{}\n", resolution).into_bytes()
+    }
+
+    // a few bytes claiming a huge image are refused rather than allocated for
+    #[test]
+    fn huge_headers_are_errors() {
+        for resolution in ["-Y 60000 +X 60000".to_owned(), format!("-Y {} +X {}", usize::MAX / 2, 3)] {
+            let mut bytes = header(&resolution);
+            bytes.extend([0u8; 64]);
+            assert!(matches!(parse_radiance(&bytes), Err(ImageError::HdrError(HdrError::InvalidHeader(_)))), "{}", resolution);
+        }
+    }
+
+    #[test]
+    fn run_length_images_still_parse() {
+        let image = Image::from_data(300, 2, ChannelLayout::Rgb, ImageData::F32(vec![0.5; 300 * 2 * 3])).expect("an image");
+        let mut bytes = vec![];
+        write_radiance(&image, &mut bytes).expect("an encoded image");
+        let decoded = parse_radiance(&bytes).expect("a decoded image");
+        assert_eq!((decoded.width, decoded.height), (300, 2));
+        assert!((decoded.get(299, 1, 2) - 0.5).abs() < 0.01);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,203 @@
+use crate::image::{ChannelLayout, Image};
+
+
+// each operator has a twin in opengl_abstractions/shaders_glsl/tone_mapped_quad_fragment.glsl
+// keep the two in step so the live preview matches what gets written to disk
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ToneMapper {
+    Exposure,
+    Reinhard,
+    ReinhardExtended { white_point:f32 },
+    Aces,
+    Hable,
+}
+
+impl ToneMapper {
+    // the operator uniform of the shader
+    pub fn shader_index(&self) -> f32 {
+        match self {
+            ToneMapper::Exposure => 0.0,
+            ToneMapper::Reinhard => 1.0,
+            ToneMapper::ReinhardExtended { .. } => 2.0,
+            ToneMapper::Aces => 3.0,
+            ToneMapper::Hable => 4.0,
+        }
+    }
+
+    pub fn white_point(&self) -> f32 {
+        match self {
+            ToneMapper::ReinhardExtended { white_point } => *white_point,
+            _ => 1.0,
+        }
+    }
+
+    pub fn next(&self) -> ToneMapper {
+        match self {
+            ToneMapper::Exposure => ToneMapper::Reinhard,
+            ToneMapper::Reinhard => ToneMapper::ReinhardExtended { white_point:4.0 },
+            ToneMapper::ReinhardExtended { .. } => ToneMapper::Aces,
+            ToneMapper::Aces => ToneMapper::Hable,
+            ToneMapper::Hable => ToneMapper::Exposure,
+        }
+    }
+
+    pub fn map(&self, x:f32) -> f32 {
+        match self {
+            ToneMapper::Exposure => x,
+            ToneMapper::Reinhard => x / (1.0 + x),
+            ToneMapper::ReinhardExtended { white_point } => x * (1.0 + x / (white_point*white_point)) / (1.0 + x),
+            // Narkowicz's fit of the ACES reference rendering transform
+            ToneMapper::Aces => (x * (2.51*x + 0.03)) / (x * (2.43*x + 0.59) + 0.14),
+            // Uncharted 2 filmic curve with its usual exposure bias of 2 and linear white of 11.2
+            ToneMapper::Hable => hable_partial(2.0*x) / hable_partial(11.2),
+        }
+    }
+}
+
+fn hable_partial(x:f32) -> f32 {
+    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+    ((x*(a*x + c*b) + d*e) / (x*(a*x + b) + d*f)) - e/f
+}
+
+
+// exposure is in stops, applied before the operator, gamma is applied after it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ToneMapSettings {
+    pub operator:ToneMapper,
+    pub exposure:f32,
+    pub gamma:f32,
+}
+
+impl ToneMapSettings {
+    pub fn new() -> ToneMapSettings {
+        ToneMapSettings {
+            operator:ToneMapper::Aces,
+            exposure:0.0,
+            gamma:2.2,
+        }
+    }
+
+    pub fn map_channel(&self, value:f32) -> f32 {
+        let exposed = value.max(0.0) * f32::powf(2.0, self.exposure);
+        let mapped = self.operator.map(exposed).clamp(0.0, 1.0);
+        mapped.powf(1.0 / self.gamma)
+    }
+
+    pub fn map_rgb(&self, rgb:[f32; 3]) -> [f32; 3] {
+        rgb.map(|c| self.map_channel(c))
+    }
+}
+
+impl Default for ToneMapSettings {
+    fn default() -> ToneMapSettings {
+        ToneMapSettings::new()
+    }
+}
+
+
+// maps linear radiance to a displayable u8 image, alpha is carried across untouched
+pub fn tone_map(image:&Image, settings:&ToneMapSettings) -> Image {
+    let layout = match image.layout.has_alpha() {
+        true => ChannelLayout::Rgba,
+        false => ChannelLayout::Rgb,
+    };
+    let mut mapped = Image::new_u8(image.width, image.height, layout);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [r, g, b, a] = image.rgba(x, y);
+            let [r, g, b] = settings.map_rgb([r, g, b]);
+            mapped.set_rgba(x, y, [r, g, b, a]);
+        }
+    }
+    mapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS : [ToneMapper; 5] = [
+        ToneMapper::Exposure,
+        ToneMapper::Reinhard,
+        ToneMapper::ReinhardExtended { white_point:4.0 },
+        ToneMapper::Aces,
+        ToneMapper::Hable,
+    ];
+
+    fn linear(operator:ToneMapper, exposure:f32) -> ToneMapSettings {
+        ToneMapSettings { operator, exposure, gamma:1.0 }
+    }
+
+
+    #[test]
+    fn operators_are_monotonic_from_zero_and_stay_in_range() {
+        for operator in OPERATORS {
+            for gamma in [1.0, 2.2] {
+                let settings = ToneMapSettings { operator, exposure:0.0, gamma };
+                assert!(settings.map_channel(0.0) < 1e-3, "{:?} maps 0 to {}", operator, settings.map_channel(0.0));
+                assert_eq!(settings.map_channel(-1.0), settings.map_channel(0.0), "negative radiance is black");
+                let mut previous = settings.map_channel(0.0);
+                for i in 1..=2000 {
+                    let mapped = settings.map_channel(i as f32 * 0.05);
+                    assert!((0.0..=1.0).contains(&mapped), "{:?} maps {} to {}", operator, i as f32 * 0.05, mapped);
+                    assert!(mapped >= previous, "{:?} falls at {}", operator, i as f32 * 0.05);
+                    previous = mapped;
+                }
+            }
+        }
+    }
+
+    // worked through the formulas in the shader, so drift on either side shows up here
+    #[test]
+    fn operators_match_the_shader() {
+        let inputs = [0.0, 0.18, 0.5, 1.0, 4.0, 16.0];
+        let expected = [
+            [0.0, 0.18, 0.5, 1.0, 1.0, 1.0],
+            [0.0, 0.15254, 0.33333, 0.5, 0.8, 0.94118],
+            [0.0, 0.15426, 0.34375, 0.53125, 1.0, 1.0],
+            [0.0, 0.2669, 0.61631, 0.8038, 0.97342, 1.0],
+            [0.0, 0.12834, 0.3043, 0.49292, 0.91803, 1.0],
+        ];
+        for (operator, expected) in OPERATORS.iter().zip(expected) {
+            for (input, expected) in inputs.iter().zip(expected) {
+                let mapped = linear(*operator, 0.0).map_channel(*input);
+                assert!((mapped - expected).abs() < 1e-4, "{:?} maps {} to {}, expected {}", operator, input, mapped, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn every_stop_doubles_the_input() {
+        for stops in [-2.0, -1.0, 1.0, 3.0] {
+            let scale = f32::powf(2.0, stops);
+            assert!((linear(ToneMapper::Exposure, stops).map_channel(0.1) - 0.1 * scale).abs() < 1e-6);
+            for operator in OPERATORS {
+                let (exposed, scaled) = (linear(operator, stops).map_channel(0.1), linear(operator, 0.0).map_channel(0.1 * scale));
+                assert!((exposed - scaled).abs() < 1e-6, "{:?} at {} stops gives {} not {}", operator, stops, exposed, scaled);
+            }
+        }
+    }
+
+    #[test]
+    fn the_white_point_maps_to_one() {
+        for white_point in [1.0, 2.5, 4.0, 11.2] {
+            let operator = ToneMapper::ReinhardExtended { white_point };
+            assert!((operator.map(white_point) - 1.0).abs() < 1e-5, "white point {} maps to {}", white_point, operator.map(white_point));
+            assert!(operator.map(0.5 * white_point) < 1.0);
+        }
+    }
+
+    #[test]
+    fn tone_mapped_images_keep_their_alpha() {
+        let mut image = Image::new_f32(2, 1, ChannelLayout::Rgba);
+        image.set_rgba(0, 0, [0.0, 0.5, 8.0, 0.25]);
+        image.set_rgba(1, 0, [1.0, 1.0, 1.0, 1.0]);
+        let settings = ToneMapSettings::new();
+        let mapped = tone_map(&image, &settings);
+        assert_eq!((mapped.layout, mapped.bit_depth()), (ChannelLayout::Rgba, 8));
+        let [r, g, b] = settings.map_rgb([0.0, 0.5, 8.0]);
+        let quantised = |value:f32| (value * 255.0).round() / 255.0;
+        assert_eq!(mapped.rgba(0, 0), [quantised(r), quantised(g), quantised(b), quantised(0.25)]);
+        assert_eq!(tone_map(&image.with_layout(ChannelLayout::Rgb), &settings).layout, ChannelLayout::Rgb);
+    }
+}
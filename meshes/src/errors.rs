@@ -0,0 +1,16 @@
+// line numbers are 1 based and refer to the file being parsed
+#[derive(Debug)]
+pub enum MeshError {
+    FileError(std::io::Error),
+    InvalidLine((usize, String)),
+    InvalidNumber((usize, String)),
+    IndexOutOfRange((usize, i64)),
+    DegenerateFace(usize),
+    UnknownMaterial((usize, String)),
+}
+
+impl From<std::io::Error> for MeshError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FileError(value)
+    }
+}
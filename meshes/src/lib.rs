@@ -0,0 +1,7 @@
+pub mod errors;
+
+// triangle meshes shared by the rasteriser and the ray tracer
+pub mod mesh;
+
+// wavefront obj and mtl files
+pub mod obj;
\ No newline at end of file
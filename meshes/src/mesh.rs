@@ -0,0 +1,145 @@
+use matrices::matrix::Matrix;
+
+
+// the parts of an mtl material the renderers understand, with the defaults the mtl format gives them
+#[derive(Debug, PartialEq, Clone)]
+pub struct MeshMaterial {
+    pub name:String,
+    pub diffuse_colour:[f32; 3],
+    pub specular_colour:[f32; 3],
+    pub emission_colour:[f32; 3],
+    pub specular_exponent:f32,
+    pub dissolve:f32,
+    pub refractive_index:f32,
+    pub illumination_model:u32,
+    pub diffuse_texture:Option<String>,
+}
+
+impl MeshMaterial {
+    pub fn new(name:&str) -> MeshMaterial {
+        MeshMaterial {
+            name:name.to_string(),
+            diffuse_colour:[0.8, 0.8, 0.8],
+            specular_colour:[0.0, 0.0, 0.0],
+            emission_colour:[0.0, 0.0, 0.0],
+            specular_exponent:10.0,
+            dissolve:1.0,
+            refractive_index:1.0,
+            illumination_model:2,
+            diffuse_texture:None,
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emission_colour.iter().any(|channel| *channel > 0.0)
+    }
+}
+
+
+// indices into the position, normal and uv lists of the mesh
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MeshTriangle {
+    pub positions:[usize; 3],
+    pub normals:Option<[usize; 3]>,
+    pub uvs:Option<[usize; 3]>,
+    pub material:Option<usize>,
+    pub group:usize,
+}
+
+
+// an indexed triangle mesh, every triangle belongs to a group and optionally a material
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mesh {
+    pub positions:Vec<[f32; 3]>,
+    pub normals:Vec<[f32; 3]>,
+    pub uvs:Vec<[f32; 2]>,
+    pub triangles:Vec<MeshTriangle>,
+    pub groups:Vec<String>,
+    pub materials:Vec<MeshMaterial>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh {
+            positions:vec![],
+            normals:vec![],
+            uvs:vec![],
+            triangles:vec![],
+            groups:vec![String::from("default")],
+            materials:vec![],
+        }
+    }
+
+    pub fn material(&self, triangle:&MeshTriangle) -> Option<&MeshMaterial> {
+        triangle.material.and_then(|index| self.materials.get(index))
+    }
+
+    pub fn triangle_positions(&self, triangle:&MeshTriangle) -> [[f32; 3]; 3] {
+        triangle.positions.map(|index| self.positions[index])
+    }
+
+    // the unit normal of the face, counter clockwise winding faces the viewer
+    pub fn face_normal(&self, triangle:&MeshTriangle) -> [f32; 3] {
+        let [a, b, c] = self.triangle_positions(triangle);
+        let (e1, e2) = (sub(b, a), sub(c, a));
+        normalise([e1[1]*e2[2] - e1[2]*e2[1], e1[2]*e2[0] - e1[0]*e2[2], e1[0]*e2[1] - e1[1]*e2[0]])
+    }
+
+    pub fn triangle_normals(&self, triangle:&MeshTriangle) -> Option<[[f32; 3]; 3]> {
+        triangle.normals.map(|normals| normals.map(|index| self.normals[index]))
+    }
+
+    pub fn triangle_uvs(&self, triangle:&MeshTriangle) -> Option<[[f32; 2]; 3]> {
+        triangle.uvs.map(|uvs| uvs.map(|index| self.uvs[index]))
+    }
+
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        self.positions.iter().fold(([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]), |(min, max), p| {
+            ([min[0].min(p[0]), min[1].min(p[1]), min[2].min(p[2])], [max[0].max(p[0]), max[1].max(p[1]), max[2].max(p[2])])
+        })
+    }
+
+    // xyz rgb a normal for every corner of every triangle, ready for Render::create_vao_vbo
+    // the colour is the diffuse colour of the material, faces without normals use their face normal
+    pub fn vertex_matrix(&self) -> Matrix<f32> {
+        let mut array = Vec::with_capacity(self.triangles.len() * 3 * 10);
+        for triangle in &self.triangles {
+            let (colour, alpha) = match self.material(triangle) {
+                Some(material) => (material.diffuse_colour, material.dissolve),
+                None => (MeshMaterial::new("").diffuse_colour, 1.0),
+            };
+            let face_normal = self.face_normal(triangle);
+            let normals = self.triangle_normals(triangle).unwrap_or([face_normal; 3]);
+            for (position, normal) in self.triangle_positions(triangle).iter().zip(normals.iter()) {
+                array.extend_from_slice(position);
+                array.extend_from_slice(&colour);
+                array.push(alpha);
+                array.extend_from_slice(normal);
+            }
+        }
+        // Matrix is only built from fixed size arrays, so a single row of the right width is resized
+        let mut matrix = Matrix::from_2darray([[0.0; 10]]);
+        matrix.shape = vec![10, array.len() / 10];
+        matrix.array = array;
+        matrix
+    }
+}
+
+impl Default for Mesh {
+    fn default() -> Mesh {
+        Mesh::new()
+    }
+}
+
+
+pub fn sub(a:[f32; 3], b:[f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn normalise(v:[f32; 3]) -> [f32; 3] {
+    let length = (v[0]*v[0] + v[1]*v[1] + v[2]*v[2]).sqrt();
+    match length > 0.0 {
+        true => [v[0] / length, v[1] / length, v[2] / length],
+        false => [0.0, 0.0, 1.0],
+    }
+}
@@ -0,0 +1,302 @@
+use crate::errors::MeshError;
+use crate::mesh::{Mesh, MeshMaterial, MeshTriangle, sub};
+
+use std::fs;
+use std::path::Path;
+
+
+// reads an obj file and the mtl files it names, which are looked for next to it
+pub fn load_obj(path:&str) -> Result<Mesh, MeshError> {
+    let source = fs::read_to_string(path)?;
+    let directory = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+    parse_obj(&source, &mut |mtl_path| Ok(fs::read_to_string(directory.join(mtl_path))?))
+}
+
+fn parse_number(token:Option<&str>, line_number:usize) -> Result<f32, MeshError> {
+    match token {
+        Some(token) => token.parse().map_err(|_| MeshError::InvalidNumber((line_number, token.to_string()))),
+        None => Err(MeshError::InvalidLine((line_number, String::from("too few values")))),
+    }
+}
+
+fn parse_vec3<'a>(tokens:&mut impl Iterator<Item = &'a str>, line_number:usize) -> Result<[f32; 3], MeshError> {
+    Ok([parse_number(tokens.next(), line_number)?, parse_number(tokens.next(), line_number)?, parse_number(tokens.next(), line_number)?])
+}
+
+// obj indices count from 1, negative ones count back from the last element defined so far
+fn resolve_index(token:&str, count:usize, line_number:usize) -> Result<usize, MeshError> {
+    let index : i64 = token.parse().map_err(|_| MeshError::InvalidNumber((line_number, token.to_string())))?;
+    let resolved = match index {
+        0 => None,
+        index if index > 0 => Some(index - 1),
+        index => Some(count as i64 + index),
+    };
+    match resolved {
+        Some(resolved) if resolved >= 0 && (resolved as usize) < count => Ok(resolved as usize),
+        _ => Err(MeshError::IndexOutOfRange((line_number, index))),
+    }
+}
+
+
+// one corner of a face, v, v/vt, v//vn or v/vt/vn
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    position:usize,
+    uv:Option<usize>,
+    normal:Option<usize>,
+}
+
+fn parse_corner(token:&str, mesh:&Mesh, line_number:usize) -> Result<Corner, MeshError> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), mesh.positions.len(), line_number)?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(uv) => Some(resolve_index(uv, mesh.uvs.len(), line_number)?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(normal) => Some(resolve_index(normal, mesh.normals.len(), line_number)?),
+    };
+    if parts.next().is_some() {
+        return Err(MeshError::InvalidLine((line_number, token.to_string())));
+    }
+    Ok(Corner { position, uv, normal })
+}
+
+
+// splits a simple polygon into triangles by ear clipping in the plane it mostly faces,
+// keeping the winding of the polygon, self intersecting polygons fall back to a fan
+pub fn triangulate(points:&[[f32; 3]]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return vec![];
+    }
+
+    // newell's method gives a normal that is robust to nearly collinear corners
+    let mut normal = [0.0f32; 3];
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let axis = match (normal[0].abs() >= normal[1].abs(), normal[0].abs() >= normal[2].abs(), normal[1].abs() >= normal[2].abs()) {
+        (true, true, _) => 0,
+        (false, _, true) => 1,
+        _ => 2,
+    };
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let orientation = if normal[axis] < 0.0 {-1.0} else {1.0};
+    let flat : Vec<[f32; 2]> = points.iter().map(|point| [point[u], point[v]]).collect();
+
+    let cross = |a:usize, b:usize, c:usize| {
+        let (ab, bc) = ([flat[b][0] - flat[a][0], flat[b][1] - flat[a][1]], [flat[c][0] - flat[b][0], flat[c][1] - flat[b][1]]);
+        orientation * (ab[0]*bc[1] - ab[1]*bc[0])
+    };
+    let inside = |p:usize, a:usize, b:usize, c:usize| cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0;
+
+    let mut remaining : Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|i| {
+            let (a, b, c) = (remaining[(i + m - 1) % m], remaining[*i], remaining[(i + 1) % m]);
+            cross(a, b, c) > 0.0 && remaining.iter().all(|p| [a, b, c].contains(p) || flat[*p] == flat[a]
+                || flat[*p] == flat[b] || flat[*p] == flat[c] || !inside(*p, a, b, c))
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m]]);
+                remaining.remove(i);
+            },
+            None => {
+                for i in 1..m - 1 {
+                    triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+                }
+                return triangles;
+            },
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+
+// read_mtl is given each path named by mtllib and returns the text of that file
+pub fn parse_obj(source:&str, read_mtl:&mut dyn FnMut(&str) -> Result<String, MeshError>) -> Result<Mesh, MeshError> {
+    let mut mesh = Mesh::new();
+    let mut group = 0;
+    let mut material = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => mesh.positions.push(parse_vec3(&mut tokens, line_number)?),
+            "vn" => mesh.normals.push(parse_vec3(&mut tokens, line_number)?),
+            // the optional w of a 3d texture coordinate is dropped
+            "vt" => mesh.uvs.push([parse_number(tokens.next(), line_number)?, tokens.next().map_or(Ok(0.0), |v| parse_number(Some(v), line_number))?]),
+            "f" => {
+                let corners = tokens.map(|token| parse_corner(token, &mesh, line_number)).collect::<Result<Vec<Corner>, MeshError>>()?;
+                if corners.len() < 3 {
+                    return Err(MeshError::DegenerateFace(line_number));
+                }
+                let points : Vec<[f32; 3]> = corners.iter().map(|corner| mesh.positions[corner.position]).collect();
+                for [a, b, c] in triangulate(&points) {
+                    let [a, b, c] = [corners[a], corners[b], corners[c]];
+                    mesh.triangles.push(MeshTriangle {
+                        positions:[a.position, b.position, c.position],
+                        normals:a.normal.zip(b.normal).zip(c.normal).map(|((a, b), c)| [a, b, c]),
+                        uvs:a.uv.zip(b.uv).zip(c.uv).map(|((a, b), c)| [a, b, c]),
+                        material,
+                        group,
+                    });
+                }
+            },
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<&str>>().join(" ");
+                let name = if name.is_empty() {String::from("default")} else {name};
+                group = match mesh.groups.iter().position(|existing| *existing == name) {
+                    Some(existing) => existing,
+                    None => {
+                        mesh.groups.push(name);
+                        mesh.groups.len() - 1
+                    },
+                };
+            },
+            "usemtl" => {
+                let name = tokens.collect::<Vec<&str>>().join(" ");
+                material = match mesh.materials.iter().position(|existing| existing.name == name) {
+                    Some(existing) => Some(existing),
+                    None => return Err(MeshError::UnknownMaterial((line_number, name))),
+                };
+            },
+            "mtllib" => {
+                // file names may contain spaces, the whole rest of the line is one path
+                let path = line["mtllib".len()..].trim();
+                mesh.materials.extend(parse_mtl(&read_mtl(path)?)?);
+            },
+            // smoothing groups, lines, points and curves are ignored
+            "s" | "l" | "p" | "cstype" | "deg" | "curv" | "curv2" | "surf" | "parm" | "end" => {},
+            _ => return Err(MeshError::InvalidLine((line_number, line.to_string()))),
+        }
+    }
+    Ok(mesh)
+}
+
+
+// unknown statements are skipped, mtl files carry a lot that neither renderer uses
+pub fn parse_mtl(source:&str) -> Result<Vec<MeshMaterial>, MeshError> {
+    let mut materials : Vec<MeshMaterial> = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        if keyword == "newmtl" {
+            materials.push(MeshMaterial::new(&tokens.collect::<Vec<&str>>().join(" ")));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(MeshError::InvalidLine((line_number, String::from("statement before newmtl")))),
+        };
+        match keyword {
+            "Kd" => material.diffuse_colour = parse_vec3(&mut tokens, line_number)?,
+            "Ks" => material.specular_colour = parse_vec3(&mut tokens, line_number)?,
+            "Ke" => material.emission_colour = parse_vec3(&mut tokens, line_number)?,
+            "Ns" => material.specular_exponent = parse_number(tokens.next(), line_number)?,
+            "d" => material.dissolve = parse_number(tokens.next(), line_number)?,
+            "Tr" => material.dissolve = 1.0 - parse_number(tokens.next(), line_number)?,
+            "Ni" => material.refractive_index = parse_number(tokens.next(), line_number)?,
+            "illum" => material.illumination_model = parse_number(tokens.next(), line_number)? as u32,
+            // options such as -s or -o come before the file name
+            "map_Kd" => material.diffuse_texture = tokens.last().map(|path| path.to_string()),
+            _ => {},
+        }
+    }
+    Ok(materials)
+}
+
+
+// the total area of the mesh, handy for checking a triangulation kept all of a polygon
+pub fn surface_area(mesh:&Mesh) -> f32 {
+    mesh.triangles.iter().map(|triangle| {
+        let [a, b, c] = mesh.triangle_positions(triangle);
+        let (e1, e2) = (sub(b, a), sub(c, a));
+        let cross = [e1[1]*e2[2] - e1[2]*e2[1], e1[2]*e2[0] - e1[0]*e2[2], e1[0]*e2[1] - e1[1]*e2[0]];
+        0.5 * (cross[0]*cross[0] + cross[1]*cross[1] + cross[2]*cross[2]).sqrt()
+    }).sum()
+}
+
+
+// the bundled l_block model, concave and collinear polygons, and parse errors at their lines
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn l_block() -> Mesh {
+        load_obj(concat!(env!("CARGO_MANIFEST_DIR"), "/models/l_block.obj")).expect("l_block.obj loads")
+    }
+
+    fn no_mtl(path:&str) -> Result<String, MeshError> {
+        panic!("no mtl expected, asked for {}", path)
+    }
+
+
+    #[test]
+    fn l_block_has_its_groups_and_materials() {
+        let mesh = l_block();
+        assert_eq!(mesh.positions.len(), 12);
+        // two hexagons of 4 triangles and six quads of 2
+        assert_eq!(mesh.triangles.len(), 2*4 + 6*2);
+        assert_eq!(mesh.groups, vec!["default", "l_block", "caps", "sides"]);
+        assert_eq!(mesh.materials.iter().map(|material| material.name.as_str()).collect::<Vec<&str>>(), vec!["painted", "steel"]);
+        assert!((surface_area(&mesh) - 14.0).abs() < 1e-4, "area {}", surface_area(&mesh));
+        assert_eq!(mesh.vertex_matrix().array.len(), mesh.triangles.len() * 3 * 10);
+    }
+
+    // the front cap is written with negative indices and faces +z like its normals
+    #[test]
+    fn negative_indices_resolve_to_the_front_cap() {
+        let mesh = l_block();
+        for triangle in mesh.triangles.iter().filter(|triangle| mesh.groups[triangle.group] == "caps") {
+            let normals = mesh.triangle_normals(triangle).expect("caps have normals");
+            assert!(mesh.face_normal(triangle)[2] * normals[0][2] > 0.99, "winding disagrees with the normals");
+            assert_eq!(mesh.material(triangle).map(|material| material.name.as_str()), Some("painted"));
+        }
+        let front = mesh.triangles.iter().find(|triangle| triangle.uvs.is_some()).expect("front cap has uvs");
+        assert!(mesh.triangle_positions(front).iter().all(|position| position[2] == 1.0));
+    }
+
+    #[test]
+    fn concave_and_collinear_polygons_keep_their_area() {
+        let arrow = [[0.0, 0.0, 0.0], [2.0, 1.0, 0.0], [0.0, 2.0, 0.0], [0.5, 1.0, 0.0]];
+        assert_eq!(triangulate(&arrow).len(), 2);
+        let arrow_mesh = parse_obj("v 0 0 0\nv 2 1 0\nv 0 2 0\nv 0.5 1 0\nf 1 2 3 4\n", &mut no_mtl).expect("arrow parses");
+        assert!((surface_area(&arrow_mesh) - 1.5).abs() < 1e-5, "arrow area {}", surface_area(&arrow_mesh));
+        let collinear = parse_obj("v 0 0 0\nv 1 0 0\nv 2 0 0\nv 2 1 0\nv 0 1 0\nf 1 2 3 4 5\n", &mut no_mtl).expect("collinear parses");
+        assert!((surface_area(&collinear) - 2.0).abs() < 1e-5, "collinear area {}", surface_area(&collinear));
+    }
+
+    #[test]
+    fn parse_errors_carry_their_line() {
+        assert!(matches!(parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n", &mut no_mtl), Err(MeshError::IndexOutOfRange((4, 4)))), "index out of range");
+        assert!(matches!(parse_obj("v 0 0 0\nv 1 0 0\nf -1 -2 -3\n", &mut no_mtl), Err(MeshError::IndexOutOfRange((3, -3)))), "negative index out of range");
+        assert!(matches!(parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n", &mut no_mtl), Err(MeshError::IndexOutOfRange((4, 0)))), "zero index");
+        assert!(matches!(parse_obj("v 0 0 0\nv 1 zero 0\n", &mut no_mtl), Err(MeshError::InvalidNumber((2, token))) if token == "zero"), "bad number");
+        assert!(matches!(parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n", &mut no_mtl), Err(MeshError::DegenerateFace(3))), "two corners");
+        assert!(matches!(parse_obj("usemtl missing\n", &mut no_mtl), Err(MeshError::UnknownMaterial((1, name))) if name == "missing"), "unknown material");
+    }
+}
@@ -0,0 +1,25 @@
+#version 330 core
+
+in vec2 texture_coordinate;
+
+out vec4 fragment_colour;
+
+uniform sampler2D image_texture;
+
+// outlines every texel when zoomed in, image_size is (width, height, unused)
+uniform float grid_opacity;
+uniform vec3 image_size;
+
+
+vec4 pixel_grid(vec4 colour) {
+    vec2 texel = texture_coordinate * image_size.xy;
+    vec2 line_width = fwidth(texel);
+    vec2 distance_to_edge = min(fract(texel), 1.0 - fract(texel));
+    bool on_edge = any(lessThan(distance_to_edge, 0.5 * line_width));
+    return on_edge ? mix(colour, vec4(0.5, 0.5, 0.5, 1.0), grid_opacity) : colour;
+}
+
+
+void main() {
+    fragment_colour = pixel_grid(texture(image_texture, texture_coordinate));
+}
\ No newline at end of file
@@ -0,0 +1,15 @@
+#version 330 core
+
+layout (location = 0) in vec3 point_pos;
+layout (location = 1) in vec2 point_uv;
+
+out vec2 texture_coordinate;
+
+uniform mat4 orthographic_projection;
+uniform mat4 camera_transformation;
+uniform mat4 world_transform;
+
+void main() {
+    gl_Position = orthographic_projection * camera_transformation * world_transform * vec4(point_pos, 1.0);
+    texture_coordinate = point_uv;
+}
\ No newline at end of file
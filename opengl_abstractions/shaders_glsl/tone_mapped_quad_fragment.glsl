@@ -0,0 +1,65 @@
+#version 330 core
+
+in vec2 texture_coordinate;
+
+out vec4 fragment_colour;
+
+uniform sampler2D image_texture;
+
+// must match images::tone_map::ToneMapper
+// 0 exposure only, 1 reinhard, 2 extended reinhard, 3 aces, 4 hable
+uniform float tone_map_operator;
+uniform float exposure;
+uniform float gamma;
+uniform float white_point;
+
+// outlines every texel when zoomed in, image_size is (width, height, unused)
+uniform float grid_opacity;
+uniform vec3 image_size;
+
+
+vec3 hable_partial(vec3 x) {
+    float a = 0.15;
+    float b = 0.50;
+    float c = 0.10;
+    float d = 0.20;
+    float e = 0.02;
+    float f = 0.30;
+    return ((x*(a*x + c*b) + d*e) / (x*(a*x + b) + d*f)) - e/f;
+}
+
+vec3 tone_map(vec3 x) {
+    int selected = int(tone_map_operator + 0.5);
+    if (selected == 1) {
+        return x / (1.0 + x);
+    }
+    if (selected == 2) {
+        return x * (1.0 + x / (white_point*white_point)) / (1.0 + x);
+    }
+    if (selected == 3) {
+        return (x * (2.51*x + 0.03)) / (x * (2.43*x + 0.59) + 0.14);
+    }
+    if (selected == 4) {
+        return hable_partial(2.0*x) / hable_partial(vec3(11.2));
+    }
+    return x;
+}
+
+
+vec4 pixel_grid(vec4 colour) {
+    vec2 texel = texture_coordinate * image_size.xy;
+    vec2 line_width = fwidth(texel);
+    vec2 distance_to_edge = min(fract(texel), 1.0 - fract(texel));
+    bool on_edge = any(lessThan(distance_to_edge, 0.5 * line_width));
+    return on_edge ? mix(colour, vec4(0.5, 0.5, 0.5, 1.0), grid_opacity) : colour;
+}
+
+
+void main() {
+    vec4 texel = texture(image_texture, texture_coordinate);
+
+    vec3 exposed = max(texel.rgb, vec3(0.0)) * pow(2.0, exposure);
+    vec3 mapped = clamp(tone_map(exposed), 0.0, 1.0);
+
+    fragment_colour = pixel_grid(vec4(pow(mapped, vec3(1.0 / gamma)), texel.a));
+}
\ No newline at end of file
@@ -22,11 +22,14 @@ pub enum GlError<T> {
     InvalidProgramVariantUsage(ProgramVariant),
     MatrixError(MatrixError<T>),
     TryFromIntError(TryFromIntError),
+    InvalidTextureUnit(u32),
+    InvalidTextureDataLength(usize),
 }
 
 #[derive(Clone, Copy, Debug)]
 pub enum UniformType {
     Float,
+    Int,
     Vec3,
     Mat4,
 }
@@ -42,12 +45,16 @@ pub enum ShaderType {
 pub enum ProgramVariant {
     BlinnPhongOrthographic(u32),
     SimpleOrthographic(u32),
+    TexturedQuad(u32),
+    ToneMappedQuad(u32),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ProgramSelect {
     SelectBlinnPhongOrthographic,
     SelectSimpleOrthographic,
+    SelectTexturedQuad,
+    SelectToneMappedQuad,
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -92,4 +99,32 @@ pub enum VertexObject {
     Array,
     Buffer,
     ArrayAndBuffer,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextureFormat {
+    Red,
+    RedGreen,
+    Rgb,
+    Rgba,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextureWrap {
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TextureData<'l> {
+    U8(&'l [u8]),
+    U16(&'l [u16]),
+    F32(&'l [f32]),
 }
\ No newline at end of file
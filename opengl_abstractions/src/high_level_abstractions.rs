@@ -1,7 +1,8 @@
 use crate::enums::{
     BufferType, DrawMode, DrawType,
     GlError, ProgramVariant, ShaderType,
-    UniformType, VertexObject,
+    TextureData, TextureFilter, TextureFormat,
+    TextureWrap, UniformType, VertexObject,
 };
 use crate::gl;
 use crate::gl::Gl;
@@ -21,6 +22,8 @@ pub fn set_uniform<T>(opengl:&Gl, program:ProgramVariant,
     match program {
         ProgramVariant::SimpleOrthographic(id) => intermediate_opengl::set_uniform(opengl, id, uniform_name, uniform_type, value),
         ProgramVariant::BlinnPhongOrthographic(id) => intermediate_opengl::set_uniform(opengl, id, uniform_name, uniform_type, value),
+        ProgramVariant::TexturedQuad(id) => intermediate_opengl::set_uniform(opengl, id, uniform_name, uniform_type, value),
+        ProgramVariant::ToneMappedQuad(id) => intermediate_opengl::set_uniform(opengl, id, uniform_name, uniform_type, value),
     }
 }
 
@@ -66,6 +69,19 @@ impl WithVertexObject<'_> {
 
         Ok((vao, vbo))
     }
+    pub fn new_textured_vao_vbo<T>(opengl:&Gl, data:&Matrix<f32>) -> Result<(u32, u32), GlError<T>> {
+        let (vao, with_vao) = WithVertexObject::new_vao(opengl);
+        let (vbo, with_vbo) = WithVertexObject::new_vbo(opengl);
+
+        with_vbo.buffer_data(BufferType::ArrayBuffer, data, DrawType::DynamicDraw);
+
+        match data.dtype_memsize().try_into() {
+            Ok(dtype_size) => with_vao.set_textured_vertex_attribs(dtype_size),
+            Err(error) => Err(GlError::TryFromIntError(error)),
+        }?;
+
+        Ok((vao, vbo))
+    }
     pub fn vao_vbo(opengl:&Gl, vao:u32, vbo:u32) -> WithVertexObject<'_> {
         raw_opengl::bind_vertex_array(opengl, vao);
         intermediate_opengl::bind_buffer(opengl, BufferType::ArrayBuffer, vbo);
@@ -108,6 +124,11 @@ impl WithVertexObject<'_> {
         if store_normals { intermediate_opengl::set_vertex_attrib(self.opengl, 3, store_normals, dtype_size)?; }
         Ok(())
     }
+    pub fn set_textured_vertex_attribs<T>(&self, dtype_size:i32) -> Result<(), GlError<T>> {
+        intermediate_opengl::set_textured_vertex_attrib(self.opengl, 0, dtype_size)?;
+        intermediate_opengl::set_textured_vertex_attrib(self.opengl, 1, dtype_size)?;
+        Ok(())
+    }
     pub fn draw_vao<T>(&self, mode:DrawMode, data:&Matrix<f32>) -> Result<(), GlError<T>> {
         match data.ndims() {
             2 => {
@@ -132,4 +153,63 @@ impl Drop for WithVertexObject<'_> {
             },
         }
     }
+}
+
+
+pub struct WithTexture<'l> {
+    pub opengl:&'l Gl,
+    pub texture_id:u32,
+}
+impl WithTexture<'_> {
+    // new textures sample with clamped edges, the filter is chosen by the caller
+    pub fn new_texture<T>(opengl:&Gl, width:usize, height:usize,
+                        format:TextureFormat, data:TextureData<'_>,
+                        filter:TextureFilter
+    ) -> Result<u32, GlError<T>> {
+        let texture = raw_opengl::gen_textures(opengl);
+        let with_texture = WithTexture::texture(opengl, texture);
+
+        with_texture.set_filter(filter);
+        with_texture.set_wrap(TextureWrap::ClampToEdge);
+        with_texture.tex_image_2d(width, height, format, data)?;
+
+        Ok(texture)
+    }
+    pub fn texture(opengl:&Gl, texture:u32) -> WithTexture<'_> {
+        intermediate_opengl::bind_texture(opengl, texture);
+        WithTexture { opengl, texture_id:texture }
+    }
+    pub fn texture_on_unit<T>(opengl:&Gl, texture:u32, texture_unit:u32) -> Result<WithTexture<'_>, GlError<T>> {
+        intermediate_opengl::active_texture(opengl, texture_unit)?;
+        Ok(WithTexture::texture(opengl, texture))
+    }
+    pub fn set_filter(&self, filter:TextureFilter) {
+        intermediate_opengl::set_texture_filter(self.opengl, filter);
+    }
+    pub fn set_wrap(&self, wrap:TextureWrap) {
+        intermediate_opengl::set_texture_wrap(self.opengl, wrap);
+    }
+    pub fn tex_image_2d<T>(&self, width:usize, height:usize, format:TextureFormat, data:TextureData<'_>) -> Result<(), GlError<T>> {
+        let (width, height) = texture_size(width, height)?;
+        intermediate_opengl::tex_image_2d(self.opengl, width, height, format, data)
+    }
+    pub fn tex_sub_image_2d<T>(&self, width:usize, height:usize, format:TextureFormat, data:TextureData<'_>) -> Result<(), GlError<T>> {
+        let (width, height) = texture_size(width, height)?;
+        intermediate_opengl::tex_sub_image_2d(self.opengl, width, height, format, data)
+    }
+    pub fn delete(self) {
+        raw_opengl::delete_textures(self.opengl, self.texture_id);
+    }
+}
+impl Drop for WithTexture<'_> {
+    fn drop(&mut self) {
+        intermediate_opengl::bind_texture(self.opengl, 0);
+    }
+}
+
+fn texture_size<T>(width:usize, height:usize) -> Result<(i32, i32), GlError<T>> {
+    match (width.try_into(), height.try_into()) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        (Err(error), _) | (_, Err(error)) => Err(GlError::TryFromIntError(error)),
+    }
 }
\ No newline at end of file
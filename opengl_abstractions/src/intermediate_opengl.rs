@@ -5,7 +5,8 @@ use crate::enums::{
     BlendFunc, BufferBit, BufferType,
     DrawMode, DrawType, GlEnable,
     GlError, ProgramVariant, ShaderType,
-    UniformType,
+    TextureData, TextureFilter, TextureFormat,
+    TextureWrap, UniformType,
 };
 
 use std::ffi::CString;
@@ -89,6 +90,8 @@ pub fn use_program<T>(opengl:&Gl, program:ProgramVariant) -> Result<(), GlError<
     match program {
         ProgramVariant::BlinnPhongOrthographic(id) => Ok(raw_opengl::use_program(opengl, id)),
         ProgramVariant::SimpleOrthographic(id) => Ok(raw_opengl::use_program(opengl, id)),
+        ProgramVariant::TexturedQuad(id) => Ok(raw_opengl::use_program(opengl, id)),
+        ProgramVariant::ToneMappedQuad(id) => Ok(raw_opengl::use_program(opengl, id)),
     }
 }
 
@@ -118,6 +121,7 @@ pub fn set_uniform<T>(opengl:&Gl, program_id:u32,
     let location_name = get_uniform_location(opengl, program_id, uniform_name)?;
     match uniform_type {
         UniformType::Float => raw_opengl::set_uniform_float(opengl, location_name, value),
+        UniformType::Int   => raw_opengl::set_uniform_int(opengl, location_name, value),
         UniformType::Vec3  => raw_opengl::set_uniform_vec3(opengl, location_name, value),
         UniformType::Mat4  => raw_opengl::set_uniform_mat4(opengl, location_name, value),
     }
@@ -234,6 +238,22 @@ pub fn set_vertex_attrib<T>(opengl:&Gl, layout_location:u32, store_normals:bool,
 }
 
 
+// textured vertices are laid out as x, y, z, u, v
+pub fn set_textured_vertex_attrib<T>(opengl:&Gl, layout_location:u32, dtype_size:i32) -> Result<(), GlError<T>> {
+    let n_per_vertice : i32 = 3;
+    let n_per_uv      : i32 = 2;
+    let stride = (n_per_vertice + n_per_uv) * dtype_size;
+    let (num_items, offset) = match layout_location {
+        0 => Ok((n_per_vertice, 0 as *const c_void)),
+        1 => Ok((n_per_uv, (n_per_vertice * dtype_size) as *const c_void)),
+        n => Err(GlError::InvalidLayoutLocation(n)),
+    }?;
+    raw_opengl::enable_vertex_attrib_array(opengl, layout_location);
+    raw_opengl::vertex_attrib_pointer(opengl, layout_location, num_items, gl::FLOAT, gl::FALSE, stride, offset);
+    Ok(())
+}
+
+
 pub fn buffer_sub_data(opengl:&Gl, target:BufferType, size:isize, data:*const c_void) {
     match target {
         BufferType::ArrayBuffer => raw_opengl::buffer_sub_data(opengl, gl::ARRAY_BUFFER, size, data)
@@ -253,4 +273,106 @@ pub fn draw_arrays(opengl:&Gl, mode:DrawMode, num_shapes:i32) {
 
 pub fn viewport(opengl:&Gl, width:i32, height:i32) {
     raw_opengl::viewport(opengl, 0, 0, width, height);
+}
+
+// reads the bound framebuffer as rgba u8, rows run from the bottom of the window up
+pub fn read_pixels_rgba(opengl:&Gl, width:i32, height:i32) -> Vec<u8> {
+    let mut pixels = vec![0u8; width.max(0) as usize * height.max(0) as usize * 4];
+    raw_opengl::pixel_store_i(opengl, gl::PACK_ALIGNMENT, 1);
+    raw_opengl::read_pixels(opengl, width, height, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+    pixels
+}
+
+
+pub fn active_texture<T>(opengl:&Gl, texture_unit:u32) -> Result<(), GlError<T>> {
+    // GL 3.3 guarantees at least 16 units per shader stage
+    match texture_unit < 16 {
+        true => Ok(raw_opengl::active_texture(opengl, gl::TEXTURE0 + texture_unit)),
+        false => Err(GlError::InvalidTextureUnit(texture_unit)),
+    }
+}
+
+pub fn bind_texture(opengl:&Gl, texture:u32) {
+    raw_opengl::bind_texture(opengl, gl::TEXTURE_2D, texture);
+}
+
+pub fn set_texture_filter(opengl:&Gl, filter:TextureFilter) {
+    let filter = match filter {
+        TextureFilter::Nearest => gl::NEAREST,
+        TextureFilter::Linear  => gl::LINEAR,
+    } as i32;
+    raw_opengl::tex_parameter_i(opengl, gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter);
+    raw_opengl::tex_parameter_i(opengl, gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter);
+}
+
+pub fn set_texture_wrap(opengl:&Gl, wrap:TextureWrap) {
+    let wrap = match wrap {
+        TextureWrap::ClampToEdge    => gl::CLAMP_TO_EDGE,
+        TextureWrap::Repeat         => gl::REPEAT,
+        TextureWrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+    } as i32;
+    raw_opengl::tex_parameter_i(opengl, gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, wrap);
+    raw_opengl::tex_parameter_i(opengl, gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, wrap);
+}
+
+fn texture_format_channels(format:TextureFormat) -> usize {
+    match format {
+        TextureFormat::Red      => 1,
+        TextureFormat::RedGreen => 2,
+        TextureFormat::Rgb      => 3,
+        TextureFormat::Rgba     => 4,
+    }
+}
+
+// returns (internal format, format, type, data pointer) for a tightly packed upload
+fn texture_upload_params<T>(
+    width:i32, height:i32,
+    format:TextureFormat,
+    data:TextureData<'_>,
+) -> Result<(i32, gl::types::GLenum, gl::types::GLenum, *const c_void), GlError<T>> {
+    let (data_len, data_ptr) = match data {
+        TextureData::U8(data)  => (data.len(), data.as_ptr() as *const c_void),
+        TextureData::U16(data) => (data.len(), data.as_ptr() as *const c_void),
+        TextureData::F32(data) => (data.len(), data.as_ptr() as *const c_void),
+    };
+    if data_len != width as usize * height as usize * texture_format_channels(format) {
+        return Err(GlError::InvalidTextureDataLength(data_len));
+    }
+
+    let gl_format = match format {
+        TextureFormat::Red      => gl::RED,
+        TextureFormat::RedGreen => gl::RG,
+        TextureFormat::Rgb      => gl::RGB,
+        TextureFormat::Rgba     => gl::RGBA,
+    };
+    let (internal_format, gl_type) = match (format, data) {
+        (TextureFormat::Red,      TextureData::U8(_))  => (gl::R8,       gl::UNSIGNED_BYTE),
+        (TextureFormat::RedGreen, TextureData::U8(_))  => (gl::RG8,      gl::UNSIGNED_BYTE),
+        (TextureFormat::Rgb,      TextureData::U8(_))  => (gl::RGB8,     gl::UNSIGNED_BYTE),
+        (TextureFormat::Rgba,     TextureData::U8(_))  => (gl::RGBA8,    gl::UNSIGNED_BYTE),
+        (TextureFormat::Red,      TextureData::U16(_)) => (gl::R16,      gl::UNSIGNED_SHORT),
+        (TextureFormat::RedGreen, TextureData::U16(_)) => (gl::RG16,     gl::UNSIGNED_SHORT),
+        (TextureFormat::Rgb,      TextureData::U16(_)) => (gl::RGB16,    gl::UNSIGNED_SHORT),
+        (TextureFormat::Rgba,     TextureData::U16(_)) => (gl::RGBA16,   gl::UNSIGNED_SHORT),
+        (TextureFormat::Red,      TextureData::F32(_)) => (gl::R32F,     gl::FLOAT),
+        (TextureFormat::RedGreen, TextureData::F32(_)) => (gl::RG32F,    gl::FLOAT),
+        (TextureFormat::Rgb,      TextureData::F32(_)) => (gl::RGB32F,   gl::FLOAT),
+        (TextureFormat::Rgba,     TextureData::F32(_)) => (gl::RGBA32F,  gl::FLOAT),
+    };
+    Ok((internal_format as i32, gl_format, gl_type, data_ptr))
+}
+
+pub fn tex_image_2d<T>(opengl:&Gl, width:i32, height:i32, format:TextureFormat, data:TextureData<'_>) -> Result<(), GlError<T>> {
+    let (internal_format, gl_format, gl_type, data_ptr) = texture_upload_params(width, height, format, data)?;
+    // rows of odd width rgb u8 images are not 4 byte aligned
+    raw_opengl::pixel_store_i(opengl, gl::UNPACK_ALIGNMENT, 1);
+    raw_opengl::tex_image_2d(opengl, gl::TEXTURE_2D, internal_format, width, height, gl_format, gl_type, data_ptr);
+    Ok(())
+}
+
+pub fn tex_sub_image_2d<T>(opengl:&Gl, width:i32, height:i32, format:TextureFormat, data:TextureData<'_>) -> Result<(), GlError<T>> {
+    let (_, gl_format, gl_type, data_ptr) = texture_upload_params(width, height, format, data)?;
+    raw_opengl::pixel_store_i(opengl, gl::UNPACK_ALIGNMENT, 1);
+    raw_opengl::tex_sub_image_2d(opengl, gl::TEXTURE_2D, width, height, gl_format, gl_type, data_ptr);
+    Ok(())
 }
\ No newline at end of file
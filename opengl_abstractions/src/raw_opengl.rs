@@ -65,6 +65,10 @@ pub fn set_uniform_vec3(opengl:&Gl, uniform_location:i32, vec3_ptr:*const f32) {
     unsafe { opengl.Uniform3fv(uniform_location, 1, vec3_ptr) }
 }
 
+pub fn set_uniform_int(opengl:&Gl, uniform_location:i32, int:*const f32) {
+    unsafe { opengl.Uniform1i(uniform_location, *int as i32) }
+}
+
 pub fn set_uniform_mat4(opengl:&Gl, uniform_location:i32, mat4_ptr:*const f32) {
     unsafe { opengl.UniformMatrix4fv(uniform_location, 1, gl::TRUE, mat4_ptr) }
 }
@@ -81,6 +85,16 @@ pub fn gen_buffers(opengl:&Gl) -> u32 {
     vbo
 }
 
+pub fn gen_textures(opengl:&Gl) -> u32 {
+    let mut texture = 0;
+    unsafe { opengl.GenTextures(1, &mut texture) }
+    texture
+}
+
+pub fn delete_textures(opengl:&Gl, texture:u32) {
+    unsafe { opengl.DeleteTextures(1, &texture) }
+}
+
 pub fn bind_vertex_array(opengl:&Gl, vao:u32) {
     unsafe { opengl.BindVertexArray(vao) }
 }
@@ -100,6 +114,47 @@ pub fn buffer_data(
 }
 
 
+pub fn active_texture(opengl:&Gl, texture_unit:gl::types::GLenum) {
+    unsafe { opengl.ActiveTexture(texture_unit) }
+}
+
+pub fn bind_texture(opengl:&Gl, target:gl::types::GLenum, texture:u32) {
+    unsafe { opengl.BindTexture(target, texture) }
+}
+
+pub fn tex_parameter_i(opengl:&Gl, target:gl::types::GLenum, pname:gl::types::GLenum, param:i32) {
+    unsafe { opengl.TexParameteri(target, pname, param) }
+}
+
+pub fn pixel_store_i(opengl:&Gl, pname:gl::types::GLenum, param:i32) {
+    unsafe { opengl.PixelStorei(pname, param) }
+}
+
+pub fn tex_image_2d(
+    opengl:&Gl,
+    target:gl::types::GLenum,
+    internal_format:i32,
+    width:i32,
+    height:i32,
+    format:gl::types::GLenum,
+    type_:gl::types::GLenum,
+    data_ptr:*const c_void,
+) {
+    unsafe { opengl.TexImage2D(target, 0, internal_format, width, height, 0, format, type_, data_ptr) }
+}
+
+pub fn tex_sub_image_2d(
+    opengl:&Gl,
+    target:gl::types::GLenum,
+    width:i32,
+    height:i32,
+    format:gl::types::GLenum,
+    type_:gl::types::GLenum,
+    data_ptr:*const c_void,
+) {
+    unsafe { opengl.TexSubImage2D(target, 0, 0, 0, width, height, format, type_, data_ptr) }
+}
+
 pub fn enable_vertex_attrib_array(opengl:&Gl, layout_location:u32) {
     unsafe { opengl.EnableVertexAttribArray(layout_location) }
 }
@@ -140,6 +195,10 @@ pub fn viewport(opengl:&Gl, x_low:i32, y_low:i32, x_high:i32, y_high:i32) {
     unsafe { opengl.Viewport(x_low, y_low, x_high, y_high);}
 }
 
+pub fn read_pixels(opengl:&Gl, width:i32, height:i32, format:gl::types::GLenum, type_:gl::types::GLenum, data:*mut c_void) {
+    unsafe { opengl.ReadPixels(0, 0, width, height, format, type_, data) }
+}
+
 pub fn clear_colour(opengl:&Gl, red:f32, green:f32, blue:f32, alpha:f32) {
     unsafe { opengl.ClearColor(red, green, blue, alpha) }
 }
@@ -10,31 +10,47 @@ use numeracy::matrices::matrix::Matrix;
 #[derive(Clone, Copy)]
 pub struct ProgramHolder {
     pub simple_orthographic:ProgramVariant,
-    pub blinn_phone_orthographic:ProgramVariant
+    pub blinn_phone_orthographic:ProgramVariant,
+    pub textured_quad:ProgramVariant,
+    pub tone_mapped_quad:ProgramVariant,
 }
 impl ProgramHolder {
     pub fn new<T>(
         simple_orthographic_shader:ProgramVariant,
-        blinn_phone_orthographic_shader:ProgramVariant
+        blinn_phone_orthographic_shader:ProgramVariant,
+        textured_quad_shader:ProgramVariant,
+        tone_mapped_quad_shader:ProgramVariant,
     ) -> Result<ProgramHolder, GlError<T>> {
 
         let simple_orthographic = match simple_orthographic_shader {
             ProgramVariant::SimpleOrthographic(id) => Ok(ProgramVariant::SimpleOrthographic(id)),
-            ProgramVariant::BlinnPhongOrthographic(id) => Err(GlError::InvalidProgramVariantUsage(ProgramVariant::BlinnPhongOrthographic(id))),
+            other => Err(GlError::InvalidProgramVariantUsage(other)),
         }?;
 
         let blinn_phone_orthographic = match blinn_phone_orthographic_shader {
-            ProgramVariant::SimpleOrthographic(id) => Err(GlError::InvalidProgramVariantUsage(ProgramVariant::SimpleOrthographic(id))),
             ProgramVariant::BlinnPhongOrthographic(id) => Ok(ProgramVariant::BlinnPhongOrthographic(id)),
+            other => Err(GlError::InvalidProgramVariantUsage(other)),
         }?;
 
-        Ok(ProgramHolder { simple_orthographic, blinn_phone_orthographic })
+        let textured_quad = match textured_quad_shader {
+            ProgramVariant::TexturedQuad(id) => Ok(ProgramVariant::TexturedQuad(id)),
+            other => Err(GlError::InvalidProgramVariantUsage(other)),
+        }?;
+
+        let tone_mapped_quad = match tone_mapped_quad_shader {
+            ProgramVariant::ToneMappedQuad(id) => Ok(ProgramVariant::ToneMappedQuad(id)),
+            other => Err(GlError::InvalidProgramVariantUsage(other)),
+        }?;
+
+        Ok(ProgramHolder { simple_orthographic, blinn_phone_orthographic, textured_quad, tone_mapped_quad })
     }
 
     pub fn use_program<T>(&self, opengl:&Gl, program:ProgramSelect) -> Result<(), GlError<T>> {
         match program {
             ProgramSelect::SelectSimpleOrthographic => intermediate_opengl::use_program(opengl, self.simple_orthographic),
             ProgramSelect::SelectBlinnPhongOrthographic => intermediate_opengl::use_program(opengl, self.blinn_phone_orthographic),
+            ProgramSelect::SelectTexturedQuad => intermediate_opengl::use_program(opengl, self.textured_quad),
+            ProgramSelect::SelectToneMappedQuad => intermediate_opengl::use_program(opengl, self.tone_mapped_quad),
         }
     }
 
@@ -49,6 +65,8 @@ impl WithProgram<'_> {
         match program {
             ProgramSelect::SelectSimpleOrthographic => WithProgram { opengl, program_variant: programs.simple_orthographic },
             ProgramSelect::SelectBlinnPhongOrthographic => WithProgram { opengl, program_variant: programs.blinn_phone_orthographic },
+            ProgramSelect::SelectTexturedQuad => WithProgram { opengl, program_variant: programs.textured_quad },
+            ProgramSelect::SelectToneMappedQuad => WithProgram { opengl, program_variant: programs.tone_mapped_quad },
         }
     }
 
@@ -82,5 +100,22 @@ pub fn create_program<T>(opengl:&Gl, program_type:ProgramSelect) -> Result<Progr
             )?;
             Ok(ProgramVariant::SimpleOrthographic(shader_id))
         },
+        ProgramSelect::SelectTexturedQuad => {
+            let vertex_text = shaders::TEXTURED_QUAD_VERTEX;
+            let fragment_text = shaders::TEXTURED_QUAD_FRAGMENT;
+            let shader_id = high_level_abstractions::create_shader_program(
+                opengl, vertex_text, fragment_text
+            )?;
+            Ok(ProgramVariant::TexturedQuad(shader_id))
+        },
+        ProgramSelect::SelectToneMappedQuad => {
+            // shares the textured quad vertex shader
+            let vertex_text = shaders::TEXTURED_QUAD_VERTEX;
+            let fragment_text = shaders::TONE_MAPPED_QUAD_FRAGMENT;
+            let shader_id = high_level_abstractions::create_shader_program(
+                opengl, vertex_text, fragment_text
+            )?;
+            Ok(ProgramVariant::ToneMappedQuad(shader_id))
+        },
     }
 }
\ No newline at end of file
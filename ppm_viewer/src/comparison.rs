@@ -0,0 +1,217 @@
+use render_context::render::Render;
+use render_context::enums::{DrawMode, ProgramSelect};
+use render_context::enums::{TextureData, TextureFilter, TextureFormat};
+use render_context::glfw::{Key, MouseButton};
+use matrices::matrix::Matrix;
+
+use images::errors::ImageError;
+use images::image::Image;
+use images::compare;
+use images::formats;
+
+use crate::errors::ViewerError;
+use crate::input::InputEdges;
+use crate::inspector;
+use crate::{adjust_tone_mapping, create_image_texture, fit_camera_to_image, image_program};
+use crate::{image_quad_columns, screenshot_status, update_image_texture};
+
+
+// gap between the two images in side by side mode, as a fraction of the image width
+pub const SIDE_BY_SIDE_GAP : f32 = 0.02;
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompareMode {
+    SideBySide,
+    Wipe,
+    AbsoluteDiff,
+    SignedDiff,
+}
+
+impl CompareMode {
+    pub fn next(&self) -> CompareMode {
+        match self {
+            CompareMode::SideBySide => CompareMode::Wipe,
+            CompareMode::Wipe => CompareMode::AbsoluteDiff,
+            CompareMode::AbsoluteDiff => CompareMode::SignedDiff,
+            CompareMode::SignedDiff => CompareMode::SideBySide,
+        }
+    }
+
+    pub fn is_diff(&self) -> bool {
+        matches!(self, CompareMode::AbsoluteDiff | CompareMode::SignedDiff)
+    }
+}
+
+
+// absolute diffs are per channel, signed diffs compare luma, both saturate at full_scale
+pub fn diff_image(a:&Image, b:&Image, mode:CompareMode, full_scale:f32) -> Result<Image, ImageError> {
+    match mode {
+        CompareMode::SignedDiff => compare::signed_diff_false_colour(a, b, full_scale),
+        _ => compare::scaled_abs_diff(a, b, full_scale),
+    }
+}
+
+
+// the quads of a and b for a mode, in wipe mode a is shown left of the split and b right of it
+fn mode_quads(width:usize, height:usize, mode:CompareMode, wipe:f32) -> (Matrix<f32>, Matrix<f32>) {
+    let offset_b = (1.0 + SIDE_BY_SIDE_GAP) * width as f32;
+    match mode {
+        CompareMode::SideBySide => (image_quad_columns(width, height, 0.0, 0.0, 1.0),
+                                    image_quad_columns(width, height, offset_b, 0.0, 1.0)),
+        CompareMode::Wipe => (image_quad_columns(width, height, 0.0, 0.0, wipe),
+                              image_quad_columns(width, height, 0.0, wipe, 1.0)),
+        // b is not drawn, its quad is left empty
+        _ => (image_quad_columns(width, height, 0.0, 0.0, 1.0),
+              image_quad_columns(width, height, 0.0, 1.0, 1.0)),
+    }
+}
+
+fn wipe_line(width:usize, height:usize, wipe:f32) -> Matrix<f32> {
+    let x = wipe * width as f32;
+    Matrix::from_2darray([
+        [x,             0.0, -1.0, 0.5, 0.5],
+        [x, -(height as f32), -1.0, 0.5, 0.5],
+    ])
+}
+
+fn fit_camera_to_mode(render:&mut Render, width:usize, height:usize, mode:CompareMode) {
+    match mode {
+        CompareMode::SideBySide => fit_camera_to_image(render, ((2.0 + SIDE_BY_SIDE_GAP) * width as f32) as usize, height),
+        _ => fit_camera_to_image(render, width, height),
+    }
+}
+
+
+
+pub fn view_comparison_from_paths(a_path:&str, b_path:&str) -> Result<(), ViewerError> {
+    let a = formats::load_image(a_path)?;
+    let b = formats::load_image(b_path)?;
+    view_comparison(&a, &b)
+}
+
+fn comparison_title(mode:CompareMode, full_scale:f32, status:&str) -> String {
+    let mut title = match mode.is_diff() {
+        true => format!("ppm_viewer {:?}, full scale {}", mode, full_scale),
+        false => format!("ppm_viewer {:?}", mode),
+    };
+    if !status.is_empty() {
+        title += &format!("  | {}", status);
+    }
+    title
+}
+
+// m cycles side by side, wipe, absolute diff and signed diff, pan and zoom are shared by both images
+// the right button drags the wipe, [ and ] halve and double the diff full scale
+pub fn view_comparison(a:&Image, b:&Image) -> Result<(), ViewerError> {
+    if (a.width, a.height) != (b.width, b.height) {
+        return Err(ImageError::DimensionMismatch(((a.width, a.height), (b.width, b.height))).into());
+    }
+    let (width, height) = (a.width, a.height);
+
+    let mut render = Render::default()?;
+    render.setup_render();
+
+    let mut mode = CompareMode::SideBySide;
+    let mut wipe = 0.5;
+    let mut full_scale = 0.1;
+
+    let (mut quad_a, mut quad_b) = mode_quads(width, height, mode, wipe);
+    let (vao_a, vbo_a) = render.create_vao_vbo(&quad_a)?;
+    let (vao_b, vbo_b) = render.create_vao_vbo(&quad_b)?;
+    let mut line = wipe_line(width, height, wipe);
+    let (line_vao, line_vbo) = render.create_vao_vbo(&line)?;
+
+    let texture_a = create_image_texture(&render, a, TextureFilter::Linear)?;
+    let texture_b = create_image_texture(&render, b, TextureFilter::Linear)?;
+    let diff_texture = create_image_texture(&render, &diff_image(a, b, CompareMode::AbsoluteDiff, full_scale)?, TextureFilter::Nearest)?;
+    let line_texture = render.create_texture(1, 1, TextureFormat::Rgb, TextureData::U8(&[255, 255, 255]), TextureFilter::Nearest)?;
+    let (program_a, program_b) = (image_program(a), image_program(b));
+
+    fit_camera_to_mode(&mut render, width, height, mode);
+    let mut input = InputEdges::new();
+    let mut layout_changed = true;
+    let mut diff_changed = false;
+    let mut title_changed = false;
+    // the last thing the viewer did, shown at the end of the title
+    let mut status = String::new();
+
+    while !render.render_over() {
+
+        render.begin_render_actions()?;
+
+
+        if mode.is_diff() {
+            render.use_program(ProgramSelect::SelectTexturedQuad)?;
+            render.draw_textured_vao(DrawMode::GlTriangles, vao_a, diff_texture, &quad_a)?;
+        } else {
+            render.use_program(program_a)?;
+            render.draw_textured_vao(DrawMode::GlTriangles, vao_a, texture_a, &quad_a)?;
+            render.use_program(program_b)?;
+            render.draw_textured_vao(DrawMode::GlTriangles, vao_b, texture_b, &quad_b)?;
+        }
+        if mode == CompareMode::Wipe {
+            render.use_program(ProgramSelect::SelectTexturedQuad)?;
+            render.draw_textured_vao(DrawMode::GlLines, line_vao, line_texture, &line)?;
+        }
+
+
+        if input.key_pressed(&render, Key::M) {
+            let was_side_by_side = mode == CompareMode::SideBySide;
+            mode = mode.next();
+            if was_side_by_side || mode == CompareMode::SideBySide {
+                fit_camera_to_mode(&mut render, width, height, mode);
+            }
+            layout_changed = true;
+            diff_changed = mode.is_diff();
+        }
+        if mode.is_diff() && input.key_pressed(&render, Key::LeftBracket) {
+            full_scale *= 0.5;
+            diff_changed = true;
+            layout_changed = true;
+        }
+        if mode.is_diff() && input.key_pressed(&render, Key::RightBracket) {
+            full_scale *= 2.0;
+            diff_changed = true;
+            layout_changed = true;
+        }
+        if mode == CompareMode::Wipe && InputEdges::button_down(&render, MouseButton::Button2) {
+            let (world_x, _) = inspector::cursor_to_world(&render)?;
+            wipe = (world_x / width as f32).clamp(0.0, 1.0);
+            layout_changed = true;
+        }
+
+        if diff_changed {
+            update_image_texture(&render, diff_texture, &diff_image(a, b, mode, full_scale)?)?;
+            diff_changed = false;
+        }
+        if layout_changed {
+            (quad_a, quad_b) = mode_quads(width, height, mode, wipe);
+            render.update_vbo(vbo_a, &quad_a);
+            render.update_vbo(vbo_b, &quad_b);
+            line = wipe_line(width, height, wipe);
+            render.update_vbo(line_vbo, &line);
+            layout_changed = false;
+            title_changed = true;
+        }
+        if title_changed {
+            render.window.set_title(&comparison_title(mode, full_scale, &status));
+            title_changed = false;
+        }
+
+        if input.key_pressed(&render, Key::P) {
+            status = screenshot_status(&render);
+            title_changed = true;
+        }
+        if (program_a == ProgramSelect::SelectToneMappedQuad || program_b == ProgramSelect::SelectToneMappedQuad)
+            && let Some(tone_mapping) = adjust_tone_mapping(&mut render, &mut input) {
+            status = tone_mapping;
+            title_changed = true;
+        }
+
+        render.end_render_actions()?;
+
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use images::errors::ImageError;
+use render_context::errors::RenderError;
+
+
+#[derive(Debug)]
+pub enum ViewerError {
+    FileError(std::io::Error),
+    ImageError(ImageError),
+    RenderError(RenderError),
+}
+
+impl From<std::io::Error> for ViewerError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FileError(value)
+    }
+}
+impl From<ImageError> for ViewerError {
+    fn from(value: ImageError) -> Self {
+        Self::ImageError(value)
+    }
+}
+impl From<RenderError> for ViewerError {
+    fn from(value: RenderError) -> Self {
+        Self::RenderError(value)
+    }
+}
\ No newline at end of file
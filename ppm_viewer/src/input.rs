@@ -0,0 +1,45 @@
+use render_context::glfw::{Action, Key, MouseButton};
+use render_context::render::Render;
+
+use std::collections::HashMap;
+
+
+// the render loop only flushes the events it knows about, so the viewers poll key and button state
+// and remember the last frame's state to react once per press
+pub struct InputEdges {
+    keys_down:HashMap<Key, bool>,
+    buttons_down:HashMap<MouseButton, bool>,
+}
+
+impl InputEdges {
+    pub fn new() -> InputEdges {
+        InputEdges { keys_down:HashMap::new(), buttons_down:HashMap::new() }
+    }
+
+    pub fn key_down(render:&Render, key:Key) -> bool {
+        render.window.window.get_key(key) == Action::Press
+    }
+
+    pub fn button_down(render:&Render, button:MouseButton) -> bool {
+        render.window.window.get_mouse_button(button) == Action::Press
+    }
+
+    // true on the first frame the key is held
+    pub fn key_pressed(&mut self, render:&Render, key:Key) -> bool {
+        let down = InputEdges::key_down(render, key);
+        let was_down = self.keys_down.insert(key, down).unwrap_or(false);
+        down && !was_down
+    }
+
+    pub fn button_pressed(&mut self, render:&Render, button:MouseButton) -> bool {
+        let down = InputEdges::button_down(render, button);
+        let was_down = self.buttons_down.insert(button, down).unwrap_or(false);
+        down && !was_down
+    }
+
+    pub fn button_released(&mut self, render:&Render, button:MouseButton) -> bool {
+        let down = InputEdges::button_down(render, button);
+        let was_down = self.buttons_down.insert(button, down).unwrap_or(false);
+        !down && was_down
+    }
+}
\ No newline at end of file
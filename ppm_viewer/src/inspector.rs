@@ -0,0 +1,245 @@
+use render_context::render::Render;
+use render_context::enums::{DrawMode, ProgramSelect, UniformType};
+use render_context::enums::{TextureData, TextureFilter, TextureFormat};
+use render_context::glfw::MouseButton;
+use matrices::matrix::Matrix;
+
+use images::image::{Image, ImageData};
+
+use crate::errors::ViewerError;
+use crate::input::InputEdges;
+
+
+// screen pixels per image pixel at which the texture switches to nearest filtering and the grid appears
+pub const PIXEL_GRID_ZOOM : f32 = 8.0;
+pub const PIXEL_GRID_OPACITY : f32 = 0.4;
+
+
+// per channel statistics of the normalised values in a selected rectangle, corners inclusive
+#[derive(Debug, Clone)]
+pub struct RegionStats {
+    pub top_left:(usize, usize),
+    pub bottom_right:(usize, usize),
+    pub pixels:usize,
+    pub mean:Vec<f32>,
+    pub min:Vec<f32>,
+    pub max:Vec<f32>,
+    pub std_dev:Vec<f32>,
+}
+
+impl RegionStats {
+    pub fn new(image:&Image, corner_a:(usize, usize), corner_b:(usize, usize)) -> RegionStats {
+        let top_left = (corner_a.0.min(corner_b.0), corner_a.1.min(corner_b.1));
+        let bottom_right = (corner_a.0.max(corner_b.0).min(image.width - 1), corner_a.1.max(corner_b.1).min(image.height - 1));
+        let channels = image.channels();
+
+        let mut sum = vec![0.0f64; channels];
+        let mut sum_squares = vec![0.0f64; channels];
+        let mut min = vec![f32::INFINITY; channels];
+        let mut max = vec![f32::NEG_INFINITY; channels];
+        for y in top_left.1..=bottom_right.1 {
+            for x in top_left.0..=bottom_right.0 {
+                for (c, value) in image.pixel(x, y).into_iter().enumerate() {
+                    sum[c] += value as f64;
+                    sum_squares[c] += (value as f64).powi(2);
+                    min[c] = min[c].min(value);
+                    max[c] = max[c].max(value);
+                }
+            }
+        }
+
+        let pixels = (bottom_right.0 - top_left.0 + 1) * (bottom_right.1 - top_left.1 + 1);
+        let mean : Vec<f64> = sum.iter().map(|s| s / pixels as f64).collect();
+        let std_dev = (0..channels)
+            .map(|c| (sum_squares[c] / pixels as f64 - mean[c]*mean[c]).max(0.0).sqrt() as f32)
+            .collect();
+
+        RegionStats {
+            top_left,
+            bottom_right,
+            pixels,
+            mean:mean.into_iter().map(|m| m as f32).collect(),
+            min,
+            max,
+            std_dev,
+        }
+    }
+}
+
+impl std::fmt::Display for RegionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "region {:?} to {:?}, {} pixels", self.top_left, self.bottom_right, self.pixels)?;
+        writeln!(f, "    mean    {}", format_values(&self.mean))?;
+        writeln!(f, "    min     {}", format_values(&self.min))?;
+        writeln!(f, "    max     {}", format_values(&self.max))?;
+        write!(f, "    std dev {}", format_values(&self.std_dev))
+    }
+}
+
+
+pub fn format_values(values:&[f32]) -> String {
+    let values : Vec<String> = values.iter().map(|v| format!("{:.4}", v)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+// the stored samples, before normalising
+pub fn raw_pixel(image:&Image, x:usize, y:usize) -> String {
+    let channels = image.channels();
+    let i = (y*image.width + x) * channels;
+    match &image.data {
+        ImageData::U8(data) => format!("{:?}", &data[i..i+channels]),
+        ImageData::U16(data) => format!("{:?}", &data[i..i+channels]),
+        ImageData::F32(data) => format_values(&data[i..i+channels]),
+    }
+}
+
+
+// the window cursor in world units, with pan and zoom undone
+pub fn cursor_to_world(render:&Render) -> Result<(f32, f32), ViewerError> {
+    let (width, height) = (render.window.width()?, render.window.height()?);
+    let (view_x, view_y) = render.camera.screen_to_view(render.window.last_cursor_pos, width, height);
+    Ok((view_x - render.camera.pan_xyz.0, view_y - render.camera.pan_xyz.1))
+}
+
+// the image pixel under the window cursor, the quad has one world unit per pixel with its top left on the origin
+pub fn cursor_to_pixel(render:&Render, image:&Image) -> Result<Option<(usize, usize)>, ViewerError> {
+    let (world_x, world_y) = cursor_to_world(render)?;
+    let (x, y) = (world_x.floor(), (-world_y).floor());
+    match x >= 0.0 && y >= 0.0 && (x as usize) < image.width && (y as usize) < image.height {
+        true => Ok(Some((x as usize, y as usize))),
+        false => Ok(None),
+    }
+}
+
+
+// xyz uv line segments around the selected pixels, drawn in front of the image
+fn outline_lines(top_left:(usize, usize), bottom_right:(usize, usize)) -> Matrix<f32> {
+    let (left, top) = (top_left.0 as f32, -(top_left.1 as f32));
+    let (right, bottom) = ((bottom_right.0 + 1) as f32, -((bottom_right.1 + 1) as f32));
+    Matrix::from_2darray([
+        [ left,    top, -1.0, 0.5, 0.5],
+        [right,    top, -1.0, 0.5, 0.5],
+        [right,    top, -1.0, 0.5, 0.5],
+        [right, bottom, -1.0, 0.5, 0.5],
+        [right, bottom, -1.0, 0.5, 0.5],
+        [ left, bottom, -1.0, 0.5, 0.5],
+        [ left, bottom, -1.0, 0.5, 0.5],
+        [ left,    top, -1.0, 0.5, 0.5],
+    ])
+}
+
+
+// hovering shows the pixel under the cursor in the window title
+// dragging with the right button selects a rectangle and shows its statistics in the title too
+// status is the last thing the viewer did, like saving a screenshot, shown at the end of the title
+pub struct PixelInspector {
+    pub hovered:Option<(usize, usize)>,
+    pub drag_start:Option<(usize, usize)>,
+    pub selection:Option<RegionStats>,
+    pub nearest:bool,
+    pub status:String,
+    outline:Matrix<f32>,
+    outline_vao:u32,
+    outline_vbo:u32,
+    outline_texture:u32,
+}
+
+impl PixelInspector {
+    pub fn new(render:&Render) -> Result<PixelInspector, ViewerError> {
+        let outline = outline_lines((0, 0), (0, 0));
+        let (outline_vao, outline_vbo) = render.create_vao_vbo(&outline)?;
+        let outline_texture = render.create_texture(1, 1, TextureFormat::Rgb,
+            TextureData::U8(&[255, 255, 0]), TextureFilter::Nearest)?;
+        Ok(PixelInspector {
+            hovered:None,
+            drag_start:None,
+            selection:None,
+            nearest:false,
+            status:String::new(),
+            outline,
+            outline_vao,
+            outline_vbo,
+            outline_texture,
+        })
+    }
+
+    pub fn update(&mut self, render:&mut Render, image:&Image) -> Result<(), ViewerError> {
+        let hovered = cursor_to_pixel(render, image)?;
+        // still true on the frame the drag ends, so the title shows the final selection
+        let dragging = self.drag_start.is_some();
+
+        match (InputEdges::button_down(render, MouseButton::Button2), self.drag_start) {
+            (true, None) => self.drag_start = hovered,
+            (true, Some(start)) => if let Some(end) = hovered {
+                self.select(render, image, start, end);
+            },
+            (false, Some(start)) => {
+                if let Some(end) = hovered {
+                    self.select(render, image, start, end);
+                }
+                self.drag_start = None;
+            },
+            (false, None) => {},
+        }
+
+        if hovered != self.hovered || dragging {
+            self.hovered = hovered;
+            render.window.set_title(&self.title(image));
+        }
+        Ok(())
+    }
+
+    pub fn set_status(&mut self, render:&mut Render, image:&Image, status:String) {
+        self.status = status;
+        render.window.set_title(&self.title(image));
+    }
+
+    fn select(&mut self, render:&Render, image:&Image, start:(usize, usize), end:(usize, usize)) {
+        let stats = RegionStats::new(image, start, end);
+        self.outline = outline_lines(stats.top_left, stats.bottom_right);
+        render.update_vbo(self.outline_vbo, &self.outline);
+        self.selection = Some(stats);
+    }
+
+    pub fn title(&self, image:&Image) -> String {
+        let mut title = format!("ppm_viewer {}x{}", image.width, image.height);
+        if let Some((x, y)) = self.hovered {
+            title += &format!("  ({}, {}) raw {} rgb {}", x, y, raw_pixel(image, x, y), format_values(&image.rgb(x, y)));
+        }
+        if let Some(selection) = &self.selection {
+            title += &format!("  | {:?}-{:?} mean {} min {} max {} std dev {}",
+                selection.top_left, selection.bottom_right, format_values(&selection.mean),
+                format_values(&selection.min), format_values(&selection.max), format_values(&selection.std_dev));
+        }
+        if !self.status.is_empty() {
+            title += &format!("  | {}", self.status);
+        }
+        title
+    }
+
+    // nearest filtering and a grid over each pixel once pixels are large enough to pick out, call after use_program
+    pub fn set_pixel_grid(&mut self, render:&Render, program:ProgramSelect, texture:u32, image:&Image) -> Result<(), ViewerError> {
+        let screen_pixels_per_pixel = render.window.height()? as f32 / (2.0 * render.camera.zoom);
+        let nearest = screen_pixels_per_pixel >= PIXEL_GRID_ZOOM;
+        if nearest != self.nearest {
+            self.nearest = nearest;
+            render.set_texture_filter(texture, if nearest {TextureFilter::Nearest} else {TextureFilter::Linear});
+        }
+
+        let opacity = if nearest {PIXEL_GRID_OPACITY} else {0.0};
+        render.set_uniform(program, "grid_opacity", UniformType::Float, Matrix::from_scalar(opacity))?;
+        render.set_uniform(program, "image_size", UniformType::Vec3,
+            Matrix::from_1darray([image.width as f32, image.height as f32, 0.0]))?;
+        Ok(())
+    }
+
+    pub fn draw_selection(&self, render:&Render) -> Result<(), ViewerError> {
+        if self.selection.is_none() {
+            return Ok(());
+        }
+        render.use_program(ProgramSelect::SelectTexturedQuad)?;
+        render.set_uniform(ProgramSelect::SelectTexturedQuad, "grid_opacity", UniformType::Float, Matrix::from_scalar(0.0))?;
+        render.draw_textured_vao(DrawMode::GlLines, self.outline_vao, self.outline_texture, &self.outline)?;
+        Ok(())
+    }
+}
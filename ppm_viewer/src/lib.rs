@@ -4,149 +4,212 @@
 // #![windows_subsystem = "windows"]
 
 
+pub mod errors;
+pub mod input;
+pub mod inspector;
+pub mod comparison;
 
 
-
-use shaders::{ProgramHolder, ProgramType};
 use render_context::render::Render;
-use opengl::{GlSettings, WithObject};
+use render_context::enums::{DrawMode, ProgramSelect};
+use render_context::enums::{TextureData, TextureFilter, TextureFormat};
+use render_context::glfw::Key;
 //use matrices::_tests::matrix_as_1_array::Matrix;
 use matrices::matrix::Matrix;
 
+use images::image::{ChannelLayout, Image, ImageData};
+use images::formats;
+use images::png;
+
+use crate::errors::ViewerError;
+use crate::input::InputEdges;
+use crate::inspector::PixelInspector;
 
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 
+// xyz uv vertices of two triangles covering the image, one world unit per pixel
+// the top left corner sits on the origin and rows run down the y axis
+pub fn image_quad(width:usize, height:usize) -> Matrix<f32> {
+    image_quad_columns(width, height, 0.0, 0.0, 1.0)
+}
 
-pub fn square_top_left(side_len:f32, xyz:[f32;3], rgb:Vec<&str>, a:f32) -> Matrix<f32> {
-    let r : f32 = rgb[0].trim().parse().unwrap();
-    let g : f32 = rgb[1].trim().parse().unwrap();
-    let b : f32 = rgb[2].trim().parse().unwrap();
-    //println!("{}, {}, {}", r, g, b);
+// the columns between the fractions from and to of the width, shifted right by offset_x world units
+pub fn image_quad_columns(width:usize, height:usize, offset_x:f32, from:f32, to:f32) -> Matrix<f32> {
+    let (w, h) = (width as f32, height as f32);
+    let (left, right) = (offset_x + from*w, offset_x + to*w);
     Matrix::from_2darray([
-        [xyz[0]         , xyz[1],          xyz[2], r/256.0, g/256.0, b/256.0, a],
-        [xyz[0]         , xyz[1]-side_len, xyz[2], r/256.0, g/256.0, b/256.0, a],
-        [xyz[0]+side_len, xyz[1]-side_len, xyz[2], r/256.0, g/256.0, b/256.0, a],
-        [xyz[0]+side_len, xyz[1],          xyz[2], r/256.0, g/256.0, b/256.0, a],
-        [xyz[0]+side_len, xyz[1]-side_len, xyz[2], r/256.0, g/256.0, b/256.0, a],
-        [xyz[0]         , xyz[1],          xyz[2], r/256.0, g/256.0, b/256.0, a],
+        [ left,  0.0, 0.0, from, 0.0],
+        [ left,   -h, 0.0, from, 1.0],
+        [right,   -h, 0.0,   to, 1.0],
+        [right,  0.0, 0.0,   to, 0.0],
+        [right,   -h, 0.0,   to, 1.0],
+        [ left,  0.0, 0.0, from, 0.0],
     ])
 }
 
+// greys are expanded to rgb so the textured quad shader can sample every layout the same way
+fn texture_image(image:&Image) -> (Image, TextureFormat) {
+    let image = match image.layout {
+        ChannelLayout::Grey => image.with_layout(ChannelLayout::Rgb),
+        ChannelLayout::GreyAlpha => image.with_layout(ChannelLayout::Rgba),
+        _ => image.clone(),
+    };
+    let format = match image.layout {
+        ChannelLayout::Rgba => TextureFormat::Rgba,
+        _ => TextureFormat::Rgb,
+    };
+    (image, format)
+}
 
+fn texture_data(image:&Image) -> TextureData<'_> {
+    match &image.data {
+        ImageData::U8(data) => TextureData::U8(data),
+        ImageData::U16(data) => TextureData::U16(data),
+        ImageData::F32(data) => TextureData::F32(data),
+    }
+}
 
+pub fn create_image_texture(render:&Render, image:&Image, filter:TextureFilter) -> Result<u32, ViewerError> {
+    let (image, format) = texture_image(image);
+    Ok(render.create_texture(image.width, image.height, format, texture_data(&image), filter)?)
+}
 
+// the texture must have been created from an image of the same size and layout
+pub fn update_image_texture(render:&Render, texture:u32, image:&Image) -> Result<(), ViewerError> {
+    let (image, format) = texture_image(image);
+    Ok(render.update_texture(texture, image.width, image.height, format, texture_data(&image))?)
+}
 
-pub fn view_ppm_from_path(ppm_path:&'static str) {
-    let ppm = fs::read_to_string(ppm_path).expect("failed to read ppm file");
-    //println!("{}", ppm);
-    view_ppm_from_text(ppm);
+// centres the image and zooms out until all of it is in view
+pub fn fit_camera_to_image(render:&mut Render, width:usize, height:usize) {
+    let (w, h) = (width as f32, height as f32);
+    render.camera.pan_xyz = (-w/2.0, h/2.0, 0.0);
+    render.camera.zoom = 0.55 * f32::max(w, h);
 }
 
-pub fn view_ppm_from_text(ppm_text:String) {
-    let ppm_text_lines : Vec<&str> = ppm_text.as_str().split("\r\n").collect();
 
-    assert_eq!(ppm_text_lines[0], "P3");
-    let line2 : Vec<&str> = ppm_text_lines[1].split(" ").collect();
-    let (width, height) : (usize, usize) = (line2[0].trim().parse().unwrap(), line2[1].trim().parse().unwrap());
-    let max_colour : f32 = ppm_text_lines[2].trim().parse().unwrap();
 
 
 
-    let mut render = Render::default();
-    render.setup_render();
+// float images hold unbounded radiance and go through the same tone mapping as offline output
+pub fn image_program(image:&Image) -> ProgramSelect {
+    match image.data {
+        ImageData::F32(_) => ProgramSelect::SelectToneMappedQuad,
+        _ => ProgramSelect::SelectTexturedQuad,
+    }
+}
 
 
-    //let mut squares_matrix = Matrix2d::new_empty(0, 7);
-    let mut squares_matrices = vec![];
-    let a = vec![(4, -1.0)];
-    let side_length = 1.0;
-    let mut squares_matrix = Matrix::new_empty(vec![0, 7]);
-    //squares_matrix.array.pop();
-    for row in 0..height {
-        for col in 0..width {
-            //if squares_matrix.array.len() == 25 {
-            //if squares_matrix.array.len() == 1+16*3 {
-            if squares_matrix.array.len() == 1+20*3 {
-
-                let (t_vao, t_vbo) = render.create_vao_vbo(&squares_matrix);
-                squares_matrices.push((squares_matrix, t_vao, t_vbo));
-
-                squares_matrix = Matrix::new_empty(vec![0, 7]);
-                //squares_matrix.array.pop();
-            }
-            if true {
-                let x = ppm_text_lines[3 + row*width + col].split(" ").collect();
-                let mat = square_top_left(
-                    side_length,
-                    [row as f32, col as f32, 0.0],
-                    x,
-                    1.0);
-                    //println!("m {:?}", mat);
-                    //println!("");    
-                squares_matrix = squares_matrix.expand_along_dims(mat).expect("failed to expand matrix of squares")
-            }
-        }
+
+// saves what is currently drawn in the window as a png in the working directory
+pub fn save_screenshot(render:&Render) -> Result<String, ViewerError> {
+    let (width, height, pixels) = render.read_framebuffer()?;
+    let screenshot = Image::from_data(width, height, ChannelLayout::Rgba, ImageData::U8(pixels))?;
+
+    let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0,
+    };
+    let path = format!("screenshot_{}.png", seconds);
+    png::save_png(&screenshot, &path)?;
+    Ok(path)
+}
+
+// what to tell the user after p, a failed screenshot (of a minimised window, say) doesn't close the viewer
+pub fn screenshot_status(render:&Render) -> String {
+    match save_screenshot(render) {
+        Ok(path) => format!("saved {}", path),
+        Err(err) => format!("screenshot failed {:?}", err),
     }
+}
 
-    //squares_matrix.array.remove(0);
-    //println!("b {:?} {}", squares_matrix.nrows, squares_matrix.ncols);
-    //println!("{:?}", squares_matrix);
-
-    //for (i, j) in squares_matrix.array.iter().enumerate() {
-    //    println!("{:?}", j);
-    //    if i%3==2 {
-    //        println!("");
-    //        //let a = true;
-    //        //let a = match a {
-    //        //    true =>Err("a"),
-    //        //    false =>Ok("a"),
-    //        //}.unwrap();
-    //    }
-    //}
-
-    // first line is P3, denotes it's RBG in ASCII
-    // second line is width by height
-    // third line is max colour value
-    // beyond that is colours, iterating:
-    //      for i in width {
-    //          for j in height {
-    //              r, g, b
-    //          }
-    //      }
-
-    println!("a {}", squares_matrices.len());
-
-    println!("t");
-    let test = square_top_left(5.0, [0.0, 9.0, 0.0], vec!["128", "250", "14"], 1.0);
-    let (test_vao, _) = render.create_vao_vbo(&test);
 
-    while !render.render_over() {
-        
-        render.begin_render_actions();
 
+// t cycles the tone map operator, - and = step the exposure by a third of a stop
+// returns the new settings to show when any of them changed
+pub fn adjust_tone_mapping(render:&mut Render, input:&mut InputEdges) -> Option<String> {
+    let mut changed = false;
+    if input.key_pressed(render, Key::T) {
+        render.tone_mapping.operator = render.tone_mapping.operator.next();
+        changed = true;
+    }
+    if input.key_pressed(render, Key::Minus) {
+        render.tone_mapping.exposure -= 1.0/3.0;
+        changed = true;
+    }
+    if input.key_pressed(render, Key::Equal) {
+        render.tone_mapping.exposure += 1.0/3.0;
+        changed = true;
+    }
+    match changed {
+        true => Some(format!("tone mapping {:?}, exposure {:+.2} stops", render.tone_mapping.operator, render.tone_mapping.exposure)),
+        false => None,
+    }
+}
 
-        //render.camera.zoom -= 0.001;
 
 
+// the decoder is picked from the file signature, so any extension (or none) works
+pub fn view_image_from_path(image_path:&str) -> Result<(), ViewerError> {
+    let bytes = fs::read(image_path)?;
+    view_image_from_bytes(&bytes)
+}
 
+pub fn view_image_from_bytes(image_bytes:&[u8]) -> Result<(), ViewerError> {
+    let image = formats::decode_image(image_bytes)?;
+    view_image(&image)
+}
 
-        
-        render.use_program(ProgramType::SimpleOrthographic);
-        //render.use_program(ProgramType::BlinnPhongOrthographic);
+pub fn view_ppm_from_path(ppm_path:&str) -> Result<(), ViewerError> {
+    view_image_from_path(ppm_path)
+}
 
-        for square in &squares_matrices {
-            render.draw_vao(GlSettings::GlTriangles, square.1, &square.0);
-        }
-        //render.draw_vao(GlSettings::GlTriangles, t_vao, &squares_matrix);
-        //render.draw_vao(GlSettings::GlTriangles, test_vao, &test);
+pub fn view_ppm_from_text(ppm_text:String) -> Result<(), ViewerError> {
+    view_image_from_bytes(ppm_text.as_bytes())
+}
 
+pub fn view_image(image:&Image) -> Result<(), ViewerError> {
 
+    let mut render = Render::default()?;
+    render.setup_render();
 
-        //drop(with_vao);
-        //drop(with_program);
-        render.end_render_actions();
 
-    }
+    let quad = image_quad(image.width, image.height);
+    let (quad_vao, _) = render.create_vao_vbo(&quad)?;
+    let texture = create_image_texture(&render, image, TextureFilter::Linear)?;
+    fit_camera_to_image(&mut render, image.width, image.height);
+
+    let program = image_program(image);
+
+    let mut input = InputEdges::new();
+    let mut inspector = PixelInspector::new(&render)?;
 
+    while !render.render_over() {
+
+        render.begin_render_actions()?;
+
+
+        render.use_program(program)?;
+        inspector.set_pixel_grid(&render, program, texture, image)?;
+        render.draw_textured_vao(DrawMode::GlTriangles, quad_vao, texture, &quad)?;
+        inspector.draw_selection(&render)?;
+        inspector.update(&mut render, image)?;
+
+        // p saves a screenshot, once per press
+        if input.key_pressed(&render, Key::P) {
+            let status = screenshot_status(&render);
+            inspector.set_status(&mut render, image, status);
+        }
+        if program == ProgramSelect::SelectToneMappedQuad
+            && let Some(status) = adjust_tone_mapping(&mut render, &mut input) {
+            inspector.set_status(&mut render, image, status);
+        }
+
+        render.end_render_actions()?;
+
+    }
 
+    Ok(())
 }
\ No newline at end of file
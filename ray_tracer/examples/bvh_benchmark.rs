@@ -0,0 +1,84 @@
+// times closest hit queries against a generated triangle soup, through a bvh and a linear list
+// cargo run --release --example bvh_benchmark [triangles]
+
+use ray_tracer::bvh::Bvh;
+use ray_tracer::hittable::{HitRecord, Hittable, HittableList};
+use ray_tracer::material::{Material, PreviewShading, Scatter};
+use ray_tracer::primitives::Triangle;
+use ray_tracer::ray::Ray;
+use ray_tracer::rng::Pcg32;
+use ray_tracer::vec3::{Point, Vec3};
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+
+const RAYS : usize = 20_000;
+// the linear list is only timed on this many rays, it is far too slow for all of them on big soups
+const LINEAR_RAYS : usize = 500;
+
+
+struct Absorb;
+
+impl Material for Absorb {
+    fn scatter(&self, _ray:&Ray, _hit:&HitRecord<'_>, _rng:&mut Pcg32) -> Option<Scatter> {
+        None
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading::new(Vec3::zero())
+    }
+}
+
+
+// small triangles scattered through a cube of side 2 about the origin
+fn triangle_soup(count:usize, rng:&mut Pcg32) -> HittableList {
+    let material : Arc<dyn Material> = Arc::new(Absorb);
+    let mut list = HittableList::new();
+    for _ in 0..count {
+        let center = Point::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+        let size = 0.05;
+        let a = center + rng.unit_vector() * size;
+        let b = center + rng.unit_vector() * size;
+        let c = center + rng.unit_vector() * size;
+        list.add(Arc::new(Triangle::new(a, b, c, material.clone())));
+    }
+    list
+}
+
+// rays from a sphere around the soup towards random points inside it
+fn rays(count:usize, rng:&mut Pcg32) -> Vec<Ray> {
+    (0..count).map(|_| {
+        let origin = rng.unit_vector() * 4.0;
+        let target = Point::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+        Ray::new(origin, target - origin)
+    }).collect()
+}
+
+// the time per ray and how many rays hit
+fn trace(scene:&dyn Hittable, rays:&[Ray]) -> (Duration, usize) {
+    let start = Instant::now();
+    let hits = rays.iter().filter(|ray| scene.hit(ray, 0.001, f32::INFINITY).is_some()).count();
+    (start.elapsed() / rays.len() as u32, hits)
+}
+
+
+fn main() {
+    let triangles = std::env::args().nth(1).and_then(|count| count.parse().ok()).unwrap_or(100_000);
+    let mut rng = Pcg32::new(7, 0);
+    let soup = triangle_soup(triangles, &mut rng);
+    let rays = rays(RAYS, &mut rng);
+
+    let start = Instant::now();
+    let bvh = Bvh::from(soup.clone());
+    let build_time = start.elapsed();
+    println!("{} triangles, bvh of {} nodes and depth {} built in {:?}", triangles, bvh.nodes.len(), bvh.depth(), build_time);
+
+    let (bvh_time, hit_count) = trace(&bvh, &rays);
+    let (linear_time, _) = trace(&soup, &rays[..LINEAR_RAYS]);
+
+    println!("{} of {} rays hit", hit_count, rays.len());
+    println!("bvh    {:?} per ray", bvh_time);
+    println!("linear {:?} per ray", linear_time);
+    println!("speed up {:.1}x", linear_time.as_secs_f64() / bvh_time.as_secs_f64());
+}
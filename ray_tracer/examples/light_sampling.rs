@@ -0,0 +1,29 @@
+// renders the cornell box with and without next event estimation at the same samples per pixel,
+// to see how much less noise sampling the lights leaves
+// cargo run --release --example light_sampling -- <output directory>
+
+use ray_tracer::errors::RayTracerError;
+use ray_tracer::light::LightList;
+use ray_tracer::renderer::{render, save_render, RenderSettings};
+use ray_tracer::scene::{bundled_path, load_scene};
+
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+
+fn main() -> Result<(), RayTracerError> {
+    let Some(directory) = std::env::args().nth(1) else {
+        eprintln!("usage: light_sampling <output directory>");
+        std::process::exit(2);
+    };
+    std::fs::create_dir_all(&directory)?;
+
+    let scene = load_scene(&bundled_path("scenes/cornell.toml"))?;
+    let settings = RenderSettings { samples_per_pixel:16, ..scene.settings };
+    let cancel = AtomicBool::new(false);
+    for (name, lights) in [("cornell_light_sampling.png", &scene.lights), ("cornell_naive.png", &LightList::new(vec![]))] {
+        let image = render(&scene.world, lights, &scene.camera, &settings, &mut |_| {}, &cancel)?;
+        save_render(&image, &Path::new(&directory).join(name).to_string_lossy())?;
+    }
+    Ok(())
+}
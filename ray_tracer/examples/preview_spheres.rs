@@ -0,0 +1,28 @@
+// watch a few spheres converge in a window, left drag pans, right drag turns, scroll zooms and space pauses
+// cargo run --release --example preview_spheres
+
+use ray_tracer::bvh::Bvh;
+use ray_tracer::errors::RayTracerError;
+use ray_tracer::hittable::HittableList;
+use ray_tracer::light::LightList;
+use ray_tracer::material::{Dielectric, Lambertian, Metal};
+use ray_tracer::preview::preview;
+use ray_tracer::primitives::{Quad, Sphere};
+use ray_tracer::renderer::RenderSettings;
+use ray_tracer::vec3::{Colour, Point, Vec3};
+
+use std::sync::Arc;
+
+
+fn main() -> Result<(), RayTracerError> {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Quad::new(Point::new(-4.0, -3.0, -1.0), Vec3::new(8.0, 0.0, 0.0), Vec3::new(0.0, 6.0, 0.0),
+        Arc::new(Lambertian::new(Colour::splat(0.6))))));
+    world.add(Arc::new(Sphere::new(Point::new(-2.1, 0.0, 0.0), 1.0, Arc::new(Lambertian::new(Colour::new(0.7, 0.3, 0.2))))));
+    world.add(Arc::new(Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)))));
+    world.add(Arc::new(Sphere::new(Point::new(2.1, 0.0, 0.0), 1.0, Arc::new(Metal::new(Colour::new(0.8, 0.7, 0.5), 0.1)))));
+
+    let mut settings = RenderSettings::new(0, 0);
+    settings.max_depth = 8;
+    preview(&Bvh::from(world), &LightList::new(vec![]), &settings, 2)
+}
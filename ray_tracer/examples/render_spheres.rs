@@ -0,0 +1,60 @@
+// renders a few spheres with the tiled renderer, reporting progress as it goes
+// cargo run --release --example render_spheres -- <output.png|ppm|pfm|hdr>
+
+use ray_tracer::bvh::Bvh;
+use ray_tracer::camera::{Camera, CameraSettings};
+use ray_tracer::errors::RayTracerError;
+use ray_tracer::hittable::HittableList;
+use ray_tracer::light::LightList;
+use ray_tracer::material::{Dielectric, Ggx, Lambertian, Metal};
+use ray_tracer::primitives::Sphere;
+use ray_tracer::renderer::{render, save_render, RenderSettings};
+use ray_tracer::vec3::{Colour, Point, Vec3};
+
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+
+fn spheres() -> Bvh {
+    let mut world = HittableList::new();
+    world.add(Arc::new(Sphere::new(Point::new(0.0, -1000.0, 0.0), 1000.0, Arc::new(Lambertian::new(Colour::splat(0.5))))));
+    world.add(Arc::new(Sphere::new(Point::new(-2.1, 1.0, 0.0), 1.0, Arc::new(Lambertian::new(Colour::new(0.7, 0.3, 0.2))))));
+    world.add(Arc::new(Sphere::new(Point::new(0.0, 1.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)))));
+    world.add(Arc::new(Sphere::new(Point::new(2.1, 1.0, 0.0), 1.0, Arc::new(Metal::new(Colour::new(0.8, 0.7, 0.5), 0.05)))));
+    world.add(Arc::new(Sphere::new(Point::new(0.0, 0.4, 1.8), 0.4, Arc::new(Ggx::new(Colour::new(0.2, 0.4, 0.8), 0.3, 0.0)))));
+    Bvh::from(world)
+}
+
+fn main() -> Result<(), RayTracerError> {
+    let Some(output_path) = std::env::args().nth(1) else {
+        eprintln!("usage: render_spheres <output.png|ppm|pfm|hdr>");
+        std::process::exit(2);
+    };
+
+    // lit by the sky alone
+    let (world, lights) = (spheres(), LightList::new(vec![]));
+    let mut settings = RenderSettings::new(480, 270);
+    settings.samples_per_pixel = 128;
+    let camera = Camera::new(&CameraSettings {
+        look_from:Point::new(0.0, 2.0, 8.0),
+        look_at:Point::new(0.0, 0.8, 0.0),
+        up:Vec3::new(0.0, 1.0, 0.0),
+        vertical_fov:30.0,
+        aspect_ratio:settings.width as f32 / settings.height as f32,
+        aperture:0.1,
+        focus_distance:8.0,
+        shutter_open:0.0,
+        shutter_close:0.0,
+    });
+
+    let image = render(&world, &lights, &camera, &settings, &mut |progress| {
+        let eta = progress.eta.map_or(String::from("-"), |eta| format!("{:.1}s", eta.as_secs_f32()));
+        print!("\r{:>3}/{} tiles, eta {:>6}, {:.2} Mrays/s   ",
+            progress.tiles_done, progress.tiles_total, eta, progress.rays_per_second / 1e6);
+        let _ = std::io::stdout().flush();
+    }, &AtomicBool::new(false))?;
+    println!();
+
+    save_render(&image, &output_path)
+}
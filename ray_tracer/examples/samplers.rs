@@ -0,0 +1,39 @@
+// renders scenes/spheres.toml at 4 samples per pixel with each sampler, to compare the noise they leave
+// only camera rays come from the sampler and the bounces after them are random, so on a whole scene the
+// samplers differ by much less than on the pixel integrals of the sampler tests
+// cargo run --release --example samplers -- <output directory>
+
+use ray_tracer::errors::RayTracerError;
+use ray_tracer::renderer::{render, save_render, RenderSettings};
+use ray_tracer::sampler::Sampler;
+use ray_tracer::scene::{bundled_path, load_scene};
+
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+
+
+const SAMPLERS : [(&str, Sampler); 5] = [
+    ("independent", Sampler::Independent),
+    ("stratified", Sampler::Stratified),
+    ("halton", Sampler::Halton),
+    ("sobol", Sampler::Sobol),
+    ("blue_noise", Sampler::BlueNoise),
+];
+
+
+fn main() -> Result<(), RayTracerError> {
+    let Some(directory) = std::env::args().nth(1) else {
+        eprintln!("usage: samplers <output directory>");
+        std::process::exit(2);
+    };
+    std::fs::create_dir_all(&directory)?;
+
+    let scene = load_scene(&bundled_path("scenes/spheres.toml"))?;
+    let cancel = AtomicBool::new(false);
+    for (name, sampler) in SAMPLERS {
+        let settings = RenderSettings { width:240, height:135, samples_per_pixel:4, sampler, ..scene.settings };
+        let image = render(&scene.world, &scene.lights, &scene.camera, &settings, &mut |_| {}, &cancel)?;
+        save_render(&image, &Path::new(&directory).join(format!("sampler_{}.png", name)).to_string_lossy())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::ray::Ray;
+use crate::vec3::{Point, Vec3};
+
+
+// axis aligned bounding box, an empty box has min above max so it grows correctly in surrounding
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Aabb {
+    pub min:Point,
+    pub max:Point,
+}
+
+impl Aabb {
+    pub fn new(a:Point, b:Point) -> Aabb {
+        Aabb { min:a.min(b), max:a.max(b) }
+    }
+
+    pub fn empty() -> Aabb {
+        Aabb { min:Vec3::splat(f32::INFINITY), max:Vec3::splat(f32::NEG_INFINITY) }
+    }
+
+    // for unbounded shapes such as planes
+    pub fn infinite() -> Aabb {
+        Aabb { min:Vec3::splat(f32::NEG_INFINITY), max:Vec3::splat(f32::INFINITY) }
+    }
+
+    pub fn from_points(points:&[Point]) -> Aabb {
+        points.iter().fold(Aabb::empty(), |aabb, point| aabb.grow(*point))
+    }
+
+    pub fn grow(&self, point:Point) -> Aabb {
+        Aabb { min:self.min.min(point), max:self.max.max(point) }
+    }
+
+    pub fn surrounding(&self, other:&Aabb) -> Aabb {
+        Aabb { min:self.min.min(other.min), max:self.max.max(other.max) }
+    }
+
+    // flat boxes (axis aligned quads and triangles) are given some thickness so slab tests still hit them
+    pub fn padded(&self, delta:f32) -> Aabb {
+        let size = self.max - self.min;
+        let pad = size.map(|extent| if extent < delta {delta / 2.0} else {0.0});
+        Aabb { min:self.min - pad, max:self.max + pad }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.to_array().iter().chain(self.max.to_array().iter()).all(|v| v.is_finite())
+    }
+
+    pub fn centroid(&self) -> Point {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let size = self.size();
+        2.0 * (size.x*size.y + size.y*size.z + size.z*size.x)
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let size = self.size();
+        match (size.x >= size.y, size.x >= size.z, size.y >= size.z) {
+            (true, true, _) => 0,
+            (false, _, true) => 1,
+            _ => 2,
+        }
+    }
+
+    // slab test, the entry and exit distances of the ray clipped to (t_min, t_max)
+    pub fn hit_range(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<(f32, f32)> {
+        let (mut t_min, mut t_max) = (t_min, t_max);
+        for axis in 0..3 {
+            let inverse = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse;
+            if inverse < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // nan from a ray lying in a slab plane leaves the range untouched
+            if t0 > t_min { t_min = t0; }
+            if t1 < t_max { t_max = t1; }
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    pub fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> bool {
+        self.hit_range(ray, t_min, t_max).is_some()
+    }
+}
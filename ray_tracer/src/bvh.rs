@@ -0,0 +1,304 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+
+// surface area heuristic costs, relative to one primitive intersection
+const TRAVERSAL_COST : f32 = 1.0;
+const INTERSECTION_COST : f32 = 1.0;
+const SAH_BINS : usize = 12;
+// leaves never get bigger than this unless every centroid coincides
+const MAX_LEAF_SIZE : usize = 8;
+const STACK_SIZE : usize = 64;
+
+
+// interior nodes keep their left child straight after them and store the index of the right one in offset,
+// leaves store the first of count objects in offset
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BvhNode {
+    pub aabb:Aabb,
+    pub offset:u32,
+    pub count:u16,
+    pub axis:u8,
+}
+
+impl BvhNode {
+    pub fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+
+// a bounding volume hierarchy built with the binned surface area heuristic and flattened depth first
+// objects with infinite bounding boxes, such as planes, can't be split so they are tested on every ray
+#[derive(Clone, Default)]
+pub struct Bvh {
+    pub nodes:Vec<BvhNode>,
+    pub objects:Vec<Arc<dyn Hittable>>,
+    pub unbounded:Vec<Arc<dyn Hittable>>,
+}
+
+#[derive(Clone, Copy)]
+struct BuildObject {
+    aabb:Aabb,
+    centroid:Vec3,
+    index:usize,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    aabb:Aabb,
+    count:usize,
+}
+
+impl Bvh {
+    pub fn new(objects:Vec<Arc<dyn Hittable>>) -> Bvh {
+        let (bounded, unbounded) : (Vec<_>, Vec<_>) = objects.into_iter()
+            .partition(|object| object.bounding_box().is_finite());
+
+        let mut build : Vec<BuildObject> = bounded.iter().enumerate().map(|(index, object)| {
+            let aabb = object.bounding_box();
+            BuildObject { aabb, centroid:aabb.centroid(), index }
+        }).collect();
+
+        let mut nodes = Vec::with_capacity(2 * build.len());
+        if !build.is_empty() {
+            Bvh::build(&mut nodes, &mut build, 0);
+        }
+
+        // reorder the objects so every leaf refers to a contiguous run
+        let objects = build.iter().map(|object| bounded[object.index].clone()).collect();
+        Bvh { nodes, objects, unbounded }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.unbounded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn depth(&self) -> usize {
+        fn depth_from(nodes:&[BvhNode], index:usize) -> usize {
+            let node = &nodes[index];
+            match node.is_leaf() {
+                true => 1,
+                false => 1 + depth_from(nodes, index + 1).max(depth_from(nodes, node.offset as usize)),
+            }
+        }
+        if self.nodes.is_empty() {0} else {depth_from(&self.nodes, 0)}
+    }
+
+    // builds the subtree over objects, which start at first in the final object order, and returns its node index
+    fn build(nodes:&mut Vec<BvhNode>, objects:&mut [BuildObject], first:usize) -> usize {
+        let index = nodes.len();
+        let aabb = objects.iter().fold(Aabb::empty(), |aabb, object| aabb.surrounding(&object.aabb));
+        nodes.push(BvhNode { aabb, offset:first as u32, count:objects.len() as u16, axis:0 });
+
+        if objects.len() == 1 {
+            return index;
+        }
+
+        let centroids = objects.iter().fold(Aabb::empty(), |aabb, object| aabb.grow(object.centroid));
+        let split = Bvh::find_split(objects, &aabb, &centroids).map(|(axis, position)| {
+            (axis, partition(objects, |object| object.centroid[axis] < position))
+        });
+
+        let (axis, mid) = match split {
+            Some((axis, mid)) if mid > 0 && mid < objects.len() => (axis, mid),
+            // every centroid in the same place, halve the list so leaves stay small
+            _ if objects.len() > MAX_LEAF_SIZE => (centroids.longest_axis(), objects.len() / 2),
+            _ => return index,
+        };
+
+        let (left, right) = objects.split_at_mut(mid);
+        Bvh::build(nodes, left, first);
+        let right_index = Bvh::build(nodes, right, first + mid);
+        nodes[index] = BvhNode { aabb, offset:right_index as u32, count:0, axis:axis as u8 };
+        index
+    }
+
+    // the axis and centroid position of the cheapest binned split, None when a leaf is cheaper
+    fn find_split(objects:&[BuildObject], aabb:&Aabb, centroids:&Aabb) -> Option<(usize, f32)> {
+        let leaf_cost = INTERSECTION_COST * objects.len() as f32;
+        let parent_area = aabb.surface_area();
+        let mut best : Option<(usize, f32)> = None;
+        let mut best_cost = if objects.len() > MAX_LEAF_SIZE {f32::INFINITY} else {leaf_cost};
+
+        for axis in 0..3 {
+            let (low, high) = (centroids.min[axis], centroids.max[axis]);
+            if high - low <= f32::EPSILON * high.abs().max(low.abs()).max(1.0) {
+                continue;
+            }
+            let scale = SAH_BINS as f32 / (high - low);
+            let bin_of = |centroid:f32| (((centroid - low) * scale) as usize).min(SAH_BINS - 1);
+
+            let mut bins = [Bin { aabb:Aabb::empty(), count:0 }; SAH_BINS];
+            for object in objects {
+                let bin = &mut bins[bin_of(object.centroid[axis])];
+                bin.aabb = bin.aabb.surrounding(&object.aabb);
+                bin.count += 1;
+            }
+
+            // sweep from the right to get the area and count above every boundary
+            let mut right_area = [0.0; SAH_BINS];
+            let mut right_count = [0; SAH_BINS];
+            let mut sweep = Bin { aabb:Aabb::empty(), count:0 };
+            for bin in (1..SAH_BINS).rev() {
+                sweep.aabb = sweep.aabb.surrounding(&bins[bin].aabb);
+                sweep.count += bins[bin].count;
+                right_area[bin] = sweep.aabb.surface_area();
+                right_count[bin] = sweep.count;
+            }
+
+            let mut left = Bin { aabb:Aabb::empty(), count:0 };
+            for boundary in 1..SAH_BINS {
+                left.aabb = left.aabb.surrounding(&bins[boundary - 1].aabb);
+                left.count += bins[boundary - 1].count;
+                if left.count == 0 || right_count[boundary] == 0 {
+                    continue;
+                }
+                let cost = TRAVERSAL_COST + INTERSECTION_COST
+                    * (left.aabb.surface_area() * left.count as f32 + right_area[boundary] * right_count[boundary] as f32)
+                    / parent_area;
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = Some((axis, low + boundary as f32 / scale));
+                }
+            }
+        }
+        best
+    }
+}
+
+// moves everything matching the predicate to the front and returns how many did
+fn partition<T>(items:&mut [T], predicate:impl Fn(&T) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+impl From<HittableList> for Bvh {
+    fn from(list:HittableList) -> Bvh {
+        Bvh::new(list.objects)
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut t_closest = t_max;
+        for object in &self.unbounded {
+            if let Some(hit) = object.hit(ray, t_min, t_closest) {
+                t_closest = hit.t;
+                closest = Some(hit);
+            }
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let direction_negative = [ray.direction.x < 0.0, ray.direction.y < 0.0, ray.direction.z < 0.0];
+        let mut stack = [0usize; STACK_SIZE];
+        let mut stack_size = 1;
+
+        while stack_size > 0 {
+            stack_size -= 1;
+            let index = stack[stack_size];
+            let node = &self.nodes[index];
+            if !node.aabb.hit(ray, t_min, t_closest) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                let first = node.offset as usize;
+                for object in &self.objects[first..first + node.count as usize] {
+                    if let Some(hit) = object.hit(ray, t_min, t_closest) {
+                        t_closest = hit.t;
+                        closest = Some(hit);
+                    }
+                }
+                continue;
+            }
+
+            // push the far child first so the near one is visited next and shrinks t_closest sooner
+            let (left, right) = (index + 1, node.offset as usize);
+            let (near, far) = if direction_negative[node.axis as usize] {(right, left)} else {(left, right)};
+            stack[stack_size] = far;
+            stack[stack_size + 1] = near;
+            stack_size += 2;
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounded = self.nodes.first().map_or(Aabb::empty(), |node| node.aabb);
+        self.unbounded.iter().fold(bounded, |aabb, object| aabb.surrounding(&object.bounding_box()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{Lambertian, Material};
+    use crate::primitives::{Plane, Triangle};
+    use crate::rng::Pcg32;
+    use crate::vec3::{Colour, Point};
+
+    // small triangles scattered through a cube of side 2 about the origin
+    fn triangle_soup(count:usize, rng:&mut Pcg32) -> HittableList {
+        let material : Arc<dyn Material> = Arc::new(Lambertian::new(Colour::splat(0.5)));
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            let center = Point::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+            let [a, b, c] = [(); 3].map(|_| center + rng.unit_vector() * 0.05);
+            list.add(Arc::new(Triangle::new(a, b, c, material.clone())));
+        }
+        list
+    }
+
+    // rays from a sphere around the soup towards random points inside it
+    fn rays(count:usize, rng:&mut Pcg32) -> Vec<Ray> {
+        (0..count).map(|_| {
+            let origin = rng.unit_vector() * 4.0;
+            let target = Point::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+            Ray::new(origin, target - origin)
+        }).collect()
+    }
+
+    fn closest_hits(scene:&dyn Hittable, rays:&[Ray]) -> Vec<Option<f32>> {
+        rays.iter().map(|ray| scene.hit(ray, 0.001, f32::INFINITY).map(|hit| hit.t)).collect()
+    }
+
+    #[test]
+    fn bvh_finds_the_same_hits_as_a_linear_list() {
+        let mut rng = Pcg32::new(7, 0);
+        let mut soup = triangle_soup(2000, &mut rng);
+        let rays = rays(2000, &mut rng);
+        let bvh = Bvh::from(soup.clone());
+        let hits = closest_hits(&bvh, &rays);
+        assert_eq!(hits, closest_hits(&soup, &rays));
+        assert!(hits.iter().any(|hit| hit.is_some()) && hits.iter().any(|hit| hit.is_none()));
+
+        // unbounded objects sit outside the tree and are still hit
+        soup.add(Arc::new(Plane::new(Point::new(0.0, -0.5, 0.0), Vec3::new(0.0, 1.0, 0.0), Arc::new(Lambertian::new(Colour::splat(0.5))))));
+        assert_eq!(closest_hits(&Bvh::from(soup.clone()), &rays), closest_hits(&soup, &rays));
+    }
+
+    #[test]
+    fn empty_bvh_misses() {
+        let bvh = Bvh::new(vec![]);
+        assert!(bvh.is_empty());
+        assert!(bvh.hit(&Ray::new(Point::zero(), Vec3::new(0.0, 0.0, 1.0)), 0.001, f32::INFINITY).is_none());
+    }
+}
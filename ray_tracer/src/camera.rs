@@ -0,0 +1,156 @@
+use render_context::camera::Camera as RenderCamera;
+use matrices::matrix::Matrix;
+
+use crate::errors::RayTracerError;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::transform::Mat4;
+use crate::vec3::{Point, Vec3};
+
+
+// vertical_fov is in degrees, aperture is the lens diameter, 0 keeps everything in focus
+// rays are sent at random times over the shutter interval, an empty interval takes a still at shutter_open
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CameraSettings {
+    pub look_from:Point,
+    pub look_at:Point,
+    pub up:Vec3,
+    pub vertical_fov:f32,
+    pub aspect_ratio:f32,
+    pub aperture:f32,
+    pub focus_distance:f32,
+    pub shutter_open:f32,
+    pub shutter_close:f32,
+}
+
+impl CameraSettings {
+    pub fn new() -> CameraSettings {
+        CameraSettings {
+            look_from:Point::new(0.0, 0.0, 1.0),
+            look_at:Point::zero(),
+            up:Vec3::new(0.0, 1.0, 0.0),
+            vertical_fov:40.0,
+            aspect_ratio:16.0 / 9.0,
+            aperture:0.0,
+            focus_distance:1.0,
+            shutter_open:0.0,
+            shutter_close:0.0,
+        }
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> CameraSettings {
+        CameraSettings::new()
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+
+// rays are generated through a rectangle spanned by horizontal and vertical around center
+// for perspective cameras the rectangle is the focus plane and rays start on the lens,
+// for orthographic cameras the rectangle is the near plane and every ray points along forward
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Camera {
+    pub projection:Projection,
+    pub origin:Point,
+    pub center:Point,
+    pub horizontal:Vec3,
+    pub vertical:Vec3,
+    pub forward:Vec3,
+    pub lens_radius:f32,
+    pub shutter_open:f32,
+    pub shutter_close:f32,
+    u:Vec3,
+    v:Vec3,
+}
+
+impl Camera {
+    pub fn new(settings:&CameraSettings) -> Camera {
+        let half_height = (settings.vertical_fov.to_radians() / 2.0).tan();
+        let half_width = settings.aspect_ratio * half_height;
+
+        let forward = (settings.look_at - settings.look_from).unit();
+        let u = forward.cross(settings.up).unit();
+        let v = u.cross(forward);
+
+        Camera {
+            projection:Projection::Perspective,
+            origin:settings.look_from,
+            center:settings.look_from + forward * settings.focus_distance,
+            horizontal:u * (2.0 * half_width * settings.focus_distance),
+            vertical:v * (2.0 * half_height * settings.focus_distance),
+            forward,
+            lens_radius:settings.aperture / 2.0,
+            shutter_open:settings.shutter_open,
+            shutter_close:settings.shutter_close.max(settings.shutter_open),
+            u,
+            v,
+        }
+    }
+
+    // frames exactly what the window shows through the simple and blinn phong orthographic programs,
+    // by inverting the same world, camera and projection transforms render_context sets as uniforms
+    pub fn from_render_camera(camera:&RenderCamera, width:u32, height:u32) -> Result<Camera, RayTracerError> {
+        let world = Mat4::from_matrix(&Matrix::opengl_to_right_handed())?;
+        let view = Mat4::from_matrix(&camera.get_camera_transform()?)?;
+        let to_world = match (view * world).inverse() {
+            Some(inverse) => inverse,
+            None => return Err(RayTracerError::SingularMatrix),
+        };
+
+        // the projection keeps view space x and y inside the extent, nearer points have smaller z
+        let (half_width, half_height) = camera.view_extent(width, height);
+        let near = -(camera.render_distance as f32);
+        let horizontal = to_world.transform_vector(Vec3::new(2.0 * half_width, 0.0, 0.0));
+        let vertical = to_world.transform_vector(Vec3::new(0.0, 2.0 * half_height, 0.0));
+
+        Ok(Camera {
+            projection:Projection::Orthographic,
+            origin:to_world.transform_point(Point::new(0.0, 0.0, near)),
+            center:to_world.transform_point(Point::new(0.0, 0.0, near)),
+            horizontal,
+            vertical,
+            forward:to_world.transform_vector(Vec3::new(0.0, 0.0, 1.0)).unit(),
+            lens_radius:0.0,
+            shutter_open:0.0,
+            shutter_close:0.0,
+            u:horizontal.unit(),
+            v:vertical.unit(),
+        })
+    }
+
+    // s runs left to right and t bottom to top across the image, both from 0 to 1
+    pub fn get_ray(&self, s:f32, t:f32, rng:&mut Pcg32) -> Ray {
+        let lens = match self.projection {
+            Projection::Perspective => rng.in_unit_disk(),
+            Projection::Orthographic => Vec3::zero(),
+        };
+        // stills take no random number for the time, so they render as they did before there was a shutter
+        let time = match self.shutter_close > self.shutter_open {
+            true => rng.next_f32(),
+            false => 0.0,
+        };
+        self.ray_at(s, t, lens, time)
+    }
+
+    // the ray through s, t from lens, a point on the unit disk, at time from 0 to 1 across the shutter
+    pub fn ray_at(&self, s:f32, t:f32, lens:Vec3, time:f32) -> Ray {
+        let target = self.center + self.horizontal * (s - 0.5) + self.vertical * (t - 0.5);
+        let ray = match self.projection {
+            Projection::Perspective => {
+                let lens = lens * self.lens_radius;
+                let origin = self.origin + self.u * lens.x + self.v * lens.y;
+                Ray::new(origin, target - origin)
+            },
+            Projection::Orthographic => Ray::new(target, self.forward),
+        };
+        ray.with_time(self.shutter_open + (self.shutter_close - self.shutter_open) * time)
+    }
+}
@@ -0,0 +1,101 @@
+use images::errors::ImageError;
+use matrices::errors::MatrixError;
+use meshes::errors::MeshError;
+use render_context::errors::RenderError;
+
+
+// density grid files, line numbers are 1 based
+#[derive(Debug)]
+pub enum GridError {
+    FileError(std::io::Error),
+    InvalidHeader((usize, String)),
+    InvalidNumber((usize, String)),
+    NegativeDensity((usize, f32)),
+    // the number of densities the header asks for and the number found
+    WrongCount((usize, usize)),
+}
+
+// parse errors and unknown names carry the 1 based line and column they were found at, 0 when unknown
+#[derive(Debug)]
+pub enum SceneError {
+    ParseError((String, usize, usize)),
+    UnknownMaterial((String, usize, usize)),
+    UnknownTexture((String, usize, usize)),
+    InvalidDimensions((usize, usize)),
+    InvalidTransform((usize, usize)),
+    InvalidKeyframes((usize, usize)),
+    MeshError((String, MeshError)),
+    ImageError((String, ImageError)),
+    GridError((String, GridError)),
+    SerializeError(toml::ser::Error),
+}
+
+#[derive(Debug)]
+pub enum RayTracerError {
+    FileError(std::io::Error),
+    ImageError(ImageError),
+    MatrixShapeError(Vec<usize>),
+    MatrixError(MatrixError),
+    SingularMatrix,
+    // keyframes must be given, in increasing time
+    InvalidKeyframes,
+    RenderCancelled,
+    RenderError(RenderError),
+    SceneError(SceneError),
+    MeshError(MeshError),
+    GridError(GridError),
+}
+
+impl From<std::io::Error> for RayTracerError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FileError(value)
+    }
+}
+
+impl From<ImageError> for RayTracerError {
+    fn from(value: ImageError) -> Self {
+        Self::ImageError(value)
+    }
+}
+
+impl From<MatrixError> for RayTracerError {
+    fn from(value: MatrixError) -> Self {
+        Self::MatrixError(value)
+    }
+}
+
+impl From<RenderError> for RayTracerError {
+    fn from(value: RenderError) -> Self {
+        Self::RenderError(value)
+    }
+}
+
+impl From<SceneError> for RayTracerError {
+    fn from(value: SceneError) -> Self {
+        Self::SceneError(value)
+    }
+}
+
+impl From<MeshError> for RayTracerError {
+    fn from(value: MeshError) -> Self {
+        Self::MeshError(value)
+    }
+}
+
+impl From<GridError> for RayTracerError {
+    fn from(value: GridError) -> Self {
+        Self::GridError(value)
+    }
+}
+
+impl From<std::io::Error> for GridError {
+    fn from(value: std::io::Error) -> Self {
+        Self::FileError(value)
+    }
+}
+
+impl From<toml::ser::Error> for SceneError {
+    fn from(value: toml::ser::Error) -> Self {
+        Self::SerializeError(value)
+    }
+}
\ No newline at end of file
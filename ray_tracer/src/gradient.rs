@@ -0,0 +1,49 @@
+use std::io::Write;
+
+use images::errors::ImageError;
+use images::image::{ChannelLayout, Image};
+use images::netpbm::{NetpbmFormat, NetpbmWriter};
+
+use crate::vec3::Colour;
+
+
+// the original smoke test, a gradient streamed to ray_tracer_images/test.ppm a row at a time
+pub fn hello_ppm() -> Result<Image, ImageError> {
+
+    let img_width  = 256;
+    let img_height = 256;
+    let height_order = f32::log10(img_height as f32) as u32 as usize + 1;
+    //println!("{}, {}, {}, {}", f32::log10(img_height as f32), f32::log10(img_height as f32) as u32, f32::log10(img_height as f32) as u32 as usize, f32::log10(img_height as f32) as u32 as usize +1);
+
+    let mut image = Image::new_f32(img_width, img_height, ChannelLayout::Rgb);
+    let mut writer = NetpbmWriter::create("ray_tracer_images/test.ppm",
+                                        img_width, img_height,
+                                        NetpbmFormat::PixmapBinary, 255)?;
+    let mut row = Vec::with_capacity(img_width * 3);
+
+    for i in 0..img_height {
+        let lines_left = format!("{:>height_order$} lines left to write\r", writer.rows_left());
+        //let c = b.as_str();
+        //let a = vec![c, " lines left to write", "\r"].join("").as_bytes();
+        std::io::stdout().write_all(lines_left.as_bytes())?;
+        std::io::stdout().flush()?;
+        row.clear();
+        for j in 0..img_width {
+            let colour = Colour::new(
+                j as f32 / (img_width as f32 -1.0),
+                i as f32 / (img_width as f32 -1.0),
+                (i+j) as f32 / 256.0 / 1.35,
+            );
+
+            //println!("{:?}", colour);
+
+            image.set_rgb(j, i, colour.to_array());
+            row.extend(colour.to_array());
+        }
+        writer.write_row(&row)?;
+    }
+
+    writer.finish()?;
+
+    Ok(image)
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::vec3::{Point, Vec3};
+
+use std::sync::Arc;
+
+
+// the normal always faces against the incoming ray, front_face says whether that is the outward side
+// grazing rays, perpendicular to the normal, count as hitting the front
+#[derive(Clone, Copy)]
+pub struct HitRecord<'m> {
+    pub point:Point,
+    pub normal:Vec3,
+    pub t:f32,
+    pub front_face:bool,
+    pub u:f32,
+    pub v:f32,
+    pub material:&'m dyn Material,
+}
+
+impl<'m> HitRecord<'m> {
+    pub fn new(ray:&Ray, t:f32, outward_normal:Vec3, u:f32, v:f32, material:&'m dyn Material) -> HitRecord<'m> {
+        let front_face = ray.direction.dot(outward_normal) <= 0.0;
+        HitRecord {
+            point:ray.at(t),
+            normal:if front_face {outward_normal} else {-outward_normal},
+            t,
+            front_face,
+            u,
+            v,
+            material,
+        }
+    }
+}
+
+
+pub trait Hittable: Send + Sync {
+    // the closest hit with t in (t_min, t_max)
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>>;
+
+    fn bounding_box(&self) -> Aabb;
+}
+
+
+// a point on a surface, normal faces outward like the normal the surface's hits are made with
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SurfaceSample {
+    pub point:Point,
+    pub normal:Vec3,
+    pub u:f32,
+    pub v:f32,
+}
+
+// finite shapes that can be picked points on, so they can be sampled as lights
+pub trait Surface: Hittable {
+    fn area(&self) -> f32;
+
+    // a point uniformly distributed over the area
+    fn sample(&self, rng:&mut Pcg32) -> SurfaceSample;
+
+    fn material(&self) -> &dyn Material;
+}
+
+
+#[derive(Clone, Default)]
+pub struct HittableList {
+    pub objects:Vec<Arc<dyn Hittable>>,
+}
+
+impl HittableList {
+    pub fn new() -> HittableList {
+        HittableList { objects:vec![] }
+    }
+
+    pub fn add(&mut self, object:Arc<dyn Hittable>) {
+        self.objects.push(object);
+    }
+
+    pub fn clear(&mut self) {
+        self.objects.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let mut closest = None;
+        let mut t_closest = t_max;
+        for object in &self.objects {
+            if let Some(hit) = object.hit(ray, t_min, t_closest) {
+                t_closest = hit.t;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.objects.iter().fold(Aabb::empty(), |aabb, object| aabb.surrounding(&object.bounding_box()))
+    }
+}
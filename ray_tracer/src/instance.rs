@@ -0,0 +1,156 @@
+use crate::aabb::Aabb;
+use crate::errors::RayTracerError;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::transform::{AffineParts, Mat4};
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+
+// another hittable placed in the world by an affine transform, so one mesh can be drawn many times
+// rays are taken into object space without normalising their direction, which keeps t the same in both spaces
+// instances of emissive objects glow where rays find them but are not sampled as lights
+#[derive(Clone)]
+pub struct Instance {
+    pub object:Arc<dyn Hittable>,
+    pub transform:Mat4,
+    inverse:Mat4,
+    bounds:Aabb,
+}
+
+impl Instance {
+    pub fn new(object:Arc<dyn Hittable>, transform:Mat4) -> Result<Instance, RayTracerError> {
+        let inverse = match transform.inverse() {
+            Some(inverse) => inverse,
+            None => return Err(RayTracerError::SingularMatrix),
+        };
+        let bounds = Instance::transformed_bounds(&object.bounding_box(), &transform);
+        Ok(Instance { object, transform, inverse, bounds })
+    }
+
+    pub fn inverse(&self) -> &Mat4 {
+        &self.inverse
+    }
+
+    // the box around the eight transformed corners, which holds everything inside the original box
+    fn transformed_bounds(aabb:&Aabb, transform:&Mat4) -> Aabb {
+        if aabb.is_empty() || !aabb.is_finite() {
+            return *aabb;
+        }
+        let corners : Vec<Vec3> = (0..8).map(|corner| {
+            let pick = |axis:usize| if corner & (1 << axis) == 0 {aabb.min[axis]} else {aabb.max[axis]};
+            transform.transform_point(Vec3::new(pick(0), pick(1), pick(2)))
+        }).collect();
+        Aabb::from_points(&corners)
+    }
+}
+
+// the hit with object of ray taken into object space by inverse, brought back out to world space
+fn hit_transformed<'o>(object:&'o dyn Hittable, inverse:&Mat4, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'o>> {
+    let local = Ray::new(inverse.transform_point(ray.origin), inverse.transform_vector(ray.direction)).with_time(ray.time);
+    let hit = object.hit(&local, t_min, t_max)?;
+    // the inverse transpose keeps the normal facing against the ray, so front_face carries over
+    Some(HitRecord {
+        point:ray.at(hit.t),
+        normal:Mat4::transform_normal(inverse, hit.normal).unit(),
+        ..hit
+    })
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        hit_transformed(self.object.as_ref(), &self.inverse, ray, t_min, t_max)
+    }
+
+    // planes and other unbounded objects stay unbounded
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+
+
+// an object moving through keyframed transforms, a ray sees it where the keyframes either side of its time
+// blend to, and before the first keyframe or after the last it stays where that keyframe puts it
+// two keyframes move it linearly, transforms are blended as translation, rotation and scale,
+// so they should be made of only those
+#[derive(Clone)]
+pub struct MovingInstance {
+    pub object:Arc<dyn Hittable>,
+    keyframes:Vec<(f32, AffineParts)>,
+    bounds:Aabb,
+}
+
+impl MovingInstance {
+    // rotations are bounded at steps no bigger than this, in radians
+    const BOUNDS_STEP : f32 = 0.05;
+
+    // keyframes are times and the transforms at them, in increasing time
+    pub fn new(object:Arc<dyn Hittable>, keyframes:Vec<(f32, Mat4)>) -> Result<MovingInstance, RayTracerError> {
+        if keyframes.is_empty() || keyframes.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(RayTracerError::InvalidKeyframes);
+        }
+        let keyframes = keyframes.iter()
+            .map(|(time, transform)| AffineParts::from_mat4(transform).map(|parts| (*time, parts)).ok_or(RayTracerError::SingularMatrix))
+            .collect::<Result<Vec<_>, RayTracerError>>()?;
+        let bounds = MovingInstance::motion_bounds(&object.bounding_box(), &keyframes);
+        Ok(MovingInstance { object, keyframes, bounds })
+    }
+
+    pub fn linear(object:Arc<dyn Hittable>, start:Mat4, end:Mat4, start_time:f32, end_time:f32) -> Result<MovingInstance, RayTracerError> {
+        MovingInstance::new(object, vec![(start_time, start), (end_time, end)])
+    }
+
+    pub fn parts_at(&self, time:f32) -> AffineParts {
+        let next = self.keyframes.partition_point(|(keyframe_time, _)| *keyframe_time <= time);
+        match next {
+            0 => self.keyframes[0].1,
+            next if next == self.keyframes.len() => self.keyframes[next - 1].1,
+            next => {
+                let ((t0, a), (t1, b)) = (self.keyframes[next - 1], self.keyframes[next]);
+                a.lerp(&b, (time - t0) / (t1 - t0))
+            },
+        }
+    }
+
+    pub fn transform_at(&self, time:f32) -> Mat4 {
+        self.parts_at(time).to_mat4()
+    }
+
+    // the boxes around the object at steps through every segment, each grown by how far a corner
+    // turning through one step can bow out from the straight line between its ends
+    fn motion_bounds(aabb:&Aabb, keyframes:&[(f32, AffineParts)]) -> Aabb {
+        if aabb.is_empty() || !aabb.is_finite() {
+            return *aabb;
+        }
+        let bounds_at = |parts:&AffineParts| Instance::transformed_bounds(aabb, &parts.to_mat4());
+        let mut bounds = bounds_at(&keyframes[0].1);
+        for pair in keyframes.windows(2) {
+            let (a, b) = (pair[0].1, pair[1].1);
+            let steps = (a.rotation.angle_to(&b.rotation) / MovingInstance::BOUNDS_STEP).ceil().max(1.0) as usize;
+            let step_angle = a.rotation.angle_to(&b.rotation) / steps as f32;
+            let radius = [a.scale, b.scale].iter()
+                .map(|scale| (aabb.min.map(f32::abs).max(aabb.max.map(f32::abs)) * scale.map(f32::abs)).length())
+                .fold(0.0, f32::max);
+            let pad = Vec3::splat(radius * (1.0 / (step_angle / 2.0).cos() - 1.0));
+            for step in 1..=steps {
+                let stepped = bounds_at(&a.lerp(&b, step as f32 / steps as f32));
+                bounds = bounds.surrounding(&Aabb { min:stepped.min - pad, max:stepped.max + pad });
+            }
+        }
+        bounds
+    }
+}
+
+impl Hittable for MovingInstance {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let inverse = self.parts_at(ray.time).inverse_mat4();
+        hit_transformed(self.object.as_ref(), &inverse, ray, t_min, t_max)
+    }
+
+    // everywhere the object goes over all of its keyframes
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
@@ -0,0 +1,32 @@
+pub mod errors;
+
+// vectors and rays, convertible to and from the matrices render_context uses
+pub mod vec3;
+pub mod ray;
+pub mod rng;
+pub mod transform;
+
+// scene geometry and what it is made of
+pub mod aabb;
+pub mod hittable;
+pub mod primitives;
+pub mod mesh;
+pub mod bvh;
+pub mod instance;
+pub mod material;
+pub mod texture;
+pub mod light;
+pub mod medium;
+
+// where rays come from
+pub mod camera;
+pub mod sampler;
+
+// turning a scene into an image
+pub mod renderer;
+pub mod preview;
+
+// scenes read from and written to toml files
+pub mod scene;
+
+pub mod gradient;
\ No newline at end of file
@@ -0,0 +1,116 @@
+use images::image::luma;
+
+use crate::bvh::Bvh;
+use crate::hittable::{HitRecord, Hittable, Surface};
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::vec3::{Colour, Point, Vec3};
+
+use std::sync::Arc;
+
+
+// a point picked on a light for a shadow ray, with the light emitted towards the origin it was picked for
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    pub direction:Vec3,
+    pub distance:f32,
+    pub emitted:Colour,
+    // solid angle density at the origin
+    pub pdf:f32,
+}
+
+
+// the emissive surfaces of a scene, sampled for next event estimation
+// a light is picked in proportion to its power, its mean emitted luminance times its area,
+// and then a point uniformly over its area, so the area density anywhere on a light is its luminance over the total
+#[derive(Clone, Default)]
+pub struct LightList {
+    pub lights:Vec<Arc<dyn Surface>>,
+    cumulative_power:Vec<f32>,
+    total_power:f32,
+    // finds which lights a ray that hit something emissive could have sampled
+    bvh:Bvh,
+}
+
+impl LightList {
+    // surfaces that emit nothing are left out
+    pub fn new(surfaces:Vec<Arc<dyn Surface>>) -> LightList {
+        let lights : Vec<Arc<dyn Surface>> = surfaces.into_iter()
+            .filter(|surface| LightList::power(surface.as_ref()) > 0.0)
+            .collect();
+        let cumulative_power : Vec<f32> = lights.iter()
+            .scan(0.0, |total, light| {
+                *total += LightList::power(light.as_ref());
+                Some(*total)
+            })
+            .collect();
+        let total_power = cumulative_power.last().cloned().unwrap_or(0.0);
+        let bvh = Bvh::new(lights.iter().map(|light| light.clone() as Arc<dyn Hittable>).collect());
+        LightList { lights, cumulative_power, total_power, bvh }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    fn luminance(emission:Colour) -> f32 {
+        luma(emission.to_array()).max(0.0)
+    }
+
+    fn power(surface:&dyn Surface) -> f32 {
+        LightList::luminance(surface.material().average_emission()) * surface.area()
+    }
+
+    // a point on a light as seen from origin, None when there are no lights or the point faces away
+    pub fn sample(&self, origin:Point, rng:&mut Pcg32) -> Option<LightSample> {
+        if self.total_power <= 0.0 {
+            return None;
+        }
+        let target = rng.next_f32() * self.total_power;
+        let index = self.cumulative_power.partition_point(|power| *power <= target).min(self.lights.len() - 1);
+        let light = &self.lights[index];
+        let surface = light.sample(rng);
+
+        let offset = surface.point - origin;
+        let distance = offset.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = offset / distance;
+        let cosine = -direction.dot(surface.normal);
+        if cosine <= 0.0 {
+            return None;
+        }
+
+        let material = light.material();
+        let ray = Ray::new(origin, direction);
+        let emitted = material.emitted(&HitRecord::new(&ray, distance, surface.normal, surface.u, surface.v, material));
+        let area_pdf = LightList::luminance(material.average_emission()) / self.total_power;
+        Some(LightSample { direction, distance, emitted, pdf:area_pdf * distance * distance / cosine })
+    }
+
+    // the solid angle density sample would have picked the point ray hit with, 0 if it isn't on a light
+    pub fn pdf(&self, ray:&Ray, hit:&HitRecord<'_>) -> f32 {
+        if self.total_power <= 0.0 {
+            return 0.0;
+        }
+        // anything in the light bvh at the same distance is the surface that was hit
+        let tolerance = 1e-4 * hit.t.max(1.0);
+        let light_hit = match self.bvh.hit(ray, hit.t - tolerance, hit.t + tolerance) {
+            Some(light_hit) => light_hit,
+            None => return 0.0,
+        };
+        let length = ray.direction.length();
+        let distance = light_hit.t * length;
+        let cosine = light_hit.normal.dot(ray.direction).abs() / length;
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        let area_pdf = LightList::luminance(light_hit.material.average_emission()) / self.total_power;
+        area_pdf * distance * distance / cosine
+    }
+}
@@ -0,0 +1,60 @@
+// renders a scene file to an image
+// ray_tracer <scene.toml> <output> [--samples n] [--threads n] [--seed n]
+// .pfm and .hdr outputs keep linear radiance, .png and .ppm are tone mapped
+
+use ray_tracer::errors::RayTracerError;
+use ray_tracer::renderer::{render, save_render, Progress};
+use ray_tracer::scene::load_scene;
+
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+
+
+const USAGE : &str = "usage: ray_tracer <scene.toml> <output.png|ppm|pfm|hdr> [--samples n] [--threads n] [--seed n]";
+
+
+fn print_progress(progress:&Progress) {
+    let eta = progress.eta.map_or(String::from("-"), |eta| format!("{:.1}s", eta.as_secs_f32()));
+    print!("\r{:>5.1}%, eta {:>7}, {:.2} Mrays/s   ", 100.0 * progress.fraction(), eta, progress.rays_per_second / 1e6);
+    let _ = std::io::stdout().flush();
+}
+
+fn parse_option<T:std::str::FromStr>(name:&str, value:Option<&String>) -> T {
+    match value.and_then(|value| value.parse().ok()) {
+        Some(value) => value,
+        None => {
+            eprintln!("{} needs a number\n{}", name, USAGE);
+            std::process::exit(2);
+        },
+    }
+}
+
+fn main() -> Result<(), RayTracerError> {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+    let (scene_path, output_path) = (&args[0], &args[1]);
+
+    let mut scene = load_scene(scene_path)?;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--samples" => scene.settings.samples_per_pixel = parse_option(option, options.next()),
+            "--threads" => scene.settings.threads = parse_option(option, options.next()),
+            "--seed" => scene.settings.seed = parse_option(option, options.next()),
+            _ => {
+                eprintln!("unknown option {}\n{}", option, USAGE);
+                std::process::exit(2);
+            },
+        }
+    }
+
+    let image = render(&scene.world, &scene.lights, &scene.camera, &scene.settings, &mut print_progress, &AtomicBool::new(false))?;
+    println!();
+
+    save_render(&image, output_path)?;
+    println!("saved {}", output_path);
+    Ok(())
+}
@@ -0,0 +1,499 @@
+use render_context::lighting::Lighting;
+
+use crate::hittable::HitRecord;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::texture::{SolidColour, Texture};
+use crate::vec3::{Colour, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+
+// the ray leaving a surface and how much of each channel survives the bounce
+// pdf is the solid angle density the direction was chosen with, and attenuation is eval over pdf,
+// None for mirror and glass like scattering that no light sample could ever pick
+#[derive(Debug, Clone, Copy)]
+pub struct Scatter {
+    pub attenuation:Colour,
+    pub ray:Ray,
+    pub pdf:Option<f32>,
+}
+
+
+// the closest blinn phong description of a material, named after the fields of render_context's Lighting
+// so a scene can be previewed in the rasteriser, diffuse_colour goes in the vertex colours
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PreviewShading {
+    pub ambient_colour:Colour,
+    pub ambient_strength:f32,
+    pub diffuse_colour:Colour,
+    pub diffuse_strength:f32,
+    pub specular_strength:f32,
+    pub specular_power:u32,
+}
+
+impl PreviewShading {
+    pub fn new(diffuse_colour:Colour) -> PreviewShading {
+        PreviewShading {
+            ambient_colour:Colour::zero(),
+            ambient_strength:0.0,
+            diffuse_colour,
+            diffuse_strength:1.0,
+            specular_strength:0.0,
+            specular_power:2,
+        }
+    }
+
+    pub fn apply_to(&self, lighting:&mut Lighting) {
+        lighting.ambient_colour = (self.ambient_colour.x, self.ambient_colour.y, self.ambient_colour.z);
+        lighting.ambient_strength = self.ambient_strength;
+        lighting.diffuse_strength = self.diffuse_strength;
+        lighting.specular_strength = self.specular_strength;
+        lighting.specular_power = self.specular_power;
+    }
+}
+
+
+pub trait Material: Send + Sync {
+    // None when the ray is absorbed
+    fn scatter(&self, ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter>;
+
+    fn emitted(&self, _hit:&HitRecord<'_>) -> Colour {
+        Colour::zero()
+    }
+
+    // the mean emitted radiance, lights are picked in proportion to it times their area
+    fn average_emission(&self) -> Colour {
+        Colour::zero()
+    }
+
+    // the bsdf times the cosine of the angle to direction, for materials whose scatter gives a pdf
+    fn eval(&self, _ray:&Ray, _hit:&HitRecord<'_>, _direction:Vec3) -> Colour {
+        Colour::zero()
+    }
+
+    // the density scatter would choose direction with
+    fn pdf(&self, _ray:&Ray, _hit:&HitRecord<'_>, _direction:Vec3) -> f32 {
+        0.0
+    }
+
+    fn preview(&self) -> PreviewShading;
+}
+
+
+// a cosine weighted direction about the unit normal
+fn cosine_direction(normal:Vec3, rng:&mut Pcg32) -> Vec3 {
+    let direction = normal + rng.unit_vector();
+    match direction.near_zero() {
+        true => normal,
+        false => direction.unit(),
+    }
+}
+
+// schlick's approximation with the reflectance at normal incidence
+fn schlick(cosine:f32, f0:Colour) -> Colour {
+    f0 + (Colour::splat(1.0) - f0) * (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+// a blinn phong exponent giving a highlight about as wide as a ggx lobe of this roughness
+fn specular_power(roughness:f32) -> u32 {
+    let alpha = (roughness * roughness).max(1e-3);
+    (2.0 / (alpha * alpha) - 2.0).clamp(1.0, 4096.0) as u32
+}
+
+
+
+pub struct Lambertian {
+    pub diffuse_colour:Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(diffuse_colour:Colour) -> Lambertian {
+        Lambertian::textured(Arc::new(SolidColour::new(diffuse_colour)))
+    }
+
+    pub fn textured(diffuse_colour:Arc<dyn Texture>) -> Lambertian {
+        Lambertian { diffuse_colour }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, _ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        let direction = cosine_direction(hit.normal, rng);
+        Some(Scatter {
+            attenuation:self.diffuse_colour.value(hit.u, hit.v, hit.point),
+            ray:Ray::new(hit.point, direction),
+            pdf:Some(hit.normal.dot(direction).max(0.0) / PI),
+        })
+    }
+
+    fn eval(&self, _ray:&Ray, hit:&HitRecord<'_>, direction:Vec3) -> Colour {
+        self.diffuse_colour.value(hit.u, hit.v, hit.point) * (hit.normal.dot(direction.unit()).max(0.0) / PI)
+    }
+
+    fn pdf(&self, _ray:&Ray, hit:&HitRecord<'_>, direction:Vec3) -> f32 {
+        hit.normal.dot(direction.unit()).max(0.0) / PI
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading::new(self.diffuse_colour.average())
+    }
+}
+
+
+
+// fuzz of 0 is a perfect mirror, rays fuzzed below the surface are absorbed
+pub struct Metal {
+    pub specular_colour:Colour,
+    pub fuzz:f32,
+}
+
+impl Metal {
+    pub fn new(specular_colour:Colour, fuzz:f32) -> Metal {
+        Metal { specular_colour, fuzz:fuzz.clamp(0.0, 1.0) }
+    }
+}
+
+impl Material for Metal {
+    fn scatter(&self, ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        let reflected = ray.direction.unit().reflect(hit.normal) + rng.unit_vector() * self.fuzz;
+        match reflected.dot(hit.normal) > 0.0 {
+            true => Some(Scatter { attenuation:self.specular_colour, ray:Ray::new(hit.point, reflected), pdf:None }),
+            false => None,
+        }
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading {
+            diffuse_strength:0.1,
+            specular_strength:1.0,
+            specular_power:specular_power(self.fuzz),
+            ..PreviewShading::new(self.specular_colour)
+        }
+    }
+}
+
+
+
+// clear glass, the refractive index is relative to the air around it
+pub struct Dielectric {
+    pub refractive_index:f32,
+}
+
+impl Dielectric {
+    pub fn new(refractive_index:f32) -> Dielectric {
+        Dielectric { refractive_index }
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        let eta_ratio = if hit.front_face {1.0 / self.refractive_index} else {self.refractive_index};
+        let direction = ray.direction.unit();
+        let cos_theta = (-direction).dot(hit.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta*cos_theta).max(0.0).sqrt();
+
+        let total_internal_reflection = eta_ratio * sin_theta > 1.0;
+        let r0 = ((1.0 - eta_ratio) / (1.0 + eta_ratio)).powi(2);
+        let reflectance = schlick(cos_theta, Colour::splat(r0)).x;
+        let scattered = match total_internal_reflection || reflectance > rng.next_f32() {
+            true => direction.reflect(hit.normal),
+            false => direction.refract(hit.normal, eta_ratio),
+        };
+        Some(Scatter { attenuation:Colour::splat(1.0), ray:Ray::new(hit.point, scattered), pdf:None })
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading {
+            diffuse_strength:0.05,
+            specular_strength:1.0,
+            specular_power:256,
+            ..PreviewShading::new(Colour::splat(1.0))
+        }
+    }
+}
+
+
+
+// emits from its front face only and absorbs whatever hits it
+pub struct Emissive {
+    pub emission_colour:Arc<dyn Texture>,
+    pub emission_strength:f32,
+}
+
+impl Emissive {
+    pub fn new(emission_colour:Colour, emission_strength:f32) -> Emissive {
+        Emissive::textured(Arc::new(SolidColour::new(emission_colour)), emission_strength)
+    }
+
+    pub fn textured(emission_colour:Arc<dyn Texture>, emission_strength:f32) -> Emissive {
+        Emissive { emission_colour, emission_strength }
+    }
+}
+
+impl Material for Emissive {
+    fn scatter(&self, _ray:&Ray, _hit:&HitRecord<'_>, _rng:&mut Pcg32) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, hit:&HitRecord<'_>) -> Colour {
+        match hit.front_face {
+            true => self.emission_colour.value(hit.u, hit.v, hit.point) * self.emission_strength,
+            false => Colour::zero(),
+        }
+    }
+
+    fn average_emission(&self) -> Colour {
+        self.emission_colour.average() * self.emission_strength
+    }
+
+    // the rasteriser has no emission, ambient light is the closest it gets
+    fn preview(&self) -> PreviewShading {
+        let emission_colour = self.emission_colour.average();
+        PreviewShading {
+            ambient_colour:emission_colour,
+            ambient_strength:1.0,
+            diffuse_strength:0.0,
+            ..PreviewShading::new(emission_colour)
+        }
+    }
+}
+
+
+
+// microfacet material with a ggx (trowbridge reitz) specular lobe over a lambertian base
+// metallic 0 is a dielectric with 4% reflectance at normal incidence, metallic 1 tints the reflection with diffuse_colour
+// the specular lobe is sampled from the normal distribution with probability specular_probability
+// roughness is read from the red channel of its texture and kept in 0.02 to 1
+pub struct Ggx {
+    pub diffuse_colour:Arc<dyn Texture>,
+    pub roughness:Arc<dyn Texture>,
+    pub metallic:f32,
+    pub specular_strength:f32,
+}
+
+impl Ggx {
+    pub fn new(diffuse_colour:Colour, roughness:f32, metallic:f32) -> Ggx {
+        Ggx::textured(Arc::new(SolidColour::new(diffuse_colour)), Arc::new(SolidColour::new(Colour::splat(roughness))), metallic)
+    }
+
+    pub fn textured(diffuse_colour:Arc<dyn Texture>, roughness:Arc<dyn Texture>, metallic:f32) -> Ggx {
+        Ggx {
+            diffuse_colour,
+            roughness,
+            metallic:metallic.clamp(0.0, 1.0),
+            specular_strength:1.0,
+        }
+    }
+
+    pub fn roughness_at(&self, hit:&HitRecord<'_>) -> f32 {
+        self.roughness.value(hit.u, hit.v, hit.point).x.clamp(0.02, 1.0)
+    }
+
+    pub fn f0(&self, diffuse_colour:Colour) -> Colour {
+        Colour::splat(0.04).lerp(diffuse_colour, self.metallic) * self.specular_strength
+    }
+
+    fn specular_probability(&self) -> f32 {
+        0.5 + 0.5 * self.metallic
+    }
+
+    // smith's masking shadowing for ggx, separable form
+    fn smith_g1(alpha:f32, cosine:f32) -> f32 {
+        let alpha2 = alpha * alpha;
+        let cosine2 = cosine * cosine;
+        2.0 * cosine / (cosine + (alpha2 + (1.0 - alpha2) * cosine2).sqrt())
+    }
+
+    // a microfacet normal from the ggx distribution about the unit normal
+    fn sample_half_vector(alpha:f32, normal:Vec3, rng:&mut Pcg32) -> Vec3 {
+        let (xi1, xi2) = (rng.next_f32(), rng.next_f32());
+        let alpha2 = alpha * alpha;
+        let cos_theta = ((1.0 - xi1) / (1.0 + (alpha2 - 1.0) * xi1)).max(0.0).sqrt();
+        let sin_theta = (1.0 - cos_theta*cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * xi2;
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta).unit()
+    }
+
+    // the normal distribution
+    fn distribution(alpha:f32, n_dot_h:f32) -> f32 {
+        let alpha2 = alpha * alpha;
+        let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    // the brdf times the cosine, and the density of the lobe mixture scatter samples from
+    fn eval_pdf(&self, ray:&Ray, hit:&HitRecord<'_>, light:Vec3) -> (Colour, f32) {
+        let light = light.unit();
+        let view = -ray.direction.unit();
+        let n_dot_l = hit.normal.dot(light);
+        if n_dot_l <= 0.0 {
+            return (Colour::zero(), 0.0);
+        }
+        let diffuse_colour = self.diffuse_colour.value(hit.u, hit.v, hit.point);
+        let roughness = self.roughness_at(hit);
+        let alpha = roughness * roughness;
+        let f0 = self.f0(diffuse_colour);
+        let half = (view + light).unit();
+        let n_dot_v = hit.normal.dot(view).max(1e-4);
+        let n_dot_h = hit.normal.dot(half).max(1e-4);
+        let v_dot_h = view.dot(half).max(1e-4);
+        let d = Ggx::distribution(alpha, n_dot_h);
+
+        let specular = schlick(v_dot_h, f0) * (d * Ggx::smith_g1(alpha, n_dot_v) * Ggx::smith_g1(alpha, n_dot_l) / (4.0 * n_dot_v));
+        let diffuse = diffuse_colour * (1.0 - self.metallic) * (Colour::splat(1.0) - schlick(n_dot_v, f0)) * (n_dot_l / PI);
+        let p_specular = self.specular_probability();
+        let pdf = p_specular * d * n_dot_h / (4.0 * v_dot_h) + (1.0 - p_specular) * n_dot_l / PI;
+        (specular + diffuse, pdf)
+    }
+}
+
+impl Material for Ggx {
+    // picks a lobe, then weights by the whole brdf over the density of both lobes
+    fn scatter(&self, ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        let light = match rng.next_f32() < self.specular_probability() {
+            true => {
+                let roughness = self.roughness_at(hit);
+                let half = Ggx::sample_half_vector(roughness * roughness, hit.normal, rng);
+                ray.direction.unit().reflect(half)
+            },
+            false => cosine_direction(hit.normal, rng),
+        };
+        let (value, pdf) = self.eval_pdf(ray, hit, light);
+        match pdf > 0.0 {
+            true => Some(Scatter { attenuation:value / pdf, ray:Ray::new(hit.point, light), pdf:Some(pdf) }),
+            false => None,
+        }
+    }
+
+    fn eval(&self, ray:&Ray, hit:&HitRecord<'_>, direction:Vec3) -> Colour {
+        self.eval_pdf(ray, hit, direction).0
+    }
+
+    fn pdf(&self, ray:&Ray, hit:&HitRecord<'_>, direction:Vec3) -> f32 {
+        self.eval_pdf(ray, hit, direction).1
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading {
+            diffuse_strength:1.0 - self.metallic,
+            specular_strength:self.specular_strength * (0.04 + 0.96 * self.metallic),
+            specular_power:specular_power(self.roughness.average().x.clamp(0.02, 1.0)),
+            ..PreviewShading::new(self.diffuse_colour.average())
+        }
+    }
+}
+
+
+
+// the density of scattering from direction into scattered under the henyey greenstein phase function
+// asymmetry is the mean cosine of the angle turned through, 0 is isotropic, positive scatters forwards
+fn henyey_greenstein(asymmetry:f32, direction:Vec3, scattered:Vec3) -> f32 {
+    let cosine = direction.unit().dot(scattered.unit());
+    let g2 = asymmetry * asymmetry;
+    (1.0 - g2) / (4.0 * PI * (1.0 + g2 - 2.0 * asymmetry * cosine).max(1e-8).powf(1.5))
+}
+
+// a direction about direction from the henyey greenstein distribution, by inverting its cdf in the cosine
+fn henyey_greenstein_direction(asymmetry:f32, direction:Vec3, rng:&mut Pcg32) -> Vec3 {
+    let (xi1, xi2) = (rng.next_f32(), rng.next_f32());
+    let cos_theta = match asymmetry.abs() < 1e-3 {
+        true => 1.0 - 2.0 * xi1,
+        false => {
+            let g2 = asymmetry * asymmetry;
+            let s = (1.0 - g2) / (1.0 - asymmetry + 2.0 * asymmetry * xi1);
+            ((1.0 + g2 - s * s) / (2.0 * asymmetry)).clamp(-1.0, 1.0)
+        },
+    };
+    let sin_theta = (1.0 - cos_theta*cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * xi2;
+    let forward = direction.unit();
+    let (tangent, bitangent) = forward.orthonormal_basis();
+    tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + forward * cos_theta
+}
+
+
+
+// the phase function of a medium that scatters equally in every direction
+// albedo is the fraction of each channel scattered rather than absorbed at each collision
+pub struct Isotropic {
+    pub albedo:Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo:Colour) -> Isotropic {
+        Isotropic::textured(Arc::new(SolidColour::new(albedo)))
+    }
+
+    pub fn textured(albedo:Arc<dyn Texture>) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        Some(Scatter {
+            attenuation:self.albedo.value(hit.u, hit.v, hit.point),
+            ray:Ray::new(hit.point, rng.unit_vector()),
+            pdf:Some(1.0 / (4.0 * PI)),
+        })
+    }
+
+    // media have no surface, so there is no cosine
+    fn eval(&self, _ray:&Ray, hit:&HitRecord<'_>, _direction:Vec3) -> Colour {
+        self.albedo.value(hit.u, hit.v, hit.point) / (4.0 * PI)
+    }
+
+    fn pdf(&self, _ray:&Ray, _hit:&HitRecord<'_>, _direction:Vec3) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading::new(self.albedo.average())
+    }
+}
+
+
+
+// the phase function of a medium that prefers scattering forwards, like haze and smoke, or backwards
+// asymmetry is kept in -0.99 to 0.99, where the lobe is still wider than a float can resolve
+pub struct HenyeyGreenstein {
+    pub albedo:Arc<dyn Texture>,
+    pub asymmetry:f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo:Colour, asymmetry:f32) -> HenyeyGreenstein {
+        HenyeyGreenstein::textured(Arc::new(SolidColour::new(albedo)), asymmetry)
+    }
+
+    pub fn textured(albedo:Arc<dyn Texture>, asymmetry:f32) -> HenyeyGreenstein {
+        HenyeyGreenstein { albedo, asymmetry:asymmetry.clamp(-0.99, 0.99) }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    // sampled exactly, so the weight is just the albedo
+    fn scatter(&self, ray:&Ray, hit:&HitRecord<'_>, rng:&mut Pcg32) -> Option<Scatter> {
+        let direction = henyey_greenstein_direction(self.asymmetry, ray.direction, rng);
+        Some(Scatter {
+            attenuation:self.albedo.value(hit.u, hit.v, hit.point),
+            ray:Ray::new(hit.point, direction),
+            pdf:Some(henyey_greenstein(self.asymmetry, ray.direction, direction)),
+        })
+    }
+
+    fn eval(&self, ray:&Ray, hit:&HitRecord<'_>, direction:Vec3) -> Colour {
+        self.albedo.value(hit.u, hit.v, hit.point) * henyey_greenstein(self.asymmetry, ray.direction, direction)
+    }
+
+    fn pdf(&self, ray:&Ray, _hit:&HitRecord<'_>, direction:Vec3) -> f32 {
+        henyey_greenstein(self.asymmetry, ray.direction, direction)
+    }
+
+    fn preview(&self) -> PreviewShading {
+        PreviewShading::new(self.albedo.average())
+    }
+}
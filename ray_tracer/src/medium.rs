@@ -0,0 +1,211 @@
+use crate::aabb::Aabb;
+use crate::errors::GridError;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::vec3::{Point, Vec3};
+
+use std::fs;
+use std::sync::Arc;
+
+
+// hit has no random stream, so the free path along a ray is drawn from one seeded by the ray itself
+// every ray of a path is new, and the same ray always meets the medium at the same distance,
+// which keeps renders reproducible and the closest hit the same however often a ray is tested
+fn ray_rng(ray:&Ray) -> Pcg32 {
+    let bits = [ray.origin.x, ray.origin.y, ray.origin.z, ray.direction.x, ray.direction.y, ray.direction.z, ray.time];
+    let hash = bits.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, value| {
+        // splitmix64 finaliser over each coordinate
+        let mut z = (hash ^ value.to_bits() as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    });
+    Pcg32::new(hash, 0)
+}
+
+// a collision inside a medium, the normal faces back along the ray so it counts as a front face
+fn scattering_hit<'m>(ray:&Ray, t:f32, phase:&'m dyn Material) -> HitRecord<'m> {
+    HitRecord::new(ray, t, -ray.direction.unit(), 0.0, 0.0, phase)
+}
+
+
+
+// fog or smoke of the same density everywhere inside a closed convex boundary, density is the chance
+// of a collision per unit length, and phase, isotropic or henyey greenstein, decides where it scatters to
+// the boundary's own material is never used, and rays starting inside it are scattered from where they start
+pub struct ConstantMedium {
+    pub boundary:Arc<dyn Hittable>,
+    pub density:f32,
+    pub phase:Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary:Arc<dyn Hittable>, density:f32, phase:Arc<dyn Material>) -> ConstantMedium {
+        ConstantMedium { boundary, density:density.max(0.0), phase }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        if self.density <= 0.0 {
+            return None;
+        }
+        // where the whole line enters and leaves, so a ray starting inside still finds its way out
+        let entry = self.boundary.hit(ray, f32::NEG_INFINITY, f32::INFINITY)?;
+        let exit = self.boundary.hit(ray, entry.t + 1e-4, f32::INFINITY)?;
+        let (start, end) = (entry.t.max(t_min), exit.t.min(t_max));
+        if start >= end {
+            return None;
+        }
+
+        let free_path = -(1.0 - ray_rng(ray).next_f32()).ln() / self.density;
+        let t = start + free_path / ray.direction.length();
+        match t < end {
+            true => Some(scattering_hit(ray, t, self.phase.as_ref())),
+            false => None,
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+
+
+// densities on the nodes of a regular grid, x varies fastest, then y, then z
+#[derive(Debug, PartialEq, Clone)]
+pub struct DensityGrid {
+    pub nx:usize,
+    pub ny:usize,
+    pub nz:usize,
+    pub densities:Vec<f32>,
+}
+
+impl DensityGrid {
+    pub fn new(nx:usize, ny:usize, nz:usize, densities:Vec<f32>) -> DensityGrid {
+        DensityGrid { nx, ny, nz, densities }
+    }
+
+    // a text file, the three sizes and then every density, separated by whitespace, # starts a comment
+    pub fn parse(source:&str) -> Result<DensityGrid, GridError> {
+        let mut tokens = source.lines().enumerate()
+            .flat_map(|(index, line)| line.split('#').next().unwrap_or("").split_whitespace().map(move |token| (index + 1, token)));
+
+        let (mut size, mut header_line) = ([0; 3], 0);
+        for dimension in &mut size {
+            let (line_number, token) = tokens.next().ok_or(GridError::InvalidHeader((0, String::from("too few sizes"))))?;
+            *dimension = match token.parse::<usize>() {
+                Ok(value) if value > 0 => value,
+                _ => return Err(GridError::InvalidHeader((line_number, token.to_string()))),
+            };
+            header_line = line_number;
+        }
+        let [nx, ny, nz] = size;
+        let count = match nx.checked_mul(ny).and_then(|n| n.checked_mul(nz)) {
+            Some(count) => count,
+            None => return Err(GridError::InvalidHeader((header_line, format!("{} {} {}", nx, ny, nz)))),
+        };
+
+        // nothing is reserved from the header, which could ask for anything, the count is checked once every density is read
+        let mut densities = vec![];
+        for (line_number, token) in tokens {
+            let density : f32 = token.parse().map_err(|_| GridError::InvalidNumber((line_number, token.to_string())))?;
+            if density < 0.0 || !density.is_finite() {
+                return Err(GridError::NegativeDensity((line_number, density)));
+            }
+            densities.push(density);
+        }
+        if densities.len() != count {
+            return Err(GridError::WrongCount((count, densities.len())));
+        }
+        Ok(DensityGrid::new(nx, ny, nz, densities))
+    }
+
+    pub fn density(&self, x:usize, y:usize, z:usize) -> f32 {
+        self.densities[(z * self.ny + y) * self.nx + x]
+    }
+
+    pub fn max_density(&self) -> f32 {
+        self.densities.iter().cloned().fold(0.0, f32::max)
+    }
+
+    // trilinear between nodes, position runs from 0 to 1 across the grid and is clamped to it
+    pub fn sample(&self, position:Vec3) -> f32 {
+        let sizes = [self.nx, self.ny, self.nz];
+        let mut corners = [(0, 0, 0.0); 3];
+        for axis in 0..3 {
+            let last = sizes[axis] - 1;
+            let coordinate = position[axis].clamp(0.0, 1.0) * last as f32;
+            let lower = (coordinate.floor() as usize).min(last);
+            corners[axis] = (lower, (lower + 1).min(last), coordinate - lower as f32);
+        }
+        let [(x0, x1, fx), (y0, y1, fy), (z0, z1, fz)] = corners;
+        let lerp = |a:f32, b:f32, t:f32| a + (b - a) * t;
+        let plane = |z| lerp(
+            lerp(self.density(x0, y0, z), self.density(x1, y0, z), fx),
+            lerp(self.density(x0, y1, z), self.density(x1, y1, z), fx),
+            fy,
+        );
+        lerp(plane(z0), plane(z1), fz)
+    }
+}
+
+pub fn load_density_grid(path:&str) -> Result<DensityGrid, GridError> {
+    DensityGrid::parse(&fs::read_to_string(path)?)
+}
+
+
+
+// smoke whose density varies, a grid stretched over the box from min to max with every density times scale
+// collisions are found by delta tracking, stepping by free paths of the densest point
+// and keeping each one with the chance the density there is of that
+pub struct GridMedium {
+    pub grid:DensityGrid,
+    pub bounds:Aabb,
+    pub scale:f32,
+    pub phase:Arc<dyn Material>,
+    majorant:f32,
+}
+
+impl GridMedium {
+    pub fn new(grid:DensityGrid, min:Point, max:Point, scale:f32, phase:Arc<dyn Material>) -> GridMedium {
+        let scale = scale.max(0.0);
+        let majorant = grid.max_density() * scale;
+        GridMedium { grid, bounds:Aabb::new(min, max), scale, phase, majorant }
+    }
+
+    pub fn density_at(&self, point:Point) -> f32 {
+        let size = self.bounds.size();
+        let offset = point - self.bounds.min;
+        let position = Vec3::new(offset.x / size.x, offset.y / size.y, offset.z / size.z);
+        self.grid.sample(position) * self.scale
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let (start, end) = self.bounds.hit_range(ray, t_min, t_max)?;
+        let mut rng = ray_rng(ray);
+        let step = 1.0 / (self.majorant * ray.direction.length());
+        let mut t = start;
+        loop {
+            t -= (1.0 - rng.next_f32()).ln() * step;
+            if t >= end {
+                return None;
+            }
+            if rng.next_f32() * self.majorant < self.density_at(ray.at(t)) {
+                return Some(scattering_hit(ray, t, self.phase.as_ref()));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
@@ -0,0 +1,67 @@
+use meshes::mesh::{Mesh, MeshMaterial};
+use meshes::obj::load_obj;
+
+use crate::errors::RayTracerError;
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, Emissive, Ggx, Lambertian, Material, Metal};
+use crate::primitives::Triangle;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+
+// the closest ray tracer material to an mtl material
+// emissive materials emit, transparent ones and the glass illumination models refract,
+// illumination models with ray traced reflection and no diffuse colour are metals,
+// anything with a specular colour is ggx with a roughness matching the phong exponent
+pub fn mesh_material(material:&MeshMaterial) -> Arc<dyn Material> {
+    let colour = |array:[f32; 3]| Vec3::from(array);
+    let specular = material.specular_colour.iter().cloned().fold(0.0, f32::max);
+    // the beckmann slope for a phong exponent, which ggx roughness squares to
+    let roughness = (2.0 / (material.specular_exponent.max(0.0) + 2.0)).sqrt().sqrt();
+
+    if material.is_emissive() {
+        Arc::new(Emissive::new(colour(material.emission_colour), 1.0))
+    } else if material.dissolve < 1.0 || [4, 6, 7].contains(&material.illumination_model) {
+        Arc::new(Dielectric::new(material.refractive_index.max(1.0)))
+    } else if [3, 5].contains(&material.illumination_model) && material.diffuse_colour == [0.0; 3] {
+        Arc::new(Metal::new(colour(material.specular_colour), roughness * roughness))
+    } else if specular > 0.0 {
+        Arc::new(Ggx {
+            specular_strength:specular / 0.04,
+            ..Ggx::new(colour(material.diffuse_colour), roughness, 0.0)
+        })
+    } else {
+        Arc::new(Lambertian::new(colour(material.diffuse_colour)))
+    }
+}
+
+// one triangle per mesh triangle, material overrides the materials of the mesh when given
+// triangles without a material are given the mtl default
+pub fn mesh_triangles(mesh:&Mesh, material:Option<Arc<dyn Material>>) -> Vec<Arc<Triangle>> {
+    let materials : Vec<Arc<dyn Material>> = match &material {
+        Some(material) => vec![material.clone(); mesh.materials.len()],
+        None => mesh.materials.iter().map(mesh_material).collect(),
+    };
+    let default_material = material.unwrap_or_else(|| mesh_material(&MeshMaterial::new("")));
+
+    mesh.triangles.iter().map(|triangle| {
+        let material = match triangle.material {
+            Some(index) => materials[index].clone(),
+            None => default_material.clone(),
+        };
+        let [v0, v1, v2] = mesh.triangle_positions(triangle).map(Vec3::from);
+        let mut hittable = Triangle::new(v0, v1, v2, material);
+        if let Some(normals) = mesh.triangle_normals(triangle) {
+            hittable = hittable.with_normals(normals.map(Vec3::from));
+        }
+        if let Some(uvs) = mesh.triangle_uvs(triangle) {
+            hittable = hittable.with_uvs(uvs);
+        }
+        Arc::new(hittable)
+    }).collect()
+}
+
+pub fn load_mesh(path:&str, material:Option<Arc<dyn Material>>) -> Result<Vec<Arc<dyn Hittable>>, RayTracerError> {
+    Ok(mesh_triangles(&load_obj(path)?, material).into_iter().map(|triangle| triangle as Arc<dyn Hittable>).collect())
+}
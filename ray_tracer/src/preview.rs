@@ -0,0 +1,128 @@
+use render_context::camera::Camera as RenderCamera;
+use render_context::enums::{DrawMode, ProgramSelect, UniformType};
+use render_context::enums::{TextureData, TextureFilter, TextureFormat};
+use render_context::render::Render;
+use matrices::matrix::Matrix;
+
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::errors::RayTracerError;
+use crate::hittable::Hittable;
+use crate::light::LightList;
+use crate::renderer::{Accumulator, RenderSettings};
+use crate::transform::Mat4;
+
+
+// xyz uv vertices of two triangles covering the whole window in clip space, the top row of the image at the top
+fn screen_quad() -> Matrix<f32> {
+    Matrix::from_2darray([
+        [-1.0,  1.0, 0.0, 0.0, 0.0],
+        [-1.0, -1.0, 0.0, 0.0, 1.0],
+        [ 1.0, -1.0, 0.0, 1.0, 1.0],
+        [ 1.0,  1.0, 0.0, 1.0, 0.0],
+        [ 1.0, -1.0, 0.0, 1.0, 1.0],
+        [-1.0,  1.0, 0.0, 0.0, 0.0],
+    ])
+}
+
+// pans and zooms the window camera, keeping its angle, so the box is centred and all of it is in view
+pub fn fit_camera_to_aabb(camera:&mut RenderCamera, aabb:&Aabb) -> Result<(), RayTracerError> {
+    if aabb.is_empty() || !aabb.is_finite() {
+        return Ok(());
+    }
+    let rotation = Mat4::from_matrix(&Matrix::rotate_around_p((0.0, 0.0, 0.0), camera.angle_xyz)?)?;
+    let world = Mat4::from_matrix(&Matrix::opengl_to_right_handed())?;
+    let center = (rotation * world).transform_point(aabb.centroid());
+    camera.pan_xyz = (-center.x, -center.y, 0.0);
+    camera.zoom = 0.6 * aabb.size().max_component();
+    Ok(())
+}
+
+
+// everything about the window that changes what the ray tracer sees
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ViewState {
+    angle_xyz:(f32, f32, f32),
+    pan_xyz:(f32, f32, f32),
+    zoom:f32,
+    width:u32,
+    height:u32,
+}
+
+impl ViewState {
+    fn new(render:&Render) -> Result<ViewState, RayTracerError> {
+        Ok(ViewState {
+            angle_xyz:render.camera.angle_xyz,
+            pan_xyz:render.camera.pan_xyz,
+            zoom:render.camera.zoom,
+            width:render.window.width()?,
+            height:render.window.height()?,
+        })
+    }
+}
+
+
+// traces the world through the window camera, adding a sample per pixel every frame and showing the running average
+// panning, angling, zooming or resizing restarts the accumulation, space pauses sampling
+// the image is traced at the window size divided by downscale and stretched over the window
+pub fn preview(world:&dyn Hittable, lights:&LightList, settings:&RenderSettings, downscale:usize) -> Result<(), RayTracerError> {
+
+    let mut render = Render::default()?;
+    render.setup_render();
+    fit_camera_to_aabb(&mut render.camera, &world.bounding_box())?;
+
+    let quad = screen_quad();
+    let (quad_vao, _) = render.create_vao_vbo(&quad)?;
+    let program = ProgramSelect::SelectToneMappedQuad;
+
+    let mut accumulator = Accumulator::new(0, 0);
+    let mut texture = None;
+    let mut camera = None;
+    let mut last_view = None;
+
+    while !render.render_over() {
+
+        let view = ViewState::new(&render)?;
+        if last_view != Some(view) {
+            let width = (view.width as usize / downscale.max(1)).max(1);
+            let height = (view.height as usize / downscale.max(1)).max(1);
+            if (width, height) != (accumulator.width, accumulator.height) {
+                if let Some(texture) = texture {
+                    render.delete_texture(texture);
+                }
+                accumulator = Accumulator::new(width, height);
+                let black = vec![0.0; width * height * 3];
+                texture = Some(render.create_texture(width, height, TextureFormat::Rgb, TextureData::F32(&black), TextureFilter::Linear)?);
+            }
+            accumulator.reset();
+            camera = Some(Camera::from_render_camera(&render.camera, view.width, view.height)?);
+            last_view = Some(view);
+        }
+
+        if let (Some(camera), Some(texture), false) = (&camera, texture, render.paused) {
+            accumulator.sample_pass(world, lights, camera, settings);
+            let scale = 1.0 / accumulator.passes as f32;
+            let average : Vec<f32> = accumulator.sum.iter().flat_map(|sum| (*sum * scale).to_array()).collect();
+            render.update_texture(texture, accumulator.width, accumulator.height, TextureFormat::Rgb, TextureData::F32(&average))?;
+        }
+
+        let paused = if render.paused {", paused"} else {""};
+        let title = format!("ray tracer preview, {} samples per pixel{}", accumulator.passes, paused);
+        render.window.set_title(&title);
+
+        render.begin_render_actions()?;
+
+        // the quad is already in clip space, so the camera uniforms use_program sets are replaced with identities
+        render.use_program(program)?;
+        render.set_uniform(program, "orthographic_projection", UniformType::Mat4, Matrix::translate((0.0, 0.0, 0.0)))?;
+        render.set_uniform(program, "camera_transformation", UniformType::Mat4, Matrix::translate((0.0, 0.0, 0.0)))?;
+        if let Some(texture) = texture {
+            render.draw_textured_vao(DrawMode::GlTriangles, quad_vao, texture, &quad)?;
+        }
+
+        render.end_render_actions()?;
+
+    }
+
+    Ok(())
+}
@@ -0,0 +1,631 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, Surface, SurfaceSample};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::vec3::{Point, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+
+// thickness given to flat bounding boxes
+const FLAT_PADDING : f32 = 1e-4;
+// below this a ray counts as parallel to a surface
+const PARALLEL_EPSILON : f32 = 1e-8;
+
+
+fn in_range(t:f32, t_min:f32, t_max:f32) -> bool {
+    t > t_min && t < t_max
+}
+
+// u runs around from -x, v from the bottom (-y) to the top, for a point on the unit sphere
+pub fn sphere_uv(point:Vec3) -> (f32, f32) {
+    let theta = (-point.y).clamp(-1.0, 1.0).acos();
+    let phi = f32::atan2(-point.z, point.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+// u runs around the axis and v out from it, for a point in a disk's own frame
+fn polar_uv(x:f32, y:f32, radius:f32) -> (f32, f32) {
+    let phi = f32::atan2(y, x) + PI;
+    (phi / (2.0 * PI), (x*x + y*y).sqrt() / radius)
+}
+
+// a disk with unit normal spans radius * sin of the angle between the normal and each axis
+fn disk_extent(normal:Vec3, radius:f32) -> Vec3 {
+    normal.map(|n| radius * (1.0 - n*n).max(0.0).sqrt())
+}
+
+
+
+// a negative radius flips the normals inward, for the inside surface of hollow glass
+pub struct Sphere {
+    pub center:Point,
+    pub radius:f32,
+    pub material:Arc<dyn Material>,
+}
+
+impl Sphere {
+    pub fn new(center:Point, radius:f32, material:Arc<dyn Material>) -> Sphere {
+        Sphere { center, radius, material }
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let oc = self.center - ray.origin;
+        let a = ray.direction.length_squared();
+        let h = ray.direction.dot(oc);
+        let c = oc.length_squared() - self.radius*self.radius;
+        let discriminant = h*h - a*c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        // the near root first, the far one is the exit when the ray starts inside
+        let root = discriminant.sqrt();
+        let t = [(h - root) / a, (h + root) / a].into_iter().find(|t| in_range(*t, t_min, t_max))?;
+
+        let outward_normal = (ray.at(t) - self.center) / self.radius;
+        let (u, v) = sphere_uv(outward_normal * self.radius.signum());
+        Some(HitRecord::new(ray, t, outward_normal, u, v, self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3::splat(self.radius.abs());
+        Aabb::new(self.center - r, self.center + r)
+    }
+}
+
+impl Surface for Sphere {
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample(&self, rng:&mut Pcg32) -> SurfaceSample {
+        let direction = rng.unit_vector();
+        let (u, v) = sphere_uv(direction);
+        SurfaceSample { point:self.center + direction * self.radius.abs(), normal:direction * self.radius.signum(), u, v }
+    }
+
+    fn material(&self) -> &dyn Material {
+        self.material.as_ref()
+    }
+}
+
+
+
+// uv are coordinates in the plane in world units, textures decide how they tile
+pub struct Plane {
+    pub point:Point,
+    pub normal:Vec3,
+    pub material:Arc<dyn Material>,
+    tangent:Vec3,
+    bitangent:Vec3,
+}
+
+impl Plane {
+    pub fn new(point:Point, normal:Vec3, material:Arc<dyn Material>) -> Plane {
+        let normal = normal.unit();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Plane { point, normal, material, tangent, bitangent }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let t = self.normal.dot(self.point - ray.origin) / denominator;
+        if !in_range(t, t_min, t_max) {
+            return None;
+        }
+        let offset = ray.at(t) - self.point;
+        Some(HitRecord::new(ray, t, self.normal, offset.dot(self.tangent), offset.dot(self.bitangent), self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::infinite()
+    }
+}
+
+
+
+// counter clockwise vertices face the front, uv default to the barycentric coordinates
+// optional per vertex normals are interpolated for smooth shading
+pub struct Triangle {
+    pub vertices:[Point; 3],
+    pub normals:Option<[Vec3; 3]>,
+    pub uvs:Option<[[f32; 2]; 3]>,
+    pub material:Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(v0:Point, v1:Point, v2:Point, material:Arc<dyn Material>) -> Triangle {
+        Triangle { vertices:[v0, v1, v2], normals:None, uvs:None, material }
+    }
+
+    pub fn with_normals(mut self, normals:[Vec3; 3]) -> Triangle {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs:[[f32; 2]; 3]) -> Triangle {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    // moller trumbore, both sides are hit
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let [v0, v1, v2] = self.vertices;
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+        let p = ray.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+
+        let s = ray.origin - v0;
+        let b1 = s.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let b2 = ray.direction.dot(q) * inverse;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+        let t = edge2.dot(q) * inverse;
+        if !in_range(t, t_min, t_max) {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let (u, v) = match self.uvs {
+            Some([uv0, uv1, uv2]) => (b0*uv0[0] + b1*uv1[0] + b2*uv2[0], b0*uv0[1] + b1*uv1[1] + b2*uv2[1]),
+            None => (b1, b2),
+        };
+        let mut hit = HitRecord::new(ray, t, edge1.cross(edge2).unit(), u, v, self.material.as_ref());
+
+        // the geometric normal decides the face, the shading normal follows it
+        if let Some([n0, n1, n2]) = self.normals {
+            let shading = (n0*b0 + n1*b1 + n2*b2).unit();
+            hit.normal = if hit.front_face {shading} else {-shading};
+        }
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&self.vertices).padded(FLAT_PADDING)
+    }
+}
+
+impl Surface for Triangle {
+    fn area(&self) -> f32 {
+        let [v0, v1, v2] = self.vertices;
+        0.5 * (v1 - v0).cross(v2 - v0).length()
+    }
+
+    // folding the unit square in half keeps the barycentric coordinates uniform
+    fn sample(&self, rng:&mut Pcg32) -> SurfaceSample {
+        let [v0, v1, v2] = self.vertices;
+        let (mut b1, mut b2) = (rng.next_f32(), rng.next_f32());
+        if b1 + b2 > 1.0 {
+            (b1, b2) = (1.0 - b1, 1.0 - b2);
+        }
+        let b0 = 1.0 - b1 - b2;
+        let (u, v) = match self.uvs {
+            Some([uv0, uv1, uv2]) => (b0*uv0[0] + b1*uv1[0] + b2*uv2[0], b0*uv0[1] + b1*uv1[1] + b2*uv2[1]),
+            None => (b1, b2),
+        };
+        SurfaceSample { point:v0*b0 + v1*b1 + v2*b2, normal:(v1 - v0).cross(v2 - v0).unit(), u, v }
+    }
+
+    fn material(&self) -> &dyn Material {
+        self.material.as_ref()
+    }
+}
+
+
+
+// the parallelogram with corner q and edges u and v, uv run along the edges from 0 to 1
+pub struct Quad {
+    pub q:Point,
+    pub u:Vec3,
+    pub v:Vec3,
+    pub material:Arc<dyn Material>,
+    normal:Vec3,
+    d:f32,
+    w:Vec3,
+}
+
+impl Quad {
+    pub fn new(q:Point, u:Vec3, v:Vec3, material:Arc<dyn Material>) -> Quad {
+        let n = u.cross(v);
+        let normal = n.unit();
+        Quad { q, u, v, material, normal, d:normal.dot(q), w:n / n.length_squared() }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn area(&self) -> f32 {
+        self.u.cross(self.v).length()
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let t = (self.d - self.normal.dot(ray.origin)) / denominator;
+        if !in_range(t, t_min, t_max) {
+            return None;
+        }
+
+        let planar = ray.at(t) - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some(HitRecord::new(ray, t, self.normal, alpha, beta, self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(&[self.q, self.q + self.u, self.q + self.v, self.q + self.u + self.v]).padded(FLAT_PADDING)
+    }
+}
+
+impl Surface for Quad {
+    fn area(&self) -> f32 {
+        Quad::area(self)
+    }
+
+    fn sample(&self, rng:&mut Pcg32) -> SurfaceSample {
+        let (u, v) = (rng.next_f32(), rng.next_f32());
+        SurfaceSample { point:self.q + self.u * u + self.v * v, normal:self.normal, u, v }
+    }
+
+    fn material(&self) -> &dyn Material {
+        self.material.as_ref()
+    }
+}
+
+
+
+// uv on each face run along the two other axes from the min corner
+pub struct AaBox {
+    pub min:Point,
+    pub max:Point,
+    pub material:Arc<dyn Material>,
+}
+
+impl AaBox {
+    pub fn new(a:Point, b:Point, material:Arc<dyn Material>) -> AaBox {
+        AaBox { min:a.min(b), max:a.max(b), material }
+    }
+}
+
+impl Hittable for AaBox {
+    // slab test that remembers which slab the entry and exit were on
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let (mut t_near, mut t_far) = (f32::NEG_INFINITY, f32::INFINITY);
+        let (mut near_axis, mut far_axis) = (0, 0);
+        for axis in 0..3 {
+            let inverse = 1.0 / ray.direction[axis];
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inverse;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inverse;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > t_near { t_near = t0; near_axis = axis; }
+            if t1 < t_far { t_far = t1; far_axis = axis; }
+        }
+        if t_near > t_far {
+            return None;
+        }
+
+        // from outside the entry is hit, from inside (or with the entry clipped) the exit
+        let (t, axis) = match (in_range(t_near, t_min, t_max), in_range(t_far, t_min, t_max)) {
+            (true, _) => (t_near, near_axis),
+            (false, true) => (t_far, far_axis),
+            (false, false) => return None,
+        };
+
+        let point = ray.at(t);
+        let centre = (self.min + self.max) * 0.5;
+        let mut outward_normal = [0.0; 3];
+        outward_normal[axis] = if point[axis] > centre[axis] {1.0} else {-1.0};
+
+        let (a1, a2) = ((axis + 1) % 3, (axis + 2) % 3);
+        let size = self.max - self.min;
+        let u = (point[a1] - self.min[a1]) / size[a1];
+        let v = (point[a2] - self.min[a2]) / size[a2];
+        Some(HitRecord::new(ray, t, outward_normal.into(), u, v, self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.min, self.max).padded(FLAT_PADDING)
+    }
+}
+
+
+
+pub struct Disk {
+    pub center:Point,
+    pub normal:Vec3,
+    pub radius:f32,
+    pub material:Arc<dyn Material>,
+    tangent:Vec3,
+    bitangent:Vec3,
+}
+
+impl Disk {
+    pub fn new(center:Point, normal:Vec3, radius:f32, material:Arc<dyn Material>) -> Disk {
+        let normal = normal.unit();
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Disk { center, normal, radius, material, tangent, bitangent }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < PARALLEL_EPSILON {
+            return None;
+        }
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        if !in_range(t, t_min, t_max) {
+            return None;
+        }
+        let offset = ray.at(t) - self.center;
+        if offset.length_squared() > self.radius*self.radius {
+            return None;
+        }
+        let (u, v) = polar_uv(offset.dot(self.tangent), offset.dot(self.bitangent), self.radius);
+        Some(HitRecord::new(ray, t, self.normal, u, v, self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = disk_extent(self.normal, self.radius);
+        Aabb::new(self.center - extent, self.center + extent).padded(FLAT_PADDING)
+    }
+}
+
+
+
+// closed at both ends, the base cap is at base and the top cap at base + axis * height
+// on the side u runs around the axis and v up it, on the caps uv are polar
+pub struct Cylinder {
+    pub base:Point,
+    pub axis:Vec3,
+    pub height:f32,
+    pub radius:f32,
+    pub material:Arc<dyn Material>,
+    tangent:Vec3,
+    bitangent:Vec3,
+}
+
+impl Cylinder {
+    pub fn new(base:Point, top:Point, radius:f32, material:Arc<dyn Material>) -> Cylinder {
+        let height = (top - base).length();
+        let axis = (top - base) / height;
+        let (tangent, bitangent) = axis.orthonormal_basis();
+        Cylinder { base, axis, height, radius, material, tangent, bitangent }
+    }
+
+    fn to_local(&self, vector:Vec3) -> Vec3 {
+        Vec3::new(vector.dot(self.tangent), vector.dot(self.bitangent), vector.dot(self.axis))
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray:&Ray, t_min:f32, t_max:f32) -> Option<HitRecord<'_>> {
+        // in the cylinder's frame the side is x^2 + y^2 = r^2 for 0 <= z <= height
+        let origin = self.to_local(ray.origin - self.base);
+        let direction = self.to_local(ray.direction);
+        let r2 = self.radius * self.radius;
+        let mut closest : Option<(f32, Vec3, f32, f32)> = None;
+
+        let a = direction.x*direction.x + direction.y*direction.y;
+        if a > PARALLEL_EPSILON {
+            let h = origin.x*direction.x + origin.y*direction.y;
+            let c = origin.x*origin.x + origin.y*origin.y - r2;
+            let discriminant = h*h - a*c;
+            if discriminant >= 0.0 {
+                let root = discriminant.sqrt();
+                for t in [(-h - root) / a, (-h + root) / a] {
+                    let local = origin + direction * t;
+                    if in_range(t, t_min, t_max) && (0.0..=self.height).contains(&local.z) {
+                        let outward_normal = (self.tangent * local.x + self.bitangent * local.y) / self.radius;
+                        let u = (f32::atan2(local.y, local.x) + PI) / (2.0 * PI);
+                        closest = Some((t, outward_normal, u, local.z / self.height));
+                        break;
+                    }
+                }
+            }
+        }
+
+        if direction.z.abs() > PARALLEL_EPSILON {
+            for (z, outward_normal) in [(0.0, -self.axis), (self.height, self.axis)] {
+                let t = (z - origin.z) / direction.z;
+                let t_limit = closest.map_or(t_max, |(t, ..)| t);
+                let local = origin + direction * t;
+                if in_range(t, t_min, t_limit) && local.x*local.x + local.y*local.y <= r2 {
+                    let (u, v) = polar_uv(local.x, local.y, self.radius);
+                    closest = Some((t, outward_normal, u, v));
+                }
+            }
+        }
+
+        let (t, outward_normal, u, v) = closest?;
+        Some(HitRecord::new(ray, t, outward_normal, u, v, self.material.as_ref()))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = disk_extent(self.axis, self.radius);
+        let top = self.base + self.axis * self.height;
+        Aabb::new(self.base - extent, self.base + extent)
+            .surrounding(&Aabb::new(top - extent, top + extent))
+            .padded(FLAT_PADDING)
+    }
+}
+
+
+// every primitive against the awkward rays: hits from outside and inside,
+// grazing hits, and hits clipped by t_min and t_max
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{PreviewShading, Scatter};
+
+    struct Absorb;
+
+    impl Material for Absorb {
+        fn scatter(&self, _ray:&Ray, _hit:&HitRecord<'_>, _rng:&mut Pcg32) -> Option<Scatter> {
+            None
+        }
+
+        fn preview(&self) -> PreviewShading {
+            PreviewShading::new(Vec3::zero())
+        }
+    }
+
+    fn absorb() -> Arc<dyn Material> {
+        Arc::new(Absorb)
+    }
+
+    fn ray(origin:[f32; 3], direction:[f32; 3]) -> Ray {
+        Ray::new(origin.into(), direction.into())
+    }
+
+    fn close(a:f32, b:f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn close_vec(a:Vec3, b:[f32; 3]) -> bool {
+        close(a.x, b[0]) && close(a.y, b[1]) && close(a.z, b[2])
+    }
+
+    // expected is t, normal and front face
+    fn expect_hit(name:&str, object:&dyn Hittable, ray:Ray, t_min:f32, t_max:f32, expected:(f32, [f32; 3], bool)) {
+        let (t, normal, front_face) = expected;
+        match object.hit(&ray, t_min, t_max) {
+            Some(hit) => {
+                assert!(close(hit.t, t), "{}: t {} expected {}", name, hit.t, t);
+                assert!(close_vec(hit.normal, normal), "{}: normal {:?} expected {:?}", name, hit.normal, normal);
+                assert_eq!(hit.front_face, front_face, "{}: front face", name);
+                assert!((0.0..=1.0).contains(&hit.u) && (0.0..=1.0).contains(&hit.v) || name.starts_with("plane"),
+                    "{}: uv ({}, {}) out of range", name, hit.u, hit.v);
+                let aabb = object.bounding_box();
+                assert!(aabb.grow(hit.point) == aabb, "{}: hit {:?} outside bounding box {:?}", name, hit.point, aabb);
+            },
+            None => panic!("{}: expected a hit", name),
+        }
+    }
+
+    fn expect_miss(name:&str, object:&dyn Hittable, ray:Ray, t_min:f32, t_max:f32) {
+        if let Some(hit) = object.hit(&ray, t_min, t_max) {
+            panic!("{}: unexpected hit at t {}", name, hit.t);
+        }
+    }
+
+
+    #[test]
+    fn sphere_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let sphere = Sphere::new(Vec3::zero(), 1.0, material.clone());
+        expect_hit("sphere outside", &sphere, ray([0.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 0.001, inf, (2.0, [0.0, 0.0, -1.0], true));
+        expect_hit("sphere inside", &sphere, ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]), 0.001, inf, (1.0, [0.0, 0.0, -1.0], false));
+        expect_hit("sphere grazing", &sphere, ray([1.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 0.001, inf, (3.0, [1.0, 0.0, 0.0], true));
+        expect_miss("sphere just outside", &sphere, ray([1.001, 0.0, -3.0], [0.0, 0.0, 1.0]), 0.001, inf);
+        expect_hit("sphere near root clipped by t_min", &sphere, ray([0.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 2.5, inf, (4.0, [0.0, 0.0, -1.0], false));
+        expect_miss("sphere clipped by t_max", &sphere, ray([0.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 0.001, 1.5);
+        expect_miss("sphere behind", &sphere, ray([0.0, 0.0, 3.0], [0.0, 0.0, 1.0]), 0.001, inf);
+        let hollow = Sphere::new(Vec3::zero(), -1.0, material.clone());
+        expect_hit("sphere negative radius", &hollow, ray([0.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 0.001, inf, (2.0, [0.0, 0.0, -1.0], false));
+    }
+
+    #[test]
+    fn plane_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let plane = Plane::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0), material.clone());
+        expect_hit("plane above", &plane, ray([0.3, 2.0, 0.7], [0.0, -1.0, 0.0]), 0.001, inf, (2.0, [0.0, 1.0, 0.0], true));
+        expect_hit("plane below", &plane, ray([0.0, -2.0, 0.0], [0.0, 1.0, 0.0]), 0.001, inf, (2.0, [0.0, -1.0, 0.0], false));
+        expect_miss("plane parallel", &plane, ray([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+        expect_miss("plane clipped by t_max", &plane, ray([0.0, 2.0, 0.0], [0.0, -1.0, 0.0]), 0.001, 1.999);
+    }
+
+    #[test]
+    fn triangle_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let triangle = Triangle::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), material.clone());
+        expect_hit("triangle front", &triangle, ray([0.25, 0.25, 1.0], [0.0, 0.0, -1.0]), 0.001, inf, (1.0, [0.0, 0.0, 1.0], true));
+        expect_hit("triangle back", &triangle, ray([0.25, 0.25, -1.0], [0.0, 0.0, 1.0]), 0.001, inf, (1.0, [0.0, 0.0, -1.0], false));
+        expect_hit("triangle edge", &triangle, ray([0.5, 0.5, 1.0], [0.0, 0.0, -1.0]), 0.001, inf, (1.0, [0.0, 0.0, 1.0], true));
+        expect_hit("triangle vertex", &triangle, ray([0.0, 0.0, 1.0], [0.0, 0.0, -1.0]), 0.001, inf, (1.0, [0.0, 0.0, 1.0], true));
+        expect_miss("triangle outside edge", &triangle, ray([0.51, 0.51, 1.0], [0.0, 0.0, -1.0]), 0.001, inf);
+        expect_miss("triangle edge on", &triangle, ray([-1.0, 0.25, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+        expect_miss("triangle clipped by t_min", &triangle, ray([0.25, 0.25, 1.0], [0.0, 0.0, -1.0]), 1.001, inf);
+    }
+
+    #[test]
+    fn quad_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let quad = Quad::new(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), material.clone());
+        expect_hit("quad front", &quad, ray([0.0, 0.0, 2.0], [0.0, 0.0, -1.0]), 0.001, inf, (2.0, [0.0, 0.0, 1.0], true));
+        expect_hit("quad back", &quad, ray([0.0, 0.0, -2.0], [0.0, 0.0, 1.0]), 0.001, inf, (2.0, [0.0, 0.0, -1.0], false));
+        expect_hit("quad corner", &quad, ray([1.0, 1.0, 2.0], [0.0, 0.0, -1.0]), 0.001, inf, (2.0, [0.0, 0.0, 1.0], true));
+        expect_miss("quad outside", &quad, ray([1.01, 0.0, 2.0], [0.0, 0.0, -1.0]), 0.001, inf);
+        expect_miss("quad edge on", &quad, ray([-2.0, 0.0, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+    }
+
+    #[test]
+    fn aa_box_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let aa_box = AaBox::new(Vec3::splat(-1.0), Vec3::splat(1.0), material.clone());
+        expect_hit("box outside", &aa_box, ray([-3.0, 0.2, 0.3], [1.0, 0.0, 0.0]), 0.001, inf, (2.0, [-1.0, 0.0, 0.0], true));
+        expect_hit("box inside", &aa_box, ray([0.0, 0.0, 0.0], [0.0, 1.0, 0.0]), 0.001, inf, (1.0, [0.0, -1.0, 0.0], false));
+        expect_hit("box entry clipped by t_min", &aa_box, ray([0.0, 0.0, -3.0], [0.0, 0.0, 1.0]), 2.5, inf, (4.0, [0.0, 0.0, -1.0], false));
+        expect_hit("box grazing a face", &aa_box, ray([-3.0, 1.0, 0.0], [1.0, 0.0, 0.0]), 0.001, inf, (2.0, [-1.0, 0.0, 0.0], true));
+        expect_miss("box just outside", &aa_box, ray([-3.0, 1.001, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+        expect_miss("box clipped by t_max", &aa_box, ray([-3.0, 0.0, 0.0], [1.0, 0.0, 0.0]), 0.001, 1.5);
+    }
+
+    #[test]
+    fn disk_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let disk = Disk::new(Vec3::zero(), Vec3::new(0.0, 0.0, 1.0), 1.0, material.clone());
+        expect_hit("disk front", &disk, ray([0.5, 0.0, 1.0], [0.0, 0.0, -1.0]), 0.001, inf, (1.0, [0.0, 0.0, 1.0], true));
+        expect_hit("disk back", &disk, ray([0.0, 0.5, -1.0], [0.0, 0.0, 1.0]), 0.001, inf, (1.0, [0.0, 0.0, -1.0], false));
+        expect_hit("disk rim", &disk, ray([1.0, 0.0, 1.0], [0.0, 0.0, -1.0]), 0.001, inf, (1.0, [0.0, 0.0, 1.0], true));
+        expect_miss("disk outside rim", &disk, ray([0.8, 0.8, 1.0], [0.0, 0.0, -1.0]), 0.001, inf);
+        expect_miss("disk edge on", &disk, ray([-2.0, 0.0, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+    }
+
+    #[test]
+    fn cylinder_edge_cases() {
+        let (material, inf) = (absorb(), f32::INFINITY);
+        let cylinder = Cylinder::new(Vec3::zero(), Vec3::new(0.0, 2.0, 0.0), 1.0, material.clone());
+        expect_hit("cylinder side", &cylinder, ray([-3.0, 1.0, 0.0], [1.0, 0.0, 0.0]), 0.001, inf, (2.0, [-1.0, 0.0, 0.0], true));
+        expect_hit("cylinder top cap", &cylinder, ray([0.5, 5.0, 0.0], [0.0, -1.0, 0.0]), 0.001, inf, (3.0, [0.0, 1.0, 0.0], true));
+        expect_hit("cylinder bottom cap", &cylinder, ray([0.0, -1.0, 0.5], [0.0, 1.0, 0.0]), 0.001, inf, (1.0, [0.0, -1.0, 0.0], true));
+        expect_hit("cylinder inside side", &cylinder, ray([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]), 0.001, inf, (1.0, [0.0, 0.0, -1.0], false));
+        expect_hit("cylinder inside cap", &cylinder, ray([0.0, 1.0, 0.0], [0.0, 1.0, 0.0]), 0.001, inf, (1.0, [0.0, -1.0, 0.0], false));
+        expect_hit("cylinder grazing side", &cylinder, ray([1.0, 1.0, -3.0], [0.0, 0.0, 1.0]), 0.001, inf, (3.0, [1.0, 0.0, 0.0], true));
+        expect_hit("cylinder side clipped by t_min", &cylinder, ray([-3.0, 1.0, 0.0], [1.0, 0.0, 0.0]), 2.5, inf, (4.0, [-1.0, 0.0, 0.0], false));
+        expect_miss("cylinder above", &cylinder, ray([-3.0, 2.5, 0.0], [1.0, 0.0, 0.0]), 0.001, inf);
+        expect_miss("cylinder clipped by t_max", &cylinder, ray([0.0, 5.0, 0.0], [0.0, -1.0, 0.0]), 0.001, 2.5);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::vec3::{Point, Vec3};
+
+
+// time is when in the shutter interval the ray was sent, moving objects are hit where they were then
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Ray {
+    pub origin:Point,
+    pub direction:Vec3,
+    pub time:f32,
+}
+
+impl Ray {
+    pub fn new(origin:Point, direction:Vec3) -> Ray {
+        Ray { origin, direction, time:0.0 }
+    }
+
+    pub fn with_time(self, time:f32) -> Ray {
+        Ray { time, ..self }
+    }
+
+    pub fn at(&self, t:f32) -> Point {
+        self.origin + self.direction * t
+    }
+}
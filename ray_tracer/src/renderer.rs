@@ -0,0 +1,427 @@
+use images::image::{ChannelLayout, Image};
+use images::netpbm::{save_netpbm, NetpbmFormat};
+use images::pfm::save_pfm;
+use images::png::save_png;
+use images::radiance::save_radiance;
+use images::tone_map::{tone_map, ToneMapSettings};
+
+use crate::camera::Camera;
+use crate::errors::RayTracerError;
+use crate::hittable::Hittable;
+use crate::light::LightList;
+use crate::ray::Ray;
+use crate::rng::Pcg32;
+use crate::sampler::{concentric_disk, PixelSample, Sampler};
+use crate::vec3::Colour;
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+
+// the light arriving from rays that leave the scene
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Background {
+    Solid(Colour),
+    // blends from the first colour at the horizon to the second straight up
+    Gradient(Colour, Colour),
+}
+
+impl Background {
+    pub fn colour(&self, ray:&Ray) -> Colour {
+        match self {
+            Background::Solid(colour) => *colour,
+            Background::Gradient(horizon, zenith) => {
+                let t = 0.5 * (ray.direction.unit().y + 1.0);
+                horizon.lerp(*zenith, t)
+            },
+        }
+    }
+}
+
+
+// threads of 0 uses every available core, the image depends only on seed and sampler, never on threads or tiles
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RenderSettings {
+    pub width:usize,
+    pub height:usize,
+    pub samples_per_pixel:u32,
+    pub max_depth:u32,
+    pub tile_size:usize,
+    pub threads:usize,
+    pub seed:u64,
+    pub sampler:Sampler,
+    pub background:Background,
+}
+
+impl RenderSettings {
+    pub fn new(width:usize, height:usize) -> RenderSettings {
+        RenderSettings {
+            width,
+            height,
+            samples_per_pixel:64,
+            max_depth:16,
+            tile_size:32,
+            threads:0,
+            seed:0,
+            sampler:Sampler::Independent,
+            background:Background::Gradient(Colour::splat(1.0), Colour::new(0.5, 0.7, 1.0)),
+        }
+    }
+
+    pub fn thread_count(&self) -> usize {
+        match self.threads {
+            0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        }
+    }
+
+    pub fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = vec![];
+        for y in (0..self.height).step_by(self.tile_size.max(1)) {
+            for x in (0..self.width).step_by(self.tile_size.max(1)) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width:self.tile_size.min(self.width - x),
+                    height:self.tile_size.min(self.height - y),
+                });
+            }
+        }
+        tiles
+    }
+}
+
+
+// a rectangle of pixels, y counts down from the top row like the image
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tile {
+    pub x:usize,
+    pub y:usize,
+    pub width:usize,
+    pub height:usize,
+}
+
+
+// reported after every finished tile
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Progress {
+    pub tiles_done:usize,
+    pub tiles_total:usize,
+    pub elapsed:Duration,
+    pub eta:Option<Duration>,
+    pub rays_per_second:f64,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        self.tiles_done as f32 / self.tiles_total.max(1) as f32
+    }
+}
+
+
+// the weight of a sample from a strategy with density a, when one with density b could have made it too
+fn power_heuristic(a:f32, b:f32) -> f32 {
+    match a > 0.0 {
+        true => a * a / (a * a + b * b),
+        false => 0.0,
+    }
+}
+
+// follows a ray through the scene until it is absorbed, escapes or runs out of bounces
+// at every surface with a pdf a shadow ray goes to a point on a light, and both that and the light
+// the scattered ray finds are weighted by multiple importance sampling, with no lights it is plain path tracing
+pub fn radiance(world:&dyn Hittable, lights:&LightList, ray:&Ray, settings:&RenderSettings, rng:&mut Pcg32, rays:&mut u64) -> Colour {
+    let mut ray = *ray;
+    let mut throughput = Colour::splat(1.0);
+    let mut colour = Colour::zero();
+    // the density the last bounce was sampled with, None from the camera or a mirror
+    let mut scatter_pdf : Option<f32> = None;
+
+    for _ in 0..settings.max_depth {
+        *rays += 1;
+        let hit = match world.hit(&ray, 0.001, f32::INFINITY) {
+            Some(hit) => hit,
+            None => return colour + throughput * settings.background.colour(&ray),
+        };
+
+        let emitted = hit.material.emitted(&hit);
+        if emitted != Colour::zero() {
+            let weight = match scatter_pdf {
+                Some(pdf) => power_heuristic(pdf, lights.pdf(&ray, &hit)),
+                None => 1.0,
+            };
+            colour += throughput * emitted * weight;
+        }
+
+        let scatter = match hit.material.scatter(&ray, &hit, rng) {
+            Some(scatter) => scatter,
+            None => return colour,
+        };
+
+        if scatter.pdf.is_some() && let Some(light) = lights.sample(hit.point, rng) {
+            let value = hit.material.eval(&ray, &hit, light.direction);
+            if value != Colour::zero() && light.emitted != Colour::zero() {
+                *rays += 1;
+                let shadow = Ray::new(hit.point, light.direction).with_time(ray.time);
+                if world.hit(&shadow, 0.001, light.distance * (1.0 - 1e-4)).is_none() {
+                    let weight = power_heuristic(light.pdf, hit.material.pdf(&ray, &hit, light.direction));
+                    colour += throughput * value * light.emitted * (weight / light.pdf);
+                }
+            }
+        }
+
+        throughput *= scatter.attenuation;
+        scatter_pdf = scatter.pdf;
+        // materials only pick a direction, the whole path happens at the time the camera sent it
+        ray = scatter.ray.with_time(ray.time);
+    }
+    colour
+}
+
+// the sampler dimensions the camera ray is made from
+const PIXEL_DIMENSION : u32 = 0;
+const LENS_DIMENSION : u32 = 2;
+const TIME_DIMENSION : u32 = 4;
+
+// camera ray index of pixel x, y, its position in the pixel, on the lens and in time from the sampler
+// and the rest of its path from a random stream of its own
+#[allow(clippy::too_many_arguments)]
+pub fn sample_pixel(world:&dyn Hittable, lights:&LightList, camera:&Camera, settings:&RenderSettings, x:usize, y:usize,
+                    index:u32, rays:&mut u64) -> Colour {
+    let sample = PixelSample::new(x as u32, y as u32, index, settings.samples_per_pixel, settings.seed);
+    let [dx, dy] = settings.sampler.get_2d(&sample, PIXEL_DIMENSION);
+    let s = (x as f32 + dx) / settings.width as f32;
+    let t = 1.0 - (y as f32 + dy) / settings.height as f32;
+    let lens = concentric_disk(settings.sampler.get_2d(&sample, LENS_DIMENSION));
+    let ray = camera.ray_at(s, t, lens, settings.sampler.get_1d(&sample, TIME_DIMENSION));
+    radiance(world, lights, &ray, settings, &mut sample.rng(), rays)
+}
+
+// every sample of every pixel has its own random stream, so the result doesn't depend on which thread renders it
+pub fn render_pixel(world:&dyn Hittable, lights:&LightList, camera:&Camera, settings:&RenderSettings, x:usize, y:usize, rays:&mut u64) -> Colour {
+    let mut sum = Colour::zero();
+    for index in 0..settings.samples_per_pixel {
+        sum += sample_pixel(world, lights, camera, settings, x, y, index, rays);
+    }
+    sum / settings.samples_per_pixel.max(1) as f32
+}
+
+fn render_tile(world:&dyn Hittable, lights:&LightList, camera:&Camera, settings:&RenderSettings, tile:&Tile, rays:&mut u64) -> Vec<Colour> {
+    let mut pixels = Vec::with_capacity(tile.width * tile.height);
+    for y in tile.y..tile.y + tile.height {
+        for x in tile.x..tile.x + tile.width {
+            pixels.push(render_pixel(world, lights, camera, settings, x, y, rays));
+        }
+    }
+    pixels
+}
+
+
+// renders tiles across a pool of scoped threads into a linear float image
+// progress is called on the calling thread, and setting cancel stops the workers after their current tile
+pub fn render(world:&dyn Hittable, lights:&LightList, camera:&Camera, settings:&RenderSettings,
+              progress:&mut dyn FnMut(&Progress), cancel:&AtomicBool) -> Result<Image, RayTracerError> {
+    let tiles = settings.tiles();
+    let next_tile = AtomicUsize::new(0);
+    let rays = AtomicU64::new(0);
+    let start = Instant::now();
+    let mut image = Image::new_f32(settings.width, settings.height, ChannelLayout::Rgb);
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..settings.thread_count().min(tiles.len()) {
+            let sender = sender.clone();
+            let (tiles, next_tile, rays) = (&tiles, &next_tile, &rays);
+            scope.spawn(move || {
+                while !cancel.load(Ordering::Relaxed) {
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    let tile = match tiles.get(index) {
+                        Some(tile) => tile,
+                        None => break,
+                    };
+                    let mut tile_rays = 0;
+                    let pixels = render_tile(world, lights, camera, settings, tile, &mut tile_rays);
+                    rays.fetch_add(tile_rays, Ordering::Relaxed);
+                    if sender.send((*tile, pixels)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        // the workers hold the only senders left, so the loop ends when they all finish
+        drop(sender);
+
+        for (tiles_done, (tile, pixels)) in receiver.iter().enumerate() {
+            for (i, colour) in pixels.iter().enumerate() {
+                image.set_rgb(tile.x + i % tile.width, tile.y + i / tile.width, colour.to_array());
+            }
+
+            let elapsed = start.elapsed();
+            let tiles_done = tiles_done + 1;
+            let remaining = (tiles.len() - tiles_done) as f64 / tiles_done as f64;
+            progress(&Progress {
+                tiles_done,
+                tiles_total:tiles.len(),
+                elapsed,
+                eta:Some(elapsed.mul_f64(remaining)).filter(|_| tiles_done < tiles.len()),
+                rays_per_second:rays.load(Ordering::Relaxed) as f64 / elapsed.as_secs_f64().max(1e-9),
+            });
+        }
+    });
+
+    match cancel.load(Ordering::Relaxed) {
+        true => Err(RayTracerError::RenderCancelled),
+        false => Ok(image),
+    }
+}
+
+// .pfm and .hdr keep linear radiance, .ppm and anything else (written as png) are tone mapped
+pub fn save_render(image:&Image, path:&str) -> Result<(), RayTracerError> {
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "pfm" => save_pfm(image, path)?,
+        "hdr" => save_radiance(image, path)?,
+        "ppm" => save_netpbm(&tone_map(image, &ToneMapSettings::new()), path, NetpbmFormat::PixmapBinary)?,
+        _ => save_png(&tone_map(image, &ToneMapSettings::new()), path)?,
+    }
+    Ok(())
+}
+
+
+
+// sums one sample per pixel per pass, for previews that refine while they are watched
+// pass n takes sample n of every pixel, so after samples_per_pixel passes the sum is what render would average
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    pub width:usize,
+    pub height:usize,
+    pub passes:u32,
+    pub rays:u64,
+    pub sum:Vec<Colour>,
+}
+
+impl Accumulator {
+    pub fn new(width:usize, height:usize) -> Accumulator {
+        Accumulator { width, height, passes:0, rays:0, sum:vec![Colour::zero(); width * height] }
+    }
+
+    pub fn reset(&mut self) {
+        self.passes = 0;
+        self.rays = 0;
+        self.sum.fill(Colour::zero());
+    }
+
+    // settings width and height are taken from the accumulator, samples_per_pixel is only how many
+    // samples the sampler spreads out together, passes past it carry on with another set
+    pub fn sample_pass(&mut self, world:&dyn Hittable, lights:&LightList, camera:&Camera, settings:&RenderSettings) {
+        let settings = RenderSettings { width:self.width, height:self.height, ..*settings };
+        let pass = self.passes;
+        let rows_per_chunk = settings.tile_size.max(1);
+        let chunks = Mutex::new(self.sum.chunks_mut(rows_per_chunk * self.width.max(1)).enumerate());
+        let rays = AtomicU64::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..settings.thread_count() {
+                let (chunks, rays, settings) = (&chunks, &rays, &settings);
+                scope.spawn(move || {
+                    let mut chunk_rays = 0;
+                    loop {
+                        // the lock is only held to take the next chunk
+                        let next = chunks.lock().ok().and_then(|mut chunks| chunks.next());
+                        let (chunk_index, chunk) = match next {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        for (i, sum) in chunk.iter_mut().enumerate() {
+                            let pixel = chunk_index * rows_per_chunk * settings.width + i;
+                            let (x, y) = (pixel % settings.width, pixel / settings.width);
+                            *sum += sample_pixel(world, lights, camera, settings, x, y, pass, &mut chunk_rays);
+                        }
+                    }
+                    rays.fetch_add(chunk_rays, Ordering::Relaxed);
+                });
+            }
+        });
+
+        self.passes += 1;
+        self.rays += rays.into_inner();
+    }
+
+    // the running average as a linear float image
+    pub fn average(&self) -> Image {
+        let mut image = Image::new_f32(self.width, self.height, ChannelLayout::Rgb);
+        let scale = 1.0 / self.passes.max(1) as f32;
+        for (i, sum) in self.sum.iter().enumerate() {
+            image.set_rgb(i % self.width, i / self.width, (*sum * scale).to_array());
+        }
+        image
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::CameraSettings;
+    use crate::hittable::HittableList;
+    use crate::material::{Dielectric, Lambertian, Metal};
+    use crate::primitives::Sphere;
+    use crate::vec3::{Point, Vec3};
+
+    use std::sync::Arc;
+
+    // a diffuse, a glass and a metal sphere on a ground sphere, lit by the sky
+    fn spheres() -> (HittableList, Camera) {
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(Point::new(0.0, -1000.0, 0.0), 1000.0, Arc::new(Lambertian::new(Colour::splat(0.5))))));
+        world.add(Arc::new(Sphere::new(Point::new(-2.1, 1.0, 0.0), 1.0, Arc::new(Lambertian::new(Colour::new(0.7, 0.3, 0.2))))));
+        world.add(Arc::new(Sphere::new(Point::new(0.0, 1.0, 0.0), 1.0, Arc::new(Dielectric::new(1.5)))));
+        world.add(Arc::new(Sphere::new(Point::new(2.1, 1.0, 0.0), 1.0, Arc::new(Metal::new(Colour::new(0.8, 0.7, 0.5), 0.05)))));
+        let camera = Camera::new(&CameraSettings {
+            look_from:Point::new(0.0, 2.0, 8.0),
+            look_at:Point::new(0.0, 0.8, 0.0),
+            up:Vec3::new(0.0, 1.0, 0.0),
+            aperture:0.1,
+            focus_distance:8.0,
+            ..CameraSettings::new()
+        });
+        (world, camera)
+    }
+
+    #[test]
+    fn renders_are_the_same_on_any_number_of_threads() {
+        let (world, camera) = spheres();
+        let lights = LightList::new(vec![]);
+        let settings = RenderSettings { samples_per_pixel:2, threads:1, ..RenderSettings::new(48, 27) };
+        let cancel = AtomicBool::new(false);
+        let single = render(&world, &lights, &camera, &settings, &mut |_| {}, &cancel).expect("a render");
+        for (threads, tile_size) in [(8, 32), (3, 7)] {
+            let pooled = render(&world, &lights, &camera, &RenderSettings { threads, tile_size, ..settings }, &mut |_| {}, &cancel).expect("a render");
+            assert!(single.data == pooled.data, "renders on 1 and {} threads differ", threads);
+        }
+        let reseeded = render(&world, &lights, &camera, &RenderSettings { seed:1, ..settings }, &mut |_| {}, &cancel).expect("a render");
+        assert!(single.data != reseeded.data, "the seed changes the noise");
+    }
+
+    #[test]
+    fn renders_report_progress_and_can_be_cancelled() {
+        let (world, camera) = spheres();
+        let lights = LightList::new(vec![]);
+        let settings = RenderSettings { samples_per_pixel:1, tile_size:4, threads:2, ..RenderSettings::new(48, 27) };
+
+        let mut reports = vec![];
+        render(&world, &lights, &camera, &settings, &mut |progress| reports.push(progress.tiles_done), &AtomicBool::new(false)).expect("a render");
+        assert_eq!(reports, (1..=settings.tiles().len()).collect::<Vec<usize>>());
+
+        let cancel = AtomicBool::new(false);
+        let cancelled = render(&world, &lights, &camera, &settings, &mut |progress| {
+            if progress.tiles_done >= 3 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }, &cancel);
+        assert!(matches!(cancelled, Err(RayTracerError::RenderCancelled)), "render was not cancelled");
+    }
+}
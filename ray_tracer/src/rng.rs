@@ -0,0 +1,54 @@
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+
+
+// pcg32 (xsh rr), small and fast, and seedable per pixel or tile so renders are reproducible
+#[derive(Debug, Clone)]
+pub struct Pcg32 {
+    state:u64,
+    increment:u64,
+}
+
+impl Pcg32 {
+    const MULTIPLIER : u64 = 6364136223846793005;
+
+    // different streams give independent sequences for the same seed
+    pub fn new(seed:u64, stream:u64) -> Pcg32 {
+        let mut rng = Pcg32 { state:0, increment:(stream << 1) | 1 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Pcg32::MULTIPLIER).wrapping_add(self.increment);
+        let xor_shifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    pub fn range(&mut self, min:f32, max:f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    pub fn unit_vector(&mut self) -> Vec3 {
+        let z = self.range(-1.0, 1.0);
+        let phi = 2.0 * PI * self.next_f32();
+        let r = (1.0 - z*z).max(0.0).sqrt();
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+
+    pub fn in_unit_disk(&mut self) -> Vec3 {
+        let r = self.next_f32().sqrt();
+        let phi = 2.0 * PI * self.next_f32();
+        Vec3::new(r * phi.cos(), r * phi.sin(), 0.0)
+    }
+}
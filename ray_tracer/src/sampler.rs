@@ -0,0 +1,417 @@
+use crate::rng::Pcg32;
+use crate::vec3::Vec3;
+
+use std::f32::consts::PI;
+
+
+// the largest f32 below 1
+const ONE_MINUS_EPSILON : f32 = 1.0 - f32::EPSILON / 2.0;
+
+const PRIMES : [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131,
+];
+
+
+// splitmix64 finaliser over each value in turn
+fn hash(values:&[u64]) -> u64 {
+    values.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, value| {
+        let mut z = (hash ^ value).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
+
+// the top 24 bits as a float in [0, 1)
+fn to_unit(bits:u32) -> f32 {
+    (bits >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+// a random permutation of 0 to length - 1 chosen by key, evaluated one index at a time (kensler's permute)
+fn permute(mut i:u32, length:u32, key:u32) -> u32 {
+    let mut w = length.max(1) - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // the permutation is of the next power of two, indices past length cycle until they land inside
+    loop {
+        i ^= key;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= key >> 16;
+        i ^= (i & w) >> 4;
+        i ^= key >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= key >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | key >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length.max(1) {
+            return i.wrapping_add(key) % length.max(1);
+        }
+    }
+}
+
+// the digits of index in base mirrored about the point, 0.1 0.01 0.11 for 1 2 3 in base 2
+fn radical_inverse(base:u32, mut index:u32) -> f32 {
+    let (mut reversed, mut scale) = (0u64, 1.0f64);
+    while index > 0 {
+        reversed = reversed * base as u64 + (index % base) as u64;
+        scale /= base as f64;
+        index /= base;
+    }
+    ((reversed as f64 * scale) as f32).min(ONE_MINUS_EPSILON)
+}
+
+// the first two dimensions of the sobol sequence as 32 bit fractions, the first is van der corput
+fn sobol_2d(index:u32) -> [u32; 2] {
+    let (mut second, mut direction, mut bits) = (0u32, 1u32 << 31, index);
+    while bits != 0 {
+        if bits & 1 != 0 {
+            second ^= direction;
+        }
+        bits >>= 1;
+        direction ^= direction >> 1;
+    }
+    [index.reverse_bits(), second]
+}
+
+// owen scrambling by hashing (laine and karras, with burley's constants), each bit is flipped depending on the bits above it
+// a block of 2^m aligned values goes to another such block, so scrambled sobol points keep their stratification
+fn owen_scramble(value:u32, seed:u32) -> u32 {
+    let mut x = value.reverse_bits();
+    x ^= x.wrapping_mul(0x3d20_adea);
+    x = x.wrapping_add(seed);
+    x = x.wrapping_mul((seed >> 16) | 1);
+    x ^= x.wrapping_mul(0x0552_6c56);
+    x ^= x.wrapping_mul(0x53a2_2864);
+    x.reverse_bits()
+}
+
+// the pixels in z order with the four quadrants of every square visited in an order chosen by seed and the squares
+// around them (ahmed and wonka), so neighbouring pixels take neighbouring blocks of one sequence between them
+fn scrambled_morton(x:u32, y:u32, seed:u32) -> u32 {
+    let mut index = 0u32;
+    for level in (0..16).rev() {
+        let quadrant = ((x >> level) & 1) | (((y >> level) & 1) << 1);
+        let key = hash(&[seed as u64, level as u64, index as u64]) as u32;
+        index = (index << 2) | permute(quadrant, 4, key);
+    }
+    index
+}
+
+
+// which sample of which pixel, count is how many samples the pixel is meant to get and seed picks the whole image's samples
+// samples past count carry on with another set of count
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PixelSample {
+    pub x:u32,
+    pub y:u32,
+    pub index:u32,
+    pub count:u32,
+    pub seed:u64,
+}
+
+impl PixelSample {
+    pub fn new(x:u32, y:u32, index:u32, count:u32, seed:u64) -> PixelSample {
+        PixelSample { x, y, index, count:count.max(1), seed }
+    }
+
+    // the random stream for everything a sampler doesn't give, its own for every pixel and sample
+    pub fn rng(&self) -> Pcg32 {
+        Pcg32::new(hash(&[self.seed, self.index as u64]), ((self.y as u64) << 32) | self.x as u64)
+    }
+
+    // hashed from the pixel, sample and dimension, so the value is the same whichever thread asks for it
+    fn random(&self, dimension:u32) -> u32 {
+        hash(&[self.seed, self.x as u64, self.y as u64, self.index as u64, dimension as u64]) as u32
+    }
+
+    // a key that is the same for every sample of the pixel
+    fn pixel_key(&self, dimension:u32, salt:u64) -> u32 {
+        hash(&[self.seed, self.x as u64, self.y as u64, dimension as u64, salt]) as u32
+    }
+
+    // a key that is the same for every sample of a round of count in the pixel
+    fn round_key(&self, dimension:u32) -> u32 {
+        self.pixel_key(dimension, (self.index / self.count) as u64 + 1)
+    }
+}
+
+
+// how the samples of a pixel are spread over each dimension of the integral, the camera takes the pixel position,
+// lens position and time from it, and everything after from the sample's own random stream
+// every value depends only on the pixel, the sample and the seed, so renders are the same however they are threaded
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Sampler {
+    // every value random
+    Independent,
+    // one random value in each of count strata, the strata of a pixel visited in a random order
+    // 2d strata are a grid with no fewer cells than count, so some are left empty unless count is square
+    Stratified,
+    // the halton sequence with a prime base for each dimension, shifted at random in every pixel
+    Halton,
+    // two dimensions at a time of the sobol sequence, with its bits flipped at random in every pixel
+    Sobol,
+    // owen scrambled sobol shared by the whole image, pixels take blocks of it in a scrambled z order
+    // so the error in neighbouring pixels cancels out and what is left is high frequency noise
+    BlueNoise,
+}
+
+impl Sampler {
+    // a value in [0, 1) for dimension
+    pub fn get_1d(&self, sample:&PixelSample, dimension:u32) -> f32 {
+        match self {
+            Sampler::Independent => to_unit(sample.random(dimension)),
+            Sampler::Stratified => {
+                let stratum = permute(sample.index % sample.count, sample.count, sample.round_key(dimension));
+                (stratum as f32 + to_unit(sample.random(dimension))) / sample.count as f32
+            },
+            Sampler::Halton => Sampler::halton(sample, dimension),
+            Sampler::Sobol | Sampler::BlueNoise => to_unit(self.sobol(sample, dimension)[0]),
+        }
+    }
+
+    // a point in [0, 1)^2 for dimension and the one after it
+    pub fn get_2d(&self, sample:&PixelSample, dimension:u32) -> [f32; 2] {
+        match self {
+            Sampler::Independent => [to_unit(sample.random(dimension)), to_unit(sample.random(dimension + 1))],
+            Sampler::Stratified => {
+                let columns = (sample.count as f32).sqrt().ceil() as u32;
+                let rows = sample.count.div_ceil(columns);
+                let cell = permute(sample.index % sample.count, columns * rows, sample.round_key(dimension));
+                [
+                    ((cell % columns) as f32 + to_unit(sample.random(dimension))) / columns as f32,
+                    ((cell / columns) as f32 + to_unit(sample.random(dimension + 1))) / rows as f32,
+                ]
+            },
+            Sampler::Halton => [Sampler::halton(sample, dimension), Sampler::halton(sample, dimension + 1)],
+            Sampler::Sobol | Sampler::BlueNoise => self.sobol(sample, dimension).map(to_unit),
+        }
+    }
+
+    // dimensions past the table of primes fall back to random values
+    fn halton(sample:&PixelSample, dimension:u32) -> f32 {
+        match PRIMES.get(dimension as usize) {
+            Some(base) => {
+                let shift = to_unit(sample.pixel_key(dimension, 0));
+                (radical_inverse(*base, sample.index) + shift).fract().min(ONE_MINUS_EPSILON)
+            },
+            None => to_unit(sample.random(dimension)),
+        }
+    }
+
+    // sobol is sampled two dimensions at a time, each pair from a differently shuffled copy of the sequence,
+    // which keeps every pair well spread and the pairs unrelated to each other
+    fn sobol(&self, sample:&PixelSample, dimension:u32) -> [u32; 2] {
+        match self {
+            Sampler::BlueNoise => {
+                // the whole image shares one sequence, each pixel's block rounded up to a power of two so blocks stay aligned
+                let block = sample.count.next_power_of_two();
+                let round = (sample.index / block) as u64;
+                let key = |salt:u64| hash(&[sample.seed, round, dimension as u64, salt]) as u32;
+                let pixel = scrambled_morton(sample.x, sample.y, hash(&[sample.seed, round]) as u32);
+                // big images at high counts run past 32 bits, the bits above pick the scramble so pixels
+                // that share the low bits still get their own points
+                let index = (pixel as u64 * block as u64) | (sample.index % block) as u64;
+                let scramble = match index >> 32 {
+                    0 => key(0),
+                    high => hash(&[sample.seed, round, dimension as u64, 0, high]) as u32,
+                };
+                let [a, b] = sobol_2d(owen_scramble(index as u32, scramble));
+                [owen_scramble(a, key(1)), owen_scramble(b, key(2))]
+            },
+            _ => {
+                // flipping bits below the block size reorders each block of points without leaving it
+                let mask = sample.count.next_power_of_two() - 1;
+                let index = sample.index ^ (sample.pixel_key(dimension, 0) & mask);
+                let [a, b] = sobol_2d(index);
+                [a ^ sample.pixel_key(dimension, 1), b ^ sample.pixel_key(dimension, 2)]
+            },
+        }
+    }
+}
+
+// uniform on the unit disk in the z = 0 plane, by shirley and chiu's concentric mapping,
+// which keeps points that were well spread over the square well spread over the disk
+pub fn concentric_disk(point:[f32; 2]) -> Vec3 {
+    let (a, b) = (2.0 * point[0] - 1.0, 2.0 * point[1] - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vec3::zero();
+    }
+    let (r, phi) = match a.abs() > b.abs() {
+        true => (a, PI / 4.0 * (b / a)),
+        false => (b, PI / 2.0 - PI / 4.0 * (a / b)),
+    };
+    Vec3::new(r * phi.cos(), r * phi.sin(), 0.0)
+}
+
+
+// every sampler gives the same values in [0, 1) whenever it is asked, fills its strata, integrates an edge with less
+// error than independent samples, and blue noise leaves error a blur removes
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLERS : [Sampler; 5] = [Sampler::Independent, Sampler::Stratified, Sampler::Halton, Sampler::Sobol, Sampler::BlueNoise];
+
+    // the points of dimensions 0 and 1 for the count samples of pixel x, y
+    fn pixel_points(sampler:Sampler, x:u32, y:u32, count:u32, seed:u64) -> Vec<[f32; 2]> {
+        (0..count).map(|index| sampler.get_2d(&PixelSample::new(x, y, index, count, seed), 0)).collect()
+    }
+
+    // true when every one of the count cells of columns by rows holds exactly one point
+    fn one_per_cell(points:&[[f32; 2]], columns:usize, rows:usize) -> bool {
+        let mut cells = vec![0; columns * rows];
+        for [u, v] in points {
+            cells[(v * rows as f32) as usize * columns + (u * columns as f32) as usize] += 1;
+        }
+        cells.iter().all(|count| *count == 1)
+    }
+
+    // the area under the line v = 0.3 + 0.4u, like a pixel an edge runs through, 0.5 exactly
+    fn edge(point:[f32; 2]) -> f32 {
+        if point[1] < 0.3 + 0.4 * point[0] {1.0} else {0.0}
+    }
+
+    // the error in every pixel of a size by size image, each estimating the edge with count samples
+    fn edge_errors(sampler:Sampler, size:u32, count:u32, seed:u64) -> Vec<f32> {
+        let mut errors = vec![];
+        for y in 0..size {
+            for x in 0..size {
+                let estimate = pixel_points(sampler, x, y, count, seed).into_iter().map(edge).sum::<f32>() / count as f32;
+                errors.push(estimate - 0.5);
+            }
+        }
+        errors
+    }
+
+    fn rms(values:&[f32]) -> f32 {
+        (values.iter().map(|value| value * value).sum::<f32>() / values.len() as f32).sqrt()
+    }
+
+    // how much of the error a 3x3 box blur leaves, a third for white noise and less when neighbours cancel
+    fn blurred_fraction(errors:&[f32], size:usize) -> f32 {
+        let mut blurred = vec![];
+        for y in 1..size - 1 {
+            for x in 1..size - 1 {
+                let sum = (0..9).map(|i| errors[(y + i / 3 - 1) * size + x + i % 3 - 1]).sum::<f32>();
+                blurred.push(sum / 9.0);
+            }
+        }
+        rms(&blurred) / rms(errors)
+    }
+
+
+    #[test]
+    fn samplers_give_the_same_values_in_range_every_time() {
+        for sampler in SAMPLERS {
+            for count in [1, 7, 16, 100] {
+                for index in 0..2 * count {
+                    let sample = PixelSample::new(3, 5, index, count, 11);
+                    for dimension in 0..40 {
+                        let value = sampler.get_1d(&sample, dimension);
+                        assert!((0.0..1.0).contains(&value), "{:?} gave {} in dimension {}", sampler, value, dimension);
+                        assert_eq!(value, sampler.get_1d(&sample, dimension));
+                        let [u, v] = sampler.get_2d(&sample, dimension);
+                        assert!((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v), "{:?} gave {:?}", sampler, [u, v]);
+                    }
+                }
+            }
+            // different seeds and pixels see different samples
+            assert_ne!(pixel_points(sampler, 0, 0, 16, 1), pixel_points(sampler, 0, 0, 16, 2), "{:?} ignores the seed", sampler);
+            assert_ne!(pixel_points(sampler, 0, 0, 16, 1), pixel_points(sampler, 1, 0, 16, 1), "{:?} repeats pixels", sampler);
+        }
+    }
+
+    #[test]
+    fn samples_fill_their_strata() {
+        for (x, y) in [(0, 0), (17, 3), (640, 480)] {
+            assert!(one_per_cell(&pixel_points(Sampler::Stratified, x, y, 16, 5), 4, 4));
+            assert!(one_per_cell(&pixel_points(Sampler::Stratified, x, y, 36, 5), 6, 6));
+            // 16 sobol points are a (0, 4, 2) net, one point in every box of 16 with power of two sides
+            for sampler in [Sampler::Sobol, Sampler::BlueNoise] {
+                for columns in [1, 2, 4, 8, 16] {
+                    assert!(one_per_cell(&pixel_points(sampler, x, y, 16, 5), columns, 16 / columns), "{:?} at {} columns", sampler, columns);
+                }
+                // the second 16 of 32 fill the strata again, the first 32 are a net of their own
+                let points = pixel_points(sampler, x, y, 32, 5);
+                assert!(one_per_cell(&points[16..], 4, 4) && one_per_cell(&points, 8, 4), "{:?} loses its strata past one block", sampler);
+            }
+            let times : Vec<[f32; 2]> = (0..16).map(|index| [Sampler::Halton.get_1d(&PixelSample::new(x, y, index, 16, 5), 0), 0.0]).collect();
+            assert!(one_per_cell(&times, 16, 1), "halton's first dimension is base 2");
+        }
+        // samples past count carry on in a new set of strata rather than repeating
+        let rounds = pixel_points(Sampler::Stratified, 2, 2, 32, 5);
+        let stratified = pixel_points(Sampler::Stratified, 2, 2, 16, 5);
+        let next : Vec<[f32; 2]> = (16..32).map(|index| Sampler::Stratified.get_2d(&PixelSample::new(2, 2, index, 16, 5), 0)).collect();
+        assert!(one_per_cell(&next, 4, 4) && next != stratified && rounds != stratified);
+    }
+
+    #[test]
+    fn concentric_disk_points_are_uniform() {
+        let disk : Vec<f32> = pixel_points(Sampler::Sobol, 0, 0, 256, 3).into_iter().map(|point| concentric_disk(point).length()).collect();
+        assert!(disk.iter().all(|radius| *radius <= 1.0 + 1e-6));
+        assert!((disk.iter().filter(|radius| **radius < 0.5).count() as f32 / 256.0 - 0.25).abs() < 0.02);
+    }
+
+    // the rms error over many pixels of estimating the edge with 64 samples each
+    #[test]
+    fn samplers_beat_independent_samples() {
+        let errors : Vec<f32> = SAMPLERS.iter().map(|sampler| rms(&edge_errors(*sampler, 32, 64, 9))).collect();
+        for (sampler, error) in SAMPLERS.iter().zip(&errors).skip(1) {
+            assert!(*error < errors[0] / 2.0, "{:?} has error {} against independent {}", sampler, error, errors[0]);
+        }
+        assert!(errors[3] < errors[1] && errors[4] < errors[1], "sobol should beat stratified samples");
+    }
+
+    // the pixel scrambled_morton sends to index, found a level at a time
+    fn morton_pixel(index:u32, seed:u32) -> (u32, u32) {
+        let (mut x, mut y, mut prefix) = (0, 0, 0u32);
+        for level in (0..16).rev() {
+            let key = hash(&[seed as u64, level as u64, prefix as u64]) as u32;
+            let digit = (index >> (2 * level)) & 3;
+            let quadrant = (0..4).find(|quadrant| permute(*quadrant, 4, key) == digit).expect("permute is a permutation");
+            x |= (quadrant & 1) << level;
+            y |= (quadrant >> 1) << level;
+            prefix = (prefix << 2) | digit;
+        }
+        (x, y)
+    }
+
+    // at 3840x2160 the z order takes 24 bits and 512 samples 9 more, so pixels whose z order differs
+    // only in its top bit share the low 32 bits of their index
+    #[test]
+    fn far_apart_pixels_of_big_images_get_their_own_points() {
+        let (seed, count) = (7, 512);
+        let morton_seed = hash(&[seed, 0]) as u32;
+        let ((x, y), (far_x, far_y)) = [(1000, 600), (300, 200), (1700, 100)].into_iter()
+            .map(|(x, y)| ((x, y), morton_pixel(scrambled_morton(x, y, morton_seed) ^ (1 << 23), morton_seed)))
+            .find(|(_, (far_x, far_y))| *far_x < 3840 && *far_y < 2160)
+            .expect("one of the pixels has a partner in the image");
+        assert!((far_x ^ x) >> 11 != 0 || (far_y ^ y) >> 11 != 0, "{}, {} is in the same 2048 square as {}, {}", far_x, far_y, x, y);
+        let points = pixel_points(Sampler::BlueNoise, x, y, count, seed);
+        let far_points = pixel_points(Sampler::BlueNoise, far_x, far_y, count, seed);
+        assert!(points.iter().zip(&far_points).all(|(point, far_point)| point != far_point), "pixels share points");
+        assert!(one_per_cell(&far_points, 32, 16), "the far pixel loses its strata");
+    }
+
+    #[test]
+    fn blue_noise_error_is_high_frequency() {
+        let size = 64;
+        let white = blurred_fraction(&edge_errors(Sampler::Sobol, size, 4, 9), size as usize);
+        let blue = blurred_fraction(&edge_errors(Sampler::BlueNoise, size, 4, 9), size as usize);
+        assert!((white - 1.0 / 3.0).abs() < 0.05, "sobol error from pixel to pixel should be white, blurring left {}", white);
+        assert!(blue < 0.75 * white, "blurring should remove more blue noise, it left {}", blue);
+    }
+}
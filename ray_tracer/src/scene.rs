@@ -0,0 +1,808 @@
+use images::formats::load_image;
+use meshes::mesh::MeshMaterial;
+use meshes::obj::load_obj;
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use crate::bvh::Bvh;
+use crate::camera::{Camera, CameraSettings};
+use crate::errors::{RayTracerError, SceneError};
+use crate::hittable::{Hittable, HittableList, Surface};
+use crate::instance::{Instance, MovingInstance};
+use crate::light::LightList;
+use crate::material::{Dielectric, Emissive, Ggx, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal};
+use crate::medium::{load_density_grid, ConstantMedium, GridMedium};
+use crate::mesh::{mesh_material, mesh_triangles};
+use crate::primitives::{AaBox, Cylinder, Disk, Plane, Quad, Sphere, Triangle};
+use crate::renderer::{Background, RenderSettings};
+use crate::sampler::Sampler;
+use crate::texture::{Checker, ImageTexture, NoisePattern, NoiseTexture, SolidColour, Texture, UvChecker, WrapMode};
+use crate::transform::Mat4;
+use crate::vec3::{Point, Vec3};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+
+// the hittables of an object, and the surfaces among them that could be lights
+pub type SceneObjects = (Vec<Arc<dyn Hittable>>, Vec<Arc<dyn Surface>>);
+
+// meshes loaded for instancing, by path and material name, so every instance of one shares its triangles
+pub type InstancedMeshes = BTreeMap<(String, Option<String>), Arc<dyn Hittable>>;
+
+// the key a bad transform or keyframes is reported at, and the error to report it with
+pub type MotionProblem = (&'static str, fn((usize, usize)) -> SceneError);
+
+
+// a scene as written in a toml file, vectors and colours are arrays of three floats
+// objects refer to materials by name and materials to textures, the names are checked when the file is parsed
+// mesh and image paths are relative to the scene file
+// there is no table of lights, every untransformed sphere, triangle, quad and mesh with an emissive material is
+// sampled as a light, other emissive objects only glow where rays happen to find them
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default = "SettingsDescription::new")]
+    pub settings:SettingsDescription,
+    pub camera:CameraDescription,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures:BTreeMap<String, TextureDescription>,
+    #[serde(default)]
+    pub materials:BTreeMap<String, MaterialDescription>,
+    #[serde(default)]
+    pub objects:Vec<ObjectDescription>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsDescription {
+    pub width:usize,
+    pub height:usize,
+    #[serde(default = "defaults::samples_per_pixel")]
+    pub samples_per_pixel:u32,
+    #[serde(default = "defaults::max_depth")]
+    pub max_depth:u32,
+    #[serde(default)]
+    pub seed:u64,
+    #[serde(default)]
+    pub sampler:SamplerDescription,
+    #[serde(default = "BackgroundDescription::new")]
+    pub background:BackgroundDescription,
+}
+
+// how the samples of each pixel are spread, every sampler gives the same image for the same seed however it is rendered
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplerDescription {
+    #[default]
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+    BlueNoise,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BackgroundDescription {
+    Solid { colour:[f32; 3] },
+    Gradient { horizon:[f32; 3], zenith:[f32; 3] },
+}
+
+// the aspect ratio comes from the image size, focus_distance defaults to the distance to look_at
+// rays are sent at times between shutter_open and shutter_close, in the same units as keyframe times
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDescription {
+    pub look_from:[f32; 3],
+    pub look_at:[f32; 3],
+    #[serde(default = "defaults::up")]
+    pub up:[f32; 3],
+    #[serde(default = "defaults::vertical_fov")]
+    pub vertical_fov:f32,
+    #[serde(default)]
+    pub aperture:f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus_distance:Option<f32>,
+    #[serde(default)]
+    pub shutter_open:f32,
+    #[serde(default)]
+    pub shutter_close:f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDescription {
+    Solid { colour:[f32; 3] },
+    // cubes of side size in world space
+    Checker { even:[f32; 3], odd:[f32; 3], #[serde(default = "defaults::one")] size:f32 },
+    UvChecker { even:[f32; 3], odd:[f32; 3], columns:u32, rows:u32 },
+    Image { path:String, #[serde(default)] wrap:WrapDescription },
+    Noise {
+        pattern:NoisePatternDescription,
+        #[serde(default = "defaults::white")]
+        colour:[f32; 3],
+        #[serde(default = "defaults::one")]
+        scale:f32,
+        #[serde(default)]
+        seed:u64,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapDescription {
+    #[default]
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoisePatternDescription {
+    Noise,
+    Turbulence,
+    Marble,
+}
+
+// material colours are either written out or name a texture
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColourDescription {
+    Colour([f32; 3]),
+    Texture(String),
+}
+
+// the same for single values, which are read from the red channel of the texture
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueDescription {
+    Value(f32),
+    Texture(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian { diffuse_colour:ColourDescription },
+    Metal { specular_colour:[f32; 3], #[serde(default)] fuzz:f32 },
+    Dielectric { refractive_index:f32 },
+    Emissive { emission_colour:ColourDescription, #[serde(default = "defaults::one")] emission_strength:f32 },
+    Ggx {
+        diffuse_colour:ColourDescription,
+        roughness:ValueDescription,
+        #[serde(default)]
+        metallic:f32,
+        #[serde(default = "defaults::one")]
+        specular_strength:f32,
+    },
+    // phase functions for media, albedo is the part of each channel scattered at a collision
+    Isotropic { albedo:ColourDescription },
+    // asymmetry is the henyey greenstein g, positive scatters forwards
+    HenyeyGreenstein { albedo:ColourDescription, asymmetry:f32 },
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    // primitives with a transform are instances, and transformed lights glow where rays find them but aren't sampled
+    Sphere { center:[f32; 3], radius:f32, material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    Plane { point:[f32; 3], normal:[f32; 3], material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    Triangle {
+        vertices:[[f32; 3]; 3],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals:Option<[[f32; 3]; 3]>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uvs:Option<[[f32; 2]; 3]>,
+        material:String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transform:Option<TransformDescription>,
+    },
+    Quad { corner:[f32; 3], u:[f32; 3], v:[f32; 3], material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    Box { min:[f32; 3], max:[f32; 3], material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    Disk { center:[f32; 3], normal:[f32; 3], radius:f32, material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    Cylinder { base:[f32; 3], top:[f32; 3], radius:f32, material:String, #[serde(default, skip_serializing_if = "Option::is_none")] transform:Option<TransformDescription> },
+    // a wavefront obj file, material replaces the materials from its mtl files when given
+    // with a transform the mesh is an instance, and every instance of the same file and material shares one copy
+    // of its triangles, which aren't sampled as lights
+    Mesh {
+        path:String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material:Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transform:Option<TransformDescription>,
+    },
+    // fog filling a closed convex boundary, density is collisions per unit length, material a phase function
+    ConstantMedium { boundary:BoundaryDescription, density:f32, material:String },
+    // smoke from a density grid file stretched from min to max, every density is multiplied by density
+    GridMedium {
+        path:String,
+        min:[f32; 3],
+        max:[f32; 3],
+        #[serde(default = "defaults::one")]
+        density:f32,
+        material:String,
+    },
+    // another object moving through keyframes, where it is between them is blended, two keyframes move it linearly
+    Moving { object:Box<ObjectDescription>, keyframes:Vec<KeyframeDescription> },
+}
+
+// scaled, then rotated by rotate degrees about the x, y and z axes through the origin, then moved by translate
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformDescription {
+    #[serde(default)]
+    pub translate:[f32; 3],
+    #[serde(default)]
+    pub rotate:[f32; 3],
+    #[serde(default = "defaults::ones")]
+    pub scale:[f32; 3],
+}
+
+// a transform at a time, keyframes of an object are in increasing time
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyframeDescription {
+    pub time:f32,
+    #[serde(default)]
+    pub translate:[f32; 3],
+    #[serde(default)]
+    pub rotate:[f32; 3],
+    #[serde(default = "defaults::ones")]
+    pub scale:[f32; 3],
+}
+
+// the shapes a constant medium can fill, they take no material
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BoundaryDescription {
+    Sphere { center:[f32; 3], radius:f32 },
+    Box { min:[f32; 3], max:[f32; 3] },
+    Cylinder { base:[f32; 3], top:[f32; 3], radius:f32 },
+    Mesh { path:String },
+}
+
+mod defaults {
+    pub fn samples_per_pixel() -> u32 { 64 }
+    pub fn max_depth() -> u32 { 16 }
+    pub fn up() -> [f32; 3] { [0.0, 1.0, 0.0] }
+    pub fn vertical_fov() -> f32 { 40.0 }
+    pub fn one() -> f32 { 1.0 }
+    pub fn white() -> [f32; 3] { [1.0, 1.0, 1.0] }
+    pub fn ones() -> [f32; 3] { [1.0, 1.0, 1.0] }
+}
+
+
+impl SettingsDescription {
+    pub fn new() -> SettingsDescription {
+        let settings = RenderSettings::new(640, 360);
+        SettingsDescription {
+            width:settings.width,
+            height:settings.height,
+            samples_per_pixel:settings.samples_per_pixel,
+            max_depth:settings.max_depth,
+            seed:settings.seed,
+            sampler:SamplerDescription::default(),
+            background:BackgroundDescription::new(),
+        }
+    }
+
+    pub fn render_settings(&self) -> RenderSettings {
+        RenderSettings {
+            samples_per_pixel:self.samples_per_pixel,
+            max_depth:self.max_depth,
+            seed:self.seed,
+            sampler:self.sampler.sampler(),
+            background:self.background.background(),
+            ..RenderSettings::new(self.width, self.height)
+        }
+    }
+}
+
+impl Default for SettingsDescription {
+    fn default() -> SettingsDescription {
+        SettingsDescription::new()
+    }
+}
+
+impl SamplerDescription {
+    pub fn sampler(&self) -> Sampler {
+        match self {
+            SamplerDescription::Independent => Sampler::Independent,
+            SamplerDescription::Stratified => Sampler::Stratified,
+            SamplerDescription::Halton => Sampler::Halton,
+            SamplerDescription::Sobol => Sampler::Sobol,
+            SamplerDescription::BlueNoise => Sampler::BlueNoise,
+        }
+    }
+}
+
+impl BackgroundDescription {
+    pub fn new() -> BackgroundDescription {
+        BackgroundDescription::Gradient { horizon:[1.0, 1.0, 1.0], zenith:[0.5, 0.7, 1.0] }
+    }
+
+    pub fn background(&self) -> Background {
+        match self {
+            BackgroundDescription::Solid { colour } => Background::Solid(Vec3::from(*colour)),
+            BackgroundDescription::Gradient { horizon, zenith } => Background::Gradient(Vec3::from(*horizon), Vec3::from(*zenith)),
+        }
+    }
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> BackgroundDescription {
+        BackgroundDescription::new()
+    }
+}
+
+impl CameraDescription {
+    pub fn camera_settings(&self, aspect_ratio:f32) -> CameraSettings {
+        let (look_from, look_at) = (Vec3::from(self.look_from), Vec3::from(self.look_at));
+        CameraSettings {
+            look_from,
+            look_at,
+            up:Vec3::from(self.up),
+            vertical_fov:self.vertical_fov,
+            aspect_ratio,
+            aperture:self.aperture,
+            focus_distance:self.focus_distance.unwrap_or((look_at - look_from).length()),
+            shutter_open:self.shutter_open,
+            shutter_close:self.shutter_close,
+        }
+    }
+}
+
+impl TransformDescription {
+    pub fn new() -> TransformDescription {
+        TransformDescription { translate:[0.0; 3], rotate:[0.0; 3], scale:[1.0; 3] }
+    }
+
+    pub fn transform(&self) -> Result<Mat4, RayTracerError> {
+        let placement = Mat4::placement(Vec3::from(self.translate), Vec3::from(self.rotate), Point::zero())?;
+        Ok(placement * Mat4::scale(Vec3::from(self.scale)))
+    }
+
+    // a scale of 0 on any axis flattens the object and can't be undone to trace rays against it
+    pub fn is_invertible(&self) -> bool {
+        self.scale.iter().all(|factor| *factor != 0.0 && factor.is_finite())
+    }
+}
+
+impl Default for TransformDescription {
+    fn default() -> TransformDescription {
+        TransformDescription::new()
+    }
+}
+
+impl KeyframeDescription {
+    pub fn transform(&self) -> TransformDescription {
+        TransformDescription { translate:self.translate, rotate:self.rotate, scale:self.scale }
+    }
+}
+
+impl TextureDescription {
+    pub fn texture(&self, directory:&Path) -> Result<Arc<dyn Texture>, SceneError> {
+        let v = |array:&[f32; 3]| Vec3::from(*array);
+        Ok(match self {
+            TextureDescription::Solid { colour } => Arc::new(SolidColour::new(v(colour))),
+            TextureDescription::Checker { even, odd, size } => Arc::new(Checker::new(v(even), v(odd), *size)),
+            TextureDescription::UvChecker { even, odd, columns, rows } => Arc::new(UvChecker::new(v(even), v(odd), *columns, *rows)),
+            TextureDescription::Image { path, wrap } => {
+                let path = directory.join(path).to_string_lossy().to_string();
+                let image = match load_image(&path) {
+                    Ok(image) => image,
+                    Err(error) => return Err(SceneError::ImageError((path, error))),
+                };
+                let wrap = match wrap {
+                    WrapDescription::Repeat => WrapMode::Repeat,
+                    WrapDescription::Mirror => WrapMode::Mirror,
+                    WrapDescription::Clamp => WrapMode::Clamp,
+                };
+                Arc::new(ImageTexture::new(&image, wrap))
+            },
+            TextureDescription::Noise { pattern, colour, scale, seed } => {
+                let pattern = match pattern {
+                    NoisePatternDescription::Noise => NoisePattern::Noise,
+                    NoisePatternDescription::Turbulence => NoisePattern::Turbulence,
+                    NoisePatternDescription::Marble => NoisePattern::Marble,
+                };
+                Arc::new(NoiseTexture::new(pattern, v(colour), *scale, *seed))
+            },
+        })
+    }
+}
+
+impl ColourDescription {
+    pub fn texture_name(&self) -> Option<&String> {
+        match self {
+            ColourDescription::Colour(_) => None,
+            ColourDescription::Texture(name) => Some(name),
+        }
+    }
+
+    pub fn texture(&self, textures:&BTreeMap<&String, Arc<dyn Texture>>) -> Arc<dyn Texture> {
+        match self {
+            ColourDescription::Colour(colour) => Arc::new(SolidColour::new(Vec3::from(*colour))),
+            ColourDescription::Texture(name) => textures[name].clone(),
+        }
+    }
+}
+
+impl ValueDescription {
+    pub fn texture_name(&self) -> Option<&String> {
+        match self {
+            ValueDescription::Value(_) => None,
+            ValueDescription::Texture(name) => Some(name),
+        }
+    }
+
+    pub fn texture(&self, textures:&BTreeMap<&String, Arc<dyn Texture>>) -> Arc<dyn Texture> {
+        match self {
+            ValueDescription::Value(value) => Arc::new(SolidColour::new(Vec3::splat(*value))),
+            ValueDescription::Texture(name) => textures[name].clone(),
+        }
+    }
+}
+
+impl MaterialDescription {
+    // the keys that name a texture, with the name
+    pub fn texture_names(&self) -> Vec<(&'static str, &String)> {
+        let names = match self {
+            MaterialDescription::Lambertian { diffuse_colour } => vec![("diffuse_colour", diffuse_colour.texture_name())],
+            MaterialDescription::Emissive { emission_colour, .. } => vec![("emission_colour", emission_colour.texture_name())],
+            MaterialDescription::Ggx { diffuse_colour, roughness, .. } =>
+                vec![("diffuse_colour", diffuse_colour.texture_name()), ("roughness", roughness.texture_name())],
+            MaterialDescription::Isotropic { albedo } | MaterialDescription::HenyeyGreenstein { albedo, .. } =>
+                vec![("albedo", albedo.texture_name())],
+            MaterialDescription::Metal { .. } | MaterialDescription::Dielectric { .. } => vec![],
+        };
+        names.into_iter().filter_map(|(key, name)| name.map(|name| (key, name))).collect()
+    }
+
+    pub fn material(&self, textures:&BTreeMap<&String, Arc<dyn Texture>>) -> Arc<dyn Material> {
+        match self {
+            MaterialDescription::Lambertian { diffuse_colour } => Arc::new(Lambertian::textured(diffuse_colour.texture(textures))),
+            MaterialDescription::Metal { specular_colour, fuzz } => Arc::new(Metal::new(Vec3::from(*specular_colour), *fuzz)),
+            MaterialDescription::Dielectric { refractive_index } => Arc::new(Dielectric::new(*refractive_index)),
+            MaterialDescription::Emissive { emission_colour, emission_strength } =>
+                Arc::new(Emissive::textured(emission_colour.texture(textures), *emission_strength)),
+            MaterialDescription::Ggx { diffuse_colour, roughness, metallic, specular_strength } => Arc::new(Ggx {
+                specular_strength:*specular_strength,
+                ..Ggx::textured(diffuse_colour.texture(textures), roughness.texture(textures), *metallic)
+            }),
+            MaterialDescription::Isotropic { albedo } => Arc::new(Isotropic::textured(albedo.texture(textures))),
+            MaterialDescription::HenyeyGreenstein { albedo, asymmetry } =>
+                Arc::new(HenyeyGreenstein::textured(albedo.texture(textures), *asymmetry)),
+        }
+    }
+}
+
+impl ObjectDescription {
+    pub fn material_name(&self) -> Option<&String> {
+        match self {
+            ObjectDescription::Sphere { material, .. } | ObjectDescription::Plane { material, .. }
+            | ObjectDescription::Triangle { material, .. } | ObjectDescription::Quad { material, .. }
+            | ObjectDescription::Box { material, .. } | ObjectDescription::Disk { material, .. }
+            | ObjectDescription::Cylinder { material, .. } | ObjectDescription::ConstantMedium { material, .. }
+            | ObjectDescription::GridMedium { material, .. } => Some(material),
+            ObjectDescription::Mesh { material, .. } => material.as_ref(),
+            ObjectDescription::Moving { object, .. } => object.material_name(),
+        }
+    }
+
+    pub fn transform(&self) -> Option<&TransformDescription> {
+        match self {
+            ObjectDescription::Sphere { transform, .. } | ObjectDescription::Plane { transform, .. }
+            | ObjectDescription::Triangle { transform, .. } | ObjectDescription::Quad { transform, .. }
+            | ObjectDescription::Box { transform, .. } | ObjectDescription::Disk { transform, .. }
+            | ObjectDescription::Cylinder { transform, .. } | ObjectDescription::Mesh { transform, .. } => transform.as_ref(),
+            ObjectDescription::ConstantMedium { .. } | ObjectDescription::GridMedium { .. } | ObjectDescription::Moving { .. } => None,
+        }
+    }
+
+    // a transform or keyframes that rays can't be traced through
+    pub fn invalid_motion(&self) -> Option<MotionProblem> {
+        match self {
+            _ if self.transform().is_some_and(|transform| !transform.is_invertible()) =>
+                Some(("transform", SceneError::InvalidTransform)),
+            ObjectDescription::Moving { object, keyframes } => {
+                if keyframes.is_empty() || keyframes.windows(2).any(|pair| pair[0].time >= pair[1].time) {
+                    Some(("keyframes", SceneError::InvalidKeyframes))
+                } else if keyframes.iter().any(|keyframe| !keyframe.transform().is_invertible()) {
+                    Some(("keyframes", SceneError::InvalidTransform))
+                } else {
+                    object.invalid_motion()
+                }
+            },
+            _ => None,
+        }
+    }
+
+    // only meshes can leave out their material, primitives are given it by the scene
+    // instanced meshes are looked for in instanced before they are loaded, and kept there once they are
+    pub fn objects(&self, material:Option<Arc<dyn Material>>, directory:&Path, instanced:&mut InstancedMeshes)
+                   -> Result<SceneObjects, SceneError> {
+        let objects = self.untransformed_objects(material, directory, instanced)?;
+        match self.transform() {
+            // meshes are instanced as they are loaded
+            Some(transform) if !matches!(self, ObjectDescription::Mesh { .. }) => {
+                let (hittable, _) = objects;
+                match transform.transform().and_then(|transform| Instance::new(single_hittable(hittable), transform)) {
+                    Ok(instance) => Ok((vec![Arc::new(instance)], vec![])),
+                    Err(_) => Err(SceneError::InvalidTransform((0, 0))),
+                }
+            },
+            _ => Ok(objects),
+        }
+    }
+
+    fn untransformed_objects(&self, material:Option<Arc<dyn Material>>, directory:&Path, instanced:&mut InstancedMeshes)
+                             -> Result<SceneObjects, SceneError> {
+        let v = |array:&[f32; 3]| Vec3::from(*array);
+        let surface = |surface:Arc<dyn Surface>| Ok((vec![surface.clone() as Arc<dyn Hittable>], vec![surface]));
+        let object = |object:Arc<dyn Hittable>| Ok((vec![object], vec![]));
+        let primitive_material = material.clone().unwrap_or_else(|| mesh_material(&MeshMaterial::new("")));
+
+        match self {
+            ObjectDescription::Sphere { center, radius, .. } => surface(Arc::new(Sphere::new(v(center), *radius, primitive_material))),
+            ObjectDescription::Plane { point, normal, .. } => object(Arc::new(Plane::new(v(point), v(normal), primitive_material))),
+            ObjectDescription::Triangle { vertices, normals, uvs, .. } => {
+                let mut triangle = Triangle::new(v(&vertices[0]), v(&vertices[1]), v(&vertices[2]), primitive_material);
+                if let Some(normals) = normals {
+                    triangle = triangle.with_normals(normals.map(Vec3::from));
+                }
+                if let Some(uvs) = uvs {
+                    triangle = triangle.with_uvs(*uvs);
+                }
+                surface(Arc::new(triangle))
+            },
+            ObjectDescription::Quad { corner, u, v:edge, .. } => surface(Arc::new(Quad::new(v(corner), v(u), v(edge), primitive_material))),
+            ObjectDescription::Box { min, max, .. } => object(Arc::new(AaBox::new(v(min), v(max), primitive_material))),
+            ObjectDescription::Disk { center, normal, radius, .. } => object(Arc::new(Disk::new(v(center), v(normal), *radius, primitive_material))),
+            ObjectDescription::Cylinder { base, top, radius, .. } => object(Arc::new(Cylinder::new(v(base), v(top), *radius, primitive_material))),
+            ObjectDescription::Mesh { path, material:material_name, transform:Some(transform) } => {
+                let path = directory.join(path).to_string_lossy().to_string();
+                let key = (path.clone(), material_name.clone());
+                let mesh = match instanced.get(&key) {
+                    Some(mesh) => mesh.clone(),
+                    None => {
+                        let triangles = match load_obj(&path) {
+                            Ok(mesh) => mesh_triangles(&mesh, material),
+                            Err(error) => return Err(SceneError::MeshError((path, error))),
+                        };
+                        let mesh : Arc<dyn Hittable> = Arc::new(Bvh::new(triangles.into_iter().map(|triangle| triangle as Arc<dyn Hittable>).collect()));
+                        instanced.insert(key, mesh.clone());
+                        mesh
+                    },
+                };
+                match transform.transform().and_then(|transform| Instance::new(mesh, transform)) {
+                    Ok(instance) => object(Arc::new(instance)),
+                    Err(_) => Err(SceneError::InvalidTransform((0, 0))),
+                }
+            },
+            ObjectDescription::Mesh { path, .. } => {
+                let path = directory.join(path).to_string_lossy().to_string();
+                let triangles = match load_obj(&path) {
+                    Ok(mesh) => mesh_triangles(&mesh, material),
+                    Err(error) => return Err(SceneError::MeshError((path, error))),
+                };
+                let hittables = triangles.iter().map(|triangle| triangle.clone() as Arc<dyn Hittable>).collect();
+                Ok((hittables, triangles.into_iter().map(|triangle| triangle as Arc<dyn Surface>).collect()))
+            },
+            ObjectDescription::ConstantMedium { boundary, density, .. } => {
+                let boundary = boundary.hittable(primitive_material.clone(), directory)?;
+                object(Arc::new(ConstantMedium::new(boundary, *density, primitive_material)))
+            },
+            ObjectDescription::GridMedium { path, min, max, density, .. } => {
+                let path = directory.join(path).to_string_lossy().to_string();
+                let grid = match load_density_grid(&path) {
+                    Ok(grid) => grid,
+                    Err(error) => return Err(SceneError::GridError((path, error))),
+                };
+                object(Arc::new(GridMedium::new(grid, v(min), v(max), *density, primitive_material)))
+            },
+            // moving lights glow where rays find them, but aren't sampled
+            ObjectDescription::Moving { object:moving, keyframes } => {
+                let (hittables, _) = moving.objects(material, directory, instanced)?;
+                let hittable = single_hittable(hittables);
+                let keyframes = keyframes.iter()
+                    .map(|keyframe| Ok((keyframe.time, keyframe.transform().transform()?)))
+                    .collect::<Result<Vec<_>, RayTracerError>>();
+                match keyframes.and_then(|keyframes| MovingInstance::new(hittable, keyframes)) {
+                    Ok(moving) => object(Arc::new(moving)),
+                    Err(_) => Err(SceneError::InvalidKeyframes((0, 0))),
+                }
+            },
+        }
+    }
+}
+
+// the hittables an object is made of as one, a bvh of them when there are several
+fn single_hittable(mut hittables:Vec<Arc<dyn Hittable>>) -> Arc<dyn Hittable> {
+    match hittables.len() {
+        1 => hittables.remove(0),
+        _ => Arc::new(Bvh::new(hittables)),
+    }
+}
+
+impl BoundaryDescription {
+    // material is only there because every shape has one, the medium never shades its boundary
+    pub fn hittable(&self, material:Arc<dyn Material>, directory:&Path) -> Result<Arc<dyn Hittable>, SceneError> {
+        let v = |array:&[f32; 3]| Vec3::from(*array);
+        Ok(match self {
+            BoundaryDescription::Sphere { center, radius } => Arc::new(Sphere::new(v(center), *radius, material)),
+            BoundaryDescription::Box { min, max } => Arc::new(AaBox::new(v(min), v(max), material)),
+            BoundaryDescription::Cylinder { base, top, radius } => Arc::new(Cylinder::new(v(base), v(top), *radius, material)),
+            BoundaryDescription::Mesh { path } => {
+                let path = directory.join(path).to_string_lossy().to_string();
+                match load_obj(&path) {
+                    Ok(mesh) => Arc::new(Bvh::new(mesh_triangles(&mesh, Some(material)).into_iter()
+                        .map(|triangle| triangle as Arc<dyn Hittable>)
+                        .collect())),
+                    Err(error) => return Err(SceneError::MeshError((path, error))),
+                }
+            },
+        })
+    }
+}
+
+
+// 1 based line and column of a byte offset
+pub fn line_column(source:&str, offset:usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+// tagged enums lose the spans of their fields, so positions inside objects and materials are found by reading the source again
+#[derive(Deserialize)]
+struct TableSpans {
+    #[serde(default)]
+    objects:Vec<Spanned<toml::Table>>,
+    #[serde(default)]
+    materials:BTreeMap<String, Spanned<toml::Table>>,
+}
+
+fn object_span(source:&str, index:usize) -> Option<std::ops::Range<usize>> {
+    toml::from_str::<TableSpans>(source).ok().and_then(|spans| spans.objects.get(index).map(|object| object.span()))
+}
+
+fn material_span(source:&str, name:&str) -> Option<std::ops::Range<usize>> {
+    toml::from_str::<TableSpans>(source).ok().and_then(|spans| spans.materials.get(name).map(|material| material.span()))
+}
+
+// where key is set in the table with the span, or where the table starts if it can't be found
+fn key_position(source:&str, span:Option<std::ops::Range<usize>>, key:&str) -> (usize, usize) {
+    let span = match span {
+        Some(span) => span,
+        None => return (0, 0),
+    };
+    let object = &source[span.clone()];
+    let offset = object.lines()
+        .scan(0, |start, line| {
+            let line_start = *start;
+            *start += line.len() + 1;
+            Some((line_start, line))
+        })
+        .find(|(_, line)| line.trim_start().strip_prefix(key).is_some_and(|rest| rest.trim_start().starts_with('=')))
+        .map_or(0, |(line_start, line)| line_start + line.len() - line.trim_start().len());
+    line_column(source, span.start + offset)
+}
+
+impl SceneDescription {
+    pub fn from_toml(source:&str) -> Result<SceneDescription, SceneError> {
+        let description : SceneDescription = match toml::from_str(source) {
+            Ok(description) => description,
+            Err(error) => {
+                let (line, column) = error.span().map_or((0, 0), |span| line_column(source, span.start));
+                return Err(SceneError::ParseError((error.message().to_string(), line, column)));
+            },
+        };
+        description.validate(Some(source))?;
+        Ok(description)
+    }
+
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    // every name a material or object refers to must be defined, positions are given when the source is known
+    pub fn validate(&self, source:Option<&str>) -> Result<(), SceneError> {
+        if self.settings.width == 0 || self.settings.height == 0 {
+            return Err(SceneError::InvalidDimensions((self.settings.width, self.settings.height)));
+        }
+        for (material_name, material) in &self.materials {
+            for (key, name) in material.texture_names() {
+                if !self.textures.contains_key(name) {
+                    let (line, column) = source.map_or((0, 0), |source| key_position(source, material_span(source, material_name), key));
+                    return Err(SceneError::UnknownTexture((name.clone(), line, column)));
+                }
+            }
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            if let Some((key, error)) = object.invalid_motion() {
+                let (line, column) = source.map_or((0, 0), |source| key_position(source, object_span(source, index), key));
+                return Err(error((line, column)));
+            }
+            let Some(name) = object.material_name() else {
+                continue;
+            };
+            if !self.materials.contains_key(name) {
+                let (line, column) = source.map_or((0, 0), |source| key_position(source, object_span(source, index), "material"));
+                return Err(SceneError::UnknownMaterial((name.clone(), line, column)));
+            }
+        }
+        Ok(())
+    }
+
+    // meshes and images are loaded from the working directory, build_in loads them relative to another
+    pub fn build(&self) -> Result<Scene, SceneError> {
+        self.build_in(Path::new(""))
+    }
+
+    pub fn build_in(&self, directory:&Path) -> Result<Scene, SceneError> {
+        self.validate(None)?;
+        let textures = self.textures.iter()
+            .map(|(name, texture)| Ok((name, texture.texture(directory)?)))
+            .collect::<Result<BTreeMap<&String, Arc<dyn Texture>>, SceneError>>()?;
+        let materials : BTreeMap<&String, Arc<dyn Material>> = self.materials.iter()
+            .map(|(name, material)| (name, material.material(&textures)))
+            .collect();
+
+        let mut world = HittableList::new();
+        let mut surfaces = vec![];
+        let mut instanced = InstancedMeshes::new();
+        for object in &self.objects {
+            let material = object.material_name().map(|name| materials[name].clone());
+            let (hittables, object_surfaces) = object.objects(material, directory, &mut instanced)?;
+            for hittable in hittables {
+                world.add(hittable);
+            }
+            surfaces.extend(object_surfaces);
+        }
+
+        let settings = self.settings.render_settings();
+        let aspect_ratio = settings.width as f32 / settings.height as f32;
+        Ok(Scene {
+            world:Bvh::from(world),
+            lights:LightList::new(surfaces),
+            camera:Camera::new(&self.camera.camera_settings(aspect_ratio)),
+            settings,
+        })
+    }
+}
+
+
+// a scene ready to render, every emissive sphere, quad and triangle is a light
+pub struct Scene {
+    pub world:Bvh,
+    pub lights:LightList,
+    pub camera:Camera,
+    pub settings:RenderSettings,
+}
+
+pub fn load_scene_description(path:&str) -> Result<SceneDescription, RayTracerError> {
+    let source = fs::read_to_string(path)?;
+    Ok(SceneDescription::from_toml(&source)?)
+}
+
+pub fn save_scene_description(description:&SceneDescription, path:&str) -> Result<(), RayTracerError> {
+    fs::write(path, description.to_toml()?)?;
+    Ok(())
+}
+
+pub fn load_scene(path:&str) -> Result<Scene, RayTracerError> {
+    let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+    Ok(load_scene_description(path)?.build_in(directory)?)
+}
+
+// a file shipped with the crate, like scenes/cornell.toml, found from where the crate was built
+// so examples and tests load it whatever directory they are run from
+pub fn bundled_path(relative:&str) -> String {
+    format!("{}/{}", env!("CARGO_MANIFEST_DIR"), relative)
+}
@@ -0,0 +1,348 @@
+use images::image::{Image, ImageData};
+
+use crate::rng::Pcg32;
+use crate::vec3::{Colour, Point, Vec3};
+
+
+// a colour that varies over a surface, looked up with the uv and point of a hit
+// scalar textures such as roughness read the x (red) channel
+pub trait Texture: Send + Sync {
+    fn value(&self, u:f32, v:f32, point:Point) -> Colour;
+
+    // the mean colour, for where one colour has to stand in for the texture, like the rasteriser preview
+    fn average(&self) -> Colour;
+}
+
+
+
+pub struct SolidColour {
+    pub colour:Colour,
+}
+
+impl SolidColour {
+    pub fn new(colour:Colour) -> SolidColour {
+        SolidColour { colour }
+    }
+}
+
+impl Texture for SolidColour {
+    fn value(&self, _u:f32, _v:f32, _point:Point) -> Colour {
+        self.colour
+    }
+
+    fn average(&self) -> Colour {
+        self.colour
+    }
+}
+
+
+
+// cubes of side size in world space, alternating between even and odd
+pub struct Checker {
+    pub even:Colour,
+    pub odd:Colour,
+    pub size:f32,
+}
+
+impl Checker {
+    pub fn new(even:Colour, odd:Colour, size:f32) -> Checker {
+        Checker { even, odd, size }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, _u:f32, _v:f32, point:Point) -> Colour {
+        let cell = (point / self.size).map(f32::floor);
+        match (cell.x + cell.y + cell.z).rem_euclid(2.0) < 1.0 {
+            true => self.even,
+            false => self.odd,
+        }
+    }
+
+    fn average(&self) -> Colour {
+        (self.even + self.odd) * 0.5
+    }
+}
+
+
+
+// columns by rows squares over the 0 to 1 uv square, so the pattern follows the surface
+pub struct UvChecker {
+    pub even:Colour,
+    pub odd:Colour,
+    pub columns:u32,
+    pub rows:u32,
+}
+
+impl UvChecker {
+    pub fn new(even:Colour, odd:Colour, columns:u32, rows:u32) -> UvChecker {
+        UvChecker { even, odd, columns, rows }
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u:f32, v:f32, _point:Point) -> Colour {
+        let cell = (u * self.columns as f32).floor() + (v * self.rows as f32).floor();
+        match cell.rem_euclid(2.0) < 1.0 {
+            true => self.even,
+            false => self.odd,
+        }
+    }
+
+    fn average(&self) -> Colour {
+        (self.even + self.odd) * 0.5
+    }
+}
+
+
+
+// what happens to uv outside 0 to 1
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WrapMode {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+impl WrapMode {
+    // a texel coordinate wrapped into 0..size
+    fn wrap(&self, texel:i64, size:usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self {
+            WrapMode::Repeat => texel.rem_euclid(size),
+            WrapMode::Mirror => {
+                let period = texel.rem_euclid(2 * size);
+                if period < size {period} else {2 * size - 1 - period}
+            },
+            WrapMode::Clamp => texel.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+
+// bilinear filtered lookups into an image, v runs from the bottom row up like the rest of the ray tracer
+// 8 and 16 bit images are taken to be gamma 2.2 encoded, the inverse of the tone mapper, float images are linear
+pub struct ImageTexture {
+    pub width:usize,
+    pub height:usize,
+    pub pixels:Vec<Colour>,
+    pub wrap:WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image:&Image, wrap:WrapMode) -> ImageTexture {
+        let gamma = match image.data {
+            ImageData::F32(_) => 1.0,
+            _ => 2.2,
+        };
+        let mut pixels = Vec::with_capacity(image.width * image.height);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                pixels.push(Colour::from(image.rgb(x, y)).map(|channel| channel.powf(gamma)));
+            }
+        }
+        ImageTexture { width:image.width, height:image.height, pixels, wrap }
+    }
+
+    pub fn texel(&self, x:i64, y:i64) -> Colour {
+        self.pixels[self.wrap.wrap(y, self.height) * self.width + self.wrap.wrap(x, self.width)]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u:f32, v:f32, _point:Point) -> Colour {
+        if self.pixels.is_empty() {
+            return Colour::new(1.0, 0.0, 1.0);
+        }
+        // texel centres sit at half integers
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(bottom, fy)
+    }
+
+    fn average(&self) -> Colour {
+        match self.pixels.is_empty() {
+            true => Colour::zero(),
+            false => self.pixels.iter().fold(Colour::zero(), |sum, pixel| sum + *pixel) / self.pixels.len() as f32,
+        }
+    }
+}
+
+
+
+// gradient noise with random unit vectors on the integer lattice, smooth and in about -1 to 1
+pub struct Perlin {
+    gradients:Vec<Vec3>,
+    permutations:[Vec<usize>; 3],
+}
+
+impl Perlin {
+    const POINTS : usize = 256;
+
+    pub fn new(seed:u64) -> Perlin {
+        let mut rng = Pcg32::new(seed, 0);
+        let gradients = (0..Perlin::POINTS).map(|_| rng.unit_vector()).collect();
+        let mut permutation = || {
+            let mut permutation : Vec<usize> = (0..Perlin::POINTS).collect();
+            for i in (1..Perlin::POINTS).rev() {
+                permutation.swap(i, rng.next_u32() as usize % (i + 1));
+            }
+            permutation
+        };
+        let permutations = [permutation(), permutation(), permutation()];
+        Perlin { gradients, permutations }
+    }
+
+    pub fn noise(&self, point:Point) -> f32 {
+        let cell = point.map(f32::floor);
+        let offset = point - cell;
+        // hermite smoothing hides the lattice
+        let smooth = offset.map(|t| t * t * (3.0 - 2.0 * t));
+        let mask = Perlin::POINTS as i64 - 1;
+        let (i, j, k) = (cell.x as i64, cell.y as i64, cell.z as i64);
+
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.permutations[0][((i + di) & mask) as usize]
+                        ^ self.permutations[1][((j + dj) & mask) as usize]
+                        ^ self.permutations[2][((k + dk) & mask) as usize];
+                    let (fi, fj, fk) = (di as f32, dj as f32, dk as f32);
+                    let weight = (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                        * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                        * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z));
+                    sum += weight * self.gradients[index].dot(offset - Vec3::new(fi, fj, fk));
+                }
+            }
+        }
+        sum
+    }
+
+    // the sum of octaves of halving amplitude and doubling frequency, in about 0 to 1
+    pub fn turbulence(&self, point:Point, octaves:u32) -> f32 {
+        let (mut sum, mut point, mut weight) = (0.0, point, 1.0);
+        for _ in 0..octaves {
+            sum += weight * self.noise(point).abs();
+            point *= 2.0;
+            weight *= 0.5;
+        }
+        sum
+    }
+}
+
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NoisePattern {
+    // noise mapped to 0 to 1
+    Noise,
+    Turbulence,
+    // sine bands along z, scale apart, bent by turbulence
+    Marble,
+}
+
+// noise scales colour, scale is the frequency of the noise in world space
+pub struct NoiseTexture {
+    pub perlin:Perlin,
+    pub pattern:NoisePattern,
+    pub colour:Colour,
+    pub scale:f32,
+}
+
+impl NoiseTexture {
+    const OCTAVES : u32 = 7;
+
+    pub fn new(pattern:NoisePattern, colour:Colour, scale:f32, seed:u64) -> NoiseTexture {
+        NoiseTexture { perlin:Perlin::new(seed), pattern, colour, scale }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u:f32, _v:f32, point:Point) -> Colour {
+        let scaled = point * self.scale;
+        let value = match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.perlin.noise(scaled)),
+            NoisePattern::Turbulence => self.perlin.turbulence(scaled, NoiseTexture::OCTAVES),
+            // the turbulence is left at world scale so the veins stay the same size as the bands narrow
+            NoisePattern::Marble => 0.5 * (1.0 + (scaled.z + 10.0 * self.perlin.turbulence(point, NoiseTexture::OCTAVES)).sin()),
+        };
+        self.colour * value.clamp(0.0, 1.0)
+    }
+
+    fn average(&self) -> Colour {
+        self.colour * 0.5
+    }
+}
+
+
+// checkers alternate, image lookups are filtered, wrapped and decoded, and noise stays in range
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use images::image::ChannelLayout;
+
+    fn close(a:Colour, b:Colour) -> bool {
+        (a - b).length() < 1e-4
+    }
+
+
+    #[test]
+    fn checkers_alternate_in_space_and_uv() {
+        let (black, white) = (Colour::zero(), Colour::splat(1.0));
+        let checker = Checker::new(black, white, 0.5);
+        assert!(close(checker.value(0.0, 0.0, Point::new(0.1, 0.1, 0.1)), black));
+        assert!(close(checker.value(0.0, 0.0, Point::new(0.6, 0.1, 0.1)), white));
+        assert!(close(checker.value(0.0, 0.0, Point::new(-0.1, 0.1, 0.1)), white));
+        let uv_checker = UvChecker::new(black, white, 4, 2);
+        assert!(close(uv_checker.value(0.1, 0.1, Point::zero()), black));
+        assert!(close(uv_checker.value(0.3, 0.1, Point::zero()), white));
+        assert!(close(uv_checker.value(0.3, 0.6, Point::zero()), black));
+    }
+
+    #[test]
+    fn image_textures_filter_wrap_and_decode() {
+        let (black, white, origin) = (Colour::zero(), Colour::splat(1.0), Point::zero());
+        // a 2 by 1 float image, black on the left and white on the right
+        let mut image = Image::new_f32(2, 1, ChannelLayout::Rgb);
+        image.set_rgb(1, 0, [1.0, 1.0, 1.0]);
+        let repeat = ImageTexture::new(&image, WrapMode::Repeat);
+        assert!(close(repeat.value(0.25, 0.5, origin), black));
+        assert!(close(repeat.value(0.75, 0.5, origin), white));
+        assert!(close(repeat.value(0.5, 0.5, origin), Colour::splat(0.5)), "bilinear between texel centres");
+        // at the left edge repeat blends with the right column, clamp does not
+        assert!(close(repeat.value(0.0, 0.5, origin), Colour::splat(0.5)));
+        assert!(close(ImageTexture::new(&image, WrapMode::Clamp).value(0.0, 0.5, origin), black));
+        assert!(close(ImageTexture::new(&image, WrapMode::Mirror).value(1.1, 0.5, origin), white));
+        assert!(close(repeat.value(1.25, 0.5, origin), black));
+        assert!(close(repeat.average(), Colour::splat(0.5)));
+
+        // 8 bit images are decoded from gamma 2.2
+        let mut grey = Image::new_u8(1, 1, ChannelLayout::Grey);
+        grey.set(0, 0, 0, 0.5);
+        let decoded = ImageTexture::new(&grey, WrapMode::Repeat).value(0.5, 0.5, origin).x;
+        assert!((decoded - (128.0f32 / 255.0).powf(2.2)).abs() < 1e-4, "decoded {}", decoded);
+    }
+
+    #[test]
+    fn noise_stays_in_range_and_is_zero_on_the_lattice() {
+        let perlin = Perlin::new(1);
+        let mut rng = Pcg32::new(2, 0);
+        for _ in 0..10_000 {
+            let point = Point::new(rng.range(-50.0, 50.0), rng.range(-50.0, 50.0), rng.range(-50.0, 50.0));
+            let noise = perlin.noise(point);
+            assert!((-1.0..=1.0).contains(&noise), "noise {} at {:?}", noise, point);
+            assert!(perlin.noise(point.map(f32::floor)).abs() < 1e-5, "noise is zero on the lattice");
+            for pattern in [NoisePattern::Noise, NoisePattern::Turbulence, NoisePattern::Marble] {
+                let value = NoiseTexture::new(pattern, Colour::splat(1.0), 1.0, 1).value(0.0, 0.0, point);
+                assert!((0.0..=1.0).contains(&value.x));
+            }
+        }
+        assert_eq!(Perlin::new(3).noise(Point::new(0.3, 0.7, 0.2)), Perlin::new(3).noise(Point::new(0.3, 0.7, 0.2)));
+    }
+}
@@ -0,0 +1,344 @@
+use matrices::matrix::Matrix;
+
+use crate::errors::RayTracerError;
+use crate::vec3::{Point, Vec3};
+
+use std::ops::Mul;
+
+
+// 4x4 affine transform acting on column vectors, rows[r][c]
+// the same layout as render_context's matrices, which are uploaded to opengl with transpose set
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Mat4 {
+    pub rows:[[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const fn identity() -> Mat4 {
+        Mat4 { rows:[
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]}
+    }
+
+    pub fn translate(offset:Vec3) -> Mat4 {
+        let mut m = Mat4::identity();
+        m.rows[0][3] = offset.x;
+        m.rows[1][3] = offset.y;
+        m.rows[2][3] = offset.z;
+        m
+    }
+
+    pub fn scale(factors:Vec3) -> Mat4 {
+        let mut m = Mat4::identity();
+        m.rows[0][0] = factors.x;
+        m.rows[1][1] = factors.y;
+        m.rows[2][2] = factors.z;
+        m
+    }
+
+    // right handed rotation about a unit axis, in degrees
+    pub fn rotate(axis:Vec3, degrees:f32) -> Mat4 {
+        let axis = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = 1.0 - cos;
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+        Mat4 { rows:[
+            [cos + x*x*k,   x*y*k - z*sin, x*z*k + y*sin, 0.0],
+            [y*x*k + z*sin, cos + y*y*k,   y*z*k - x*sin, 0.0],
+            [z*x*k - y*sin, z*y*k + x*sin, cos + z*z*k,   0.0],
+            [0.0,           0.0,           0.0,           1.0],
+        ]}
+    }
+
+    // rotation by angle_xyz degrees about the axes through pivot, then a translation by offset,
+    // made with the same matrices helpers render_context's camera transform is
+    pub fn placement(offset:Vec3, angle_xyz:Vec3, pivot:Point) -> Result<Mat4, RayTracerError> {
+        let rotation = Mat4::from_matrix(&Matrix::rotate_around_p((pivot.x, pivot.y, pivot.z), (angle_xyz.x, angle_xyz.y, angle_xyz.z))?)?;
+        let translation = Mat4::from_matrix(&Matrix::translate((offset.x, offset.y, offset.z)))?;
+        Ok(translation * rotation)
+    }
+
+    // a 4x4 Matrix as render_context builds it for a uniform
+    pub fn from_matrix(matrix:&Matrix<f32>) -> Result<Mat4, RayTracerError> {
+        if matrix.array.len() != 16 {
+            return Err(RayTracerError::MatrixShapeError(matrix.shape.clone()));
+        }
+        let mut rows = [[0.0; 4]; 4];
+        for (i, value) in matrix.array.iter().enumerate() {
+            rows[i / 4][i % 4] = *value;
+        }
+        Ok(Mat4 { rows })
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = self.rows[c][r];
+            }
+        }
+        Mat4 { rows }
+    }
+
+    pub fn transform_point(&self, point:Point) -> Point {
+        let m = &self.rows;
+        let p = [point.x, point.y, point.z, 1.0];
+        let row = |r:usize| m[r][0]*p[0] + m[r][1]*p[1] + m[r][2]*p[2] + m[r][3]*p[3];
+        let w = row(3);
+        let point = Vec3::new(row(0), row(1), row(2));
+        if w != 0.0 && w != 1.0 {point / w} else {point}
+    }
+
+    pub fn transform_vector(&self, vector:Vec3) -> Vec3 {
+        let m = &self.rows;
+        let row = |r:usize| m[r][0]*vector.x + m[r][1]*vector.y + m[r][2]*vector.z;
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    // normals go through the inverse transpose, pass the inverse of the transform the surface went through
+    pub fn transform_normal(inverse:&Mat4, normal:Vec3) -> Vec3 {
+        inverse.transpose().transform_vector(normal)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.cofactors().1
+    }
+
+    // None for singular matrices, such as a scale of 0 on some axis
+    pub fn inverse(&self) -> Option<Mat4> {
+        let (cofactors, determinant) = self.cofactors();
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let mut rows = [[0.0; 4]; 4];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = cofactors[c][r] / determinant;
+            }
+        }
+        Some(Mat4 { rows })
+    }
+
+    // the matrix of cofactors and the determinant, by expansion along 3x3 minors
+    fn cofactors(&self) -> ([[f32; 4]; 4], f32) {
+        let m = &self.rows;
+        let mut cofactors = [[0.0; 4]; 4];
+        for (r, row) in cofactors.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                let minor_rows : Vec<usize> = (0..4).filter(|i| *i != r).collect();
+                let minor_columns : Vec<usize> = (0..4).filter(|i| *i != c).collect();
+                let at = |i:usize, j:usize| m[minor_rows[i]][minor_columns[j]];
+                let minor = at(0, 0) * (at(1, 1)*at(2, 2) - at(1, 2)*at(2, 1))
+                          - at(0, 1) * (at(1, 0)*at(2, 2) - at(1, 2)*at(2, 0))
+                          + at(0, 2) * (at(1, 0)*at(2, 1) - at(1, 1)*at(2, 0));
+                *value = if (r + c) % 2 == 0 {minor} else {-minor};
+            }
+        }
+        let determinant = (0..4).map(|c| m[0][c] * cofactors[0][c]).sum();
+        (cofactors, determinant)
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, other:Mat4) -> Mat4 {
+        let mut rows = [[0.0; 4]; 4];
+        for (r, row) in rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[r][k] * other.rows[k][c]).sum();
+            }
+        }
+        Mat4 { rows }
+    }
+}
+
+
+
+// a unit quaternion for a rotation, w is the real part
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quaternion {
+    pub w:f32,
+    pub x:f32,
+    pub y:f32,
+    pub z:f32,
+}
+
+impl Quaternion {
+    pub const fn identity() -> Quaternion {
+        Quaternion { w:1.0, x:0.0, y:0.0, z:0.0 }
+    }
+
+    // from the upper 3x3 of a matrix that is a pure rotation (Shepperd's method, picking the largest diagonal)
+    pub fn from_rotation(m:&Mat4) -> Quaternion {
+        let r = &m.rows;
+        let trace = r[0][0] + r[1][1] + r[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion { w:0.25 * s, x:(r[2][1] - r[1][2]) / s, y:(r[0][2] - r[2][0]) / s, z:(r[1][0] - r[0][1]) / s }
+        } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+            let s = 2.0 * (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt();
+            Quaternion { w:(r[2][1] - r[1][2]) / s, x:0.25 * s, y:(r[0][1] + r[1][0]) / s, z:(r[0][2] + r[2][0]) / s }
+        } else if r[1][1] > r[2][2] {
+            let s = 2.0 * (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt();
+            Quaternion { w:(r[0][2] - r[2][0]) / s, x:(r[0][1] + r[1][0]) / s, y:0.25 * s, z:(r[1][2] + r[2][1]) / s }
+        } else {
+            let s = 2.0 * (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt();
+            Quaternion { w:(r[1][0] - r[0][1]) / s, x:(r[0][2] + r[2][0]) / s, y:(r[1][2] + r[2][1]) / s, z:0.25 * s }
+        };
+        q.normalised()
+    }
+
+    pub fn dot(&self, other:&Quaternion) -> f32 {
+        self.w*other.w + self.x*other.x + self.y*other.y + self.z*other.z
+    }
+
+    pub fn normalised(&self) -> Quaternion {
+        let length = self.dot(self).sqrt();
+        Quaternion { w:self.w / length, x:self.x / length, y:self.y / length, z:self.z / length }
+    }
+
+    // the angle turned through going from self to other the short way, in radians
+    pub fn angle_to(&self, other:&Quaternion) -> f32 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    // constant speed along the shorter arc, t from 0 at self to 1 at other
+    pub fn slerp(&self, other:&Quaternion, t:f32) -> Quaternion {
+        let mut cosine = self.dot(other);
+        let other = match cosine < 0.0 {
+            true => {
+                cosine = -cosine;
+                Quaternion { w:-other.w, x:-other.x, y:-other.y, z:-other.z }
+            },
+            false => *other,
+        };
+        // nearly parallel rotations are close enough to a straight line, and sin would be 0
+        let (a, b) = match cosine > 0.9995 {
+            true => (1.0 - t, t),
+            false => {
+                let angle = cosine.acos();
+                (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+            },
+        };
+        Quaternion {
+            w:a * self.w + b * other.w,
+            x:a * self.x + b * other.x,
+            y:a * self.y + b * other.y,
+            z:a * self.z + b * other.z,
+        }.normalised()
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        let Quaternion { w, x, y, z } = *self;
+        Mat4 { rows:[
+            [1.0 - 2.0*(y*y + z*z), 2.0*(x*y - w*z),       2.0*(x*z + w*y),       0.0],
+            [2.0*(x*y + w*z),       1.0 - 2.0*(x*x + z*z), 2.0*(y*z - w*x),       0.0],
+            [2.0*(x*z - w*y),       2.0*(y*z + w*x),       1.0 - 2.0*(x*x + y*y), 0.0],
+            [0.0,                   0.0,                   0.0,                   1.0],
+        ]}
+    }
+}
+
+
+// an affine transform split into a translation, a rotation and a scale, applied scale first
+// so transforms can be blended for motion, rotations turning at a steady rate rather than shrinking part way
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AffineParts {
+    pub translation:Vec3,
+    pub rotation:Quaternion,
+    pub scale:Vec3,
+}
+
+impl AffineParts {
+    // None for singular transforms, shear is not kept, so only translate rotate scale transforms come back whole
+    pub fn from_mat4(m:&Mat4) -> Option<AffineParts> {
+        let determinant = m.determinant();
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let column = |c:usize| Vec3::new(m.rows[0][c], m.rows[1][c], m.rows[2][c]);
+        let (x, y, z) = (column(0), column(1), column(2));
+        // a mirror is kept as a negative scale along x, so what is left is a rotation
+        let sign = determinant.signum();
+        let scale = Vec3::new(x.length() * sign, y.length(), z.length());
+        let mut rotation = Mat4::identity();
+        for (c, axis) in [x / scale.x, y / scale.y, z / scale.z].iter().enumerate() {
+            rotation.rows[0][c] = axis.x;
+            rotation.rows[1][c] = axis.y;
+            rotation.rows[2][c] = axis.z;
+        }
+        Some(AffineParts {
+            translation:Vec3::new(m.rows[0][3], m.rows[1][3], m.rows[2][3]),
+            rotation:Quaternion::from_rotation(&rotation),
+            scale,
+        })
+    }
+
+    pub fn lerp(&self, other:&AffineParts, t:f32) -> AffineParts {
+        AffineParts {
+            translation:self.translation.lerp(other.translation, t),
+            rotation:self.rotation.slerp(&other.rotation, t),
+            scale:self.scale.lerp(other.scale, t),
+        }
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::translate(self.translation) * self.rotation.to_mat4() * Mat4::scale(self.scale)
+    }
+
+    // built from the parts rather than by inverting, the rotation's inverse is its transpose
+    pub fn inverse_mat4(&self) -> Mat4 {
+        let inverse_scale = Vec3::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        Mat4::scale(inverse_scale) * self.rotation.to_mat4().transpose() * Mat4::translate(-self.translation)
+    }
+}
+
+
+// transforms split into parts and back, and rotations between them turn at a steady rate
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Pcg32;
+    use crate::scene::TransformDescription;
+
+    fn close(a:&Mat4, b:&Mat4) -> bool {
+        (0..4).all(|row| (0..4).all(|column| (a.rows[row][column] - b.rows[row][column]).abs() < 1e-4))
+    }
+
+
+    // transforms made of translations, rotations and scales come apart and back together
+    #[test]
+    fn transforms_split_into_parts_and_back() {
+        let mut rng = Pcg32::new(1, 0);
+        for _ in 0..1000 {
+            let description = TransformDescription {
+                translate:[rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), rng.range(-5.0, 5.0)],
+                rotate:[rng.range(-180.0, 180.0), rng.range(-180.0, 180.0), rng.range(-180.0, 180.0)],
+                // mirrored along x half the time
+                scale:[rng.range(0.3, 3.0) * if rng.next_f32() < 0.5 {-1.0} else {1.0}, rng.range(0.3, 3.0), rng.range(0.3, 3.0)],
+            };
+            let transform = description.transform().expect("the transform is valid");
+            let parts = AffineParts::from_mat4(&transform).expect("the transform is invertible");
+            assert!(close(&parts.to_mat4(), &transform), "{:?} comes back as {:?}", description, parts);
+            assert!(close(&(parts.inverse_mat4() * transform), &Mat4::identity()));
+        }
+        assert!(AffineParts::from_mat4(&Mat4::scale(Vec3::new(1.0, 0.0, 1.0))).is_none());
+    }
+
+    // a half turn and a bit spins the short way round at a steady rate
+    #[test]
+    fn rotations_turn_the_short_way_at_a_steady_rate() {
+        let start = Quaternion::identity();
+        let end = Quaternion::from_rotation(&Mat4::rotate(Vec3::new(1.0, 2.0, 3.0).unit(), 200.0));
+        let angle = start.angle_to(&end);
+        assert!((angle - 160f32.to_radians()).abs() < 1e-3, "the short way round is 160 degrees, not {}", angle.to_degrees());
+        for step in 0..=10 {
+            let t = step as f32 / 10.0;
+            let turned = start.angle_to(&start.slerp(&end, t));
+            assert!((turned - t * angle).abs() < 1e-3, "at {} turned {} of {}", t, turned, angle);
+        }
+    }
+}
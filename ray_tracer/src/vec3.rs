@@ -0,0 +1,238 @@
+use matrices::matrix::Matrix;
+
+use crate::errors::RayTracerError;
+
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
+
+
+// the ray tracer does a lot of small vector maths, so it keeps its own copy type
+// and converts to and from the Matrix render_context uses at the edges
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Vec3 {
+    pub x:f32,
+    pub y:f32,
+    pub z:f32,
+}
+
+pub type Point = Vec3;
+pub type Colour = Vec3;
+
+
+impl Vec3 {
+    pub const fn new(x:f32, y:f32, z:f32) -> Vec3 {
+        Vec3 { x, y, z }
+    }
+
+    pub const fn zero() -> Vec3 {
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    pub const fn splat(value:f32) -> Vec3 {
+        Vec3::new(value, value, value)
+    }
+
+    pub fn dot(&self, other:Vec3) -> f32 {
+        self.x*other.x + self.y*other.y + self.z*other.z
+    }
+
+    pub fn cross(&self, other:Vec3) -> Vec3 {
+        Vec3::new(
+            self.y*other.z - self.z*other.y,
+            self.z*other.x - self.x*other.z,
+            self.x*other.y - self.y*other.x,
+        )
+    }
+
+    pub fn length_squared(&self) -> f32 {
+        self.dot(*self)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn unit(&self) -> Vec3 {
+        *self / self.length()
+    }
+
+    pub fn near_zero(&self) -> bool {
+        let s = 1e-8;
+        self.x.abs() < s && self.y.abs() < s && self.z.abs() < s
+    }
+
+    pub fn min(&self, other:Vec3) -> Vec3 {
+        Vec3::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+
+    pub fn max(&self, other:Vec3) -> Vec3 {
+        Vec3::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+
+    pub fn max_component(&self) -> f32 {
+        self.x.max(self.y).max(self.z)
+    }
+
+    pub fn lerp(&self, other:Vec3, t:f32) -> Vec3 {
+        *self * (1.0 - t) + other * t
+    }
+
+    pub fn map(&self, f:impl Fn(f32) -> f32) -> Vec3 {
+        Vec3::new(f(self.x), f(self.y), f(self.z))
+    }
+
+    // mirror direction about the normal
+    pub fn reflect(&self, normal:Vec3) -> Vec3 {
+        *self - normal * 2.0 * self.dot(normal)
+    }
+
+    // snell's law for a unit direction, eta_ratio is incident over transmitted index
+    pub fn refract(&self, normal:Vec3, eta_ratio:f32) -> Vec3 {
+        let cos_theta = (-*self).dot(normal).min(1.0);
+        let perpendicular = (*self + normal * cos_theta) * eta_ratio;
+        let parallel = normal * -(1.0 - perpendicular.length_squared()).abs().sqrt();
+        perpendicular + parallel
+    }
+
+    // two unit vectors completing a right handed basis with the unit normal (Duff et al. 2017)
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let sign = 1.0f32.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        let tangent = Vec3::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x);
+        let bitangent = Vec3::new(b, sign + self.y * self.y * a, -self.y);
+        (tangent, bitangent)
+    }
+
+    pub fn to_array(&self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+
+impl From<[f32; 3]> for Vec3 {
+    fn from(value: [f32; 3]) -> Self {
+        Vec3::new(value[0], value[1], value[2])
+    }
+}
+
+impl From<(f32, f32, f32)> for Vec3 {
+    fn from(value: (f32, f32, f32)) -> Self {
+        Vec3::new(value.0, value.1, value.2)
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(value: Vec3) -> Self {
+        value.to_array()
+    }
+}
+
+impl From<Vec3> for Matrix<f32> {
+    fn from(value: Vec3) -> Self {
+        Matrix::from_1darray(value.to_array())
+    }
+}
+
+// accepts 3 element matrices, and 4 element ones in homogeneous coordinates
+impl TryFrom<&Matrix<f32>> for Vec3 {
+    type Error = RayTracerError;
+    fn try_from(value: &Matrix<f32>) -> Result<Self, Self::Error> {
+        match value.array.as_slice() {
+            [x, y, z] => Ok(Vec3::new(*x, *y, *z)),
+            [x, y, z, w] if *w != 0.0 => Ok(Vec3::new(x / w, y / w, z / w)),
+            _ => Err(RayTracerError::MatrixShapeError(value.shape.clone())),
+        }
+    }
+}
+
+
+impl Index<usize> for Vec3 {
+    type Output = f32;
+    fn index(&self, index:usize) -> &f32 {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => &self.z,
+        }
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+    fn add(self, other:Vec3) -> Vec3 {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+    fn sub(self, other:Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+// component wise, used for attenuating colours
+impl Mul for Vec3 {
+    type Output = Vec3;
+    fn mul(self, other:Vec3) -> Vec3 {
+        Vec3::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Vec3;
+    fn mul(self, scale:f32) -> Vec3 {
+        Vec3::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl Mul<Vec3> for f32 {
+    type Output = Vec3;
+    fn mul(self, vector:Vec3) -> Vec3 {
+        vector * self
+    }
+}
+
+impl Div<f32> for Vec3 {
+    type Output = Vec3;
+    fn div(self, scale:f32) -> Vec3 {
+        Vec3::new(self.x / scale, self.y / scale, self.z / scale)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, other:Vec3) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, other:Vec3) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f32> for Vec3 {
+    fn mul_assign(&mut self, scale:f32) {
+        *self = *self * scale;
+    }
+}
+
+impl MulAssign for Vec3 {
+    fn mul_assign(&mut self, other:Vec3) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign<f32> for Vec3 {
+    fn div_assign(&mut self, scale:f32) {
+        *self = *self / scale;
+    }
+}
@@ -0,0 +1,39 @@
+// fixtures shared by the integration tests, each test file takes what it needs
+#![allow(dead_code)]
+
+use ray_tracer::hittable::Hittable;
+use ray_tracer::material::{Lambertian, Material};
+use ray_tracer::primitives::AaBox;
+use ray_tracer::ray::Ray;
+use ray_tracer::rng::Pcg32;
+use ray_tracer::vec3::{Point, Vec3};
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+
+// the least a scene file needs, three lines long
+pub const CAMERA : &str = "[camera]\nlook_from = [0.0, 0.0, 1.0]\nlook_at = [0.0, 0.0, 0.0]\n";
+
+pub fn grey() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Vec3::splat(0.5)))
+}
+
+pub fn unit_box() -> Arc<dyn Hittable> {
+    Arc::new(AaBox::new(Point::splat(-0.5), Point::splat(0.5), grey()))
+}
+
+// a random ray from 10 away aimed somewhere near target
+pub fn random_ray(rng:&mut Pcg32, target:Point, spread:f32) -> Ray {
+    let origin = target + rng.unit_vector() * 10.0;
+    let aim = target + Vec3::new(rng.range(-spread, spread), rng.range(-spread, spread), rng.range(-spread, spread));
+    Ray::new(origin, aim - origin)
+}
+
+// for mistakes that should be reported, with where they are in the error
+pub fn expect_error<T, E:Debug>(name:&str, result:Result<T, E>, expected:fn(&E) -> bool) {
+    match result {
+        Ok(_) => panic!("{}: expected an error", name),
+        Err(error) => assert!(expected(&error), "{}: unexpected error {:?}", name, error),
+    }
+}
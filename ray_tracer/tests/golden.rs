@@ -0,0 +1,20 @@
+// renders checked against the references in tests/references, which are regenerated by running the tests
+// with UPDATE_REFERENCE_IMAGES set after a change that is meant to alter them
+
+use images::compare::Tolerances;
+use images::golden::assert_matches_reference;
+
+use ray_tracer::renderer::{render, RenderSettings};
+use ray_tracer::scene::{bundled_path, load_scene};
+
+use std::sync::atomic::AtomicBool;
+
+
+// small and noisy, but renders are the same on every run so the noise is part of the reference
+#[test]
+fn cornell_box_matches_its_reference() {
+    let scene = load_scene(&bundled_path("scenes/cornell.toml")).expect("the scene loads");
+    let settings = RenderSettings { width:32, height:32, samples_per_pixel:16, seed:0, ..scene.settings };
+    let image = render(&scene.world, &scene.lights, &scene.camera, &settings, &mut |_| {}, &AtomicBool::new(false)).expect("the render finishes");
+    assert_matches_reference(&image, &bundled_path("tests/references/cornell.pfm"), &Tolerances::new());
+}
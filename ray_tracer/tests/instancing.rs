@@ -0,0 +1,127 @@
+// instances hit where the transformed object would be, with normals from the inverse transpose, their bounds
+// hold them so the bvh finds them, an instanced mesh matches one with its vertices moved, and instanced meshes
+// in scene files share their triangles
+
+use meshes::obj::load_obj;
+
+use ray_tracer::bvh::Bvh;
+use ray_tracer::errors::{RayTracerError, SceneError};
+use ray_tracer::hittable::Hittable;
+use ray_tracer::instance::Instance;
+use ray_tracer::mesh::mesh_triangles;
+use ray_tracer::primitives::Sphere;
+use ray_tracer::rng::Pcg32;
+use ray_tracer::scene::{bundled_path, load_scene_description, InstancedMeshes, SceneDescription, TransformDescription};
+use ray_tracer::transform::Mat4;
+use ray_tracer::vec3::{Point, Vec3};
+
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+use common::{grey, random_ray, unit_box, CAMERA};
+
+
+// a unit sphere stretched into an ellipsoid with semi axes 2, 1 and 0.5, turned and moved
+#[test]
+fn an_instanced_ellipsoid_is_hit_on_its_surface_with_gradient_normals() {
+    let mut rng = Pcg32::new(1, 0);
+    let radii = Vec3::new(2.0, 1.0, 0.5);
+    let center = Point::new(1.0, -2.0, 3.0);
+    let placement = Mat4::placement(center, Vec3::new(0.0, 0.0, 30.0), Point::zero()).expect("the placement is valid");
+    let sphere = Arc::new(Sphere::new(Point::zero(), 1.0, grey()));
+    let ellipsoid = Instance::new(sphere.clone(), placement * Mat4::scale(radii)).expect("the transform inverts");
+    let to_local = placement.inverse().expect("rotations invert");
+    let mut hits = 0;
+    for _ in 0..10_000 {
+        let ray = random_ray(&mut rng, center, 2.0);
+        let Some(hit) = ellipsoid.hit(&ray, 0.001, f32::INFINITY) else {
+            continue;
+        };
+        hits += 1;
+        // on the surface, (x/a)^2 + (y/b)^2 + (z/c)^2 = 1 in the ellipsoid's own axes, with the normal along its gradient
+        let local = to_local.transform_point(hit.point);
+        let scaled = Vec3::new(local.x / radii.x, local.y / radii.y, local.z / radii.z);
+        assert!((scaled.length() - 1.0).abs() < 1e-3, "hit off the surface at {:?}", local);
+        let gradient = placement.transform_vector(Vec3::new(local.x / (radii.x * radii.x), local.y / (radii.y * radii.y), local.z / (radii.z * radii.z))).unit();
+        let outward = if hit.front_face {hit.normal} else {-hit.normal};
+        assert!((outward - gradient).length() < 1e-3, "normal {:?} should be {:?}", outward, gradient);
+        assert!(hit.normal.dot(ray.direction) <= 0.0, "normals face against the ray");
+        assert!(ellipsoid.bounding_box().grow(hit.point) == ellipsoid.bounding_box(), "the bounds hold every hit");
+    }
+    assert!(hits > 1000);
+
+    assert!(matches!(Instance::new(sphere, Mat4::scale(Vec3::new(1.0, 0.0, 1.0))), Err(RayTracerError::SingularMatrix)));
+}
+
+// a bvh of turned boxes finds the same hits as testing every one
+#[test]
+fn a_bvh_of_instances_agrees_with_testing_every_one() {
+    let mut rng = Pcg32::new(2, 0);
+    let boxes : Vec<Arc<dyn Hittable>> = (0..50).map(|i| {
+        let angles = Vec3::new(rng.range(0.0, 360.0), rng.range(0.0, 360.0), rng.range(0.0, 360.0));
+        let offset = Vec3::new(rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), rng.range(-5.0, 5.0));
+        let placement = Mat4::placement(offset, angles, Point::zero()).expect("the placement is valid");
+        Arc::new(Instance::new(unit_box(), placement * Mat4::scale(Vec3::splat(0.5 + 0.02 * i as f32))).expect("the transform inverts")) as Arc<dyn Hittable>
+    }).collect();
+    let bvh = Bvh::new(boxes.clone());
+    for _ in 0..10_000 {
+        let ray = random_ray(&mut rng, Point::zero(), 5.0);
+        let closest = boxes.iter().filter_map(|object| object.hit(&ray, 0.001, f32::INFINITY)).map(|hit| hit.t).fold(f32::INFINITY, f32::min);
+        let found = bvh.hit(&ray, 0.001, f32::INFINITY).map_or(f32::INFINITY, |hit| hit.t);
+        assert_eq!(closest, found, "the bvh missed an instance");
+    }
+}
+
+// an instanced mesh against the same mesh with its vertices moved
+#[test]
+fn an_instanced_mesh_matches_the_mesh_with_moved_vertices() {
+    let mut rng = Pcg32::new(3, 0);
+    let mut mesh = load_obj(&bundled_path("../meshes/models/l_block.obj")).expect("the mesh loads");
+    let transform = TransformDescription { translate:[1.0, 0.5, -2.0], rotate:[10.0, 40.0, -20.0], scale:[1.5, 0.5, 2.0] }.transform().expect("the transform is valid");
+    let triangles = Bvh::new(mesh_triangles(&mesh, Some(grey())).into_iter().map(|triangle| triangle as Arc<dyn Hittable>).collect());
+    let instance = Instance::new(Arc::new(triangles), transform).expect("the transform inverts");
+    for position in mesh.positions.iter_mut() {
+        *position = transform.transform_point(Vec3::from(*position)).to_array();
+    }
+    let inverse = transform.inverse().expect("the transform inverts");
+    for normal in mesh.normals.iter_mut() {
+        *normal = Mat4::transform_normal(&inverse, Vec3::from(*normal)).unit().to_array();
+    }
+    let moved = Bvh::new(mesh_triangles(&mesh, Some(grey())).into_iter().map(|triangle| triangle as Arc<dyn Hittable>).collect());
+    let (mut agreed, mut disagreed) = (0, 0);
+    for _ in 0..10_000 {
+        let ray = random_ray(&mut rng, Point::new(1.0, 0.5, -2.0), 2.0);
+        match (instance.hit(&ray, 0.001, f32::INFINITY), moved.hit(&ray, 0.001, f32::INFINITY)) {
+            (Some(a), Some(b)) => {
+                assert!((a.t - b.t).abs() < 1e-3 * a.t, "t {} and {}", a.t, b.t);
+                assert!((a.normal - b.normal).length() < 1e-3, "normals {:?} and {:?}", a.normal, b.normal);
+                assert_eq!(a.front_face, b.front_face);
+                agreed += 1;
+            },
+            (None, None) => {},
+            // rays through an edge can fall either side of it
+            _ => disagreed += 1,
+        }
+    }
+    assert!(agreed > 1000);
+    assert!(disagreed < 10, "{} rays hit only one of the meshes", disagreed);
+}
+
+// every transformed instance of a file shares one copy for each material it is drawn with
+#[test]
+fn instanced_meshes_share_their_triangles() {
+    let description = load_scene_description(&bundled_path("scenes/instances.toml")).expect("the scene loads");
+    let scenes = bundled_path("scenes");
+    let mut instanced = InstancedMeshes::new();
+    for object in &description.objects {
+        object.objects(Some(grey()), Path::new(&scenes), &mut instanced).expect("the object builds");
+    }
+    assert_eq!(description.objects.len(), 10);
+    assert_eq!(instanced.len(), 2, "one copy for the default materials and one for gold");
+    assert_eq!(SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"), description);
+
+    let flat_source = format!("{}\n[[objects]]\ntype = \"mesh\"\npath = \"l_block.obj\"\ntransform = {{ scale = [1.0, 0.0, 1.0] }}\n", CAMERA);
+    assert!(matches!(SceneDescription::from_toml(&flat_source), Err(SceneError::InvalidTransform((8, 1)))));
+}
@@ -0,0 +1,80 @@
+// light sampling densities integrate to one and agree with the densities evaluated for the same directions,
+// and sampling the lights converges on the cornell box faster than waiting for rays to find them
+
+use images::compare::mse;
+use images::image::Image;
+use images::tone_map::{tone_map, ToneMapSettings};
+
+use ray_tracer::hittable::Surface;
+use ray_tracer::light::LightList;
+use ray_tracer::material::Emissive;
+use ray_tracer::primitives::{Quad, Triangle};
+use ray_tracer::ray::Ray;
+use ray_tracer::renderer::{render, RenderSettings};
+use ray_tracer::rng::Pcg32;
+use ray_tracer::scene::{bundled_path, load_scene, Scene};
+use ray_tracer::vec3::{Colour, Point, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+
+// two lights facing the origin, of different brightness, so both the light choice and the point on it count
+fn two_lights() -> LightList {
+    let bright = Arc::new(Emissive::new(Colour::splat(1.0), 4.0));
+    let dim = Arc::new(Emissive::new(Colour::new(1.0, 0.5, 0.2), 1.0));
+    let quad : Arc<dyn Surface> = Arc::new(Quad::new(Point::new(-1.0, 2.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), bright));
+    let triangle : Arc<dyn Surface> = Arc::new(Triangle::new(Point::new(3.0, -1.0, -1.0), Point::new(3.0, -1.0, 1.0), Point::new(3.0, 1.0, 0.0), dim));
+    LightList::new(vec![quad, triangle])
+}
+
+fn render_with(scene:&Scene, lights:&LightList, settings:&RenderSettings) -> Image {
+    let image = render(&scene.world, lights, &scene.camera, settings, &mut |_| {}, &AtomicBool::new(false)).expect("the render finishes");
+    // compared after tone mapping, so the few pixels the light partly covers don't swamp the rest of the image
+    tone_map(&image, &ToneMapSettings::new())
+}
+
+
+// the integral of the solid angle density over every direction from the origin, by uniform sphere sampling
+#[test]
+fn light_densities_integrate_to_one() {
+    let lights = two_lights();
+    let (mut rng, samples) = (Pcg32::new(5, 0), 200_000);
+    let mut sum = 0.0;
+    for _ in 0..samples {
+        let ray = Ray::new(Point::zero(), rng.unit_vector());
+        if let Some(hit) = lights.lights.iter().filter_map(|light| light.hit(&ray, 0.0, f32::INFINITY)).next() {
+            sum += lights.pdf(&ray, &hit);
+        }
+    }
+    let integral = sum * 4.0 * PI / samples as f32;
+    assert!((integral - 1.0).abs() < 0.02, "light pdf integrates to {}", integral);
+}
+
+#[test]
+fn sampled_densities_match_evaluated_ones() {
+    let lights = two_lights();
+    let mut rng = Pcg32::new(6, 0);
+    for _ in 0..1000 {
+        let sample = lights.sample(Point::zero(), &mut rng).expect("both lights face the origin");
+        let ray = Ray::new(Point::zero(), sample.direction);
+        let hit = lights.lights.iter().filter_map(|light| light.hit(&ray, 0.0, f32::INFINITY)).next().expect("sample is on a light");
+        assert!((hit.t - sample.distance).abs() < 1e-3);
+        assert!((lights.pdf(&ray, &hit) - sample.pdf).abs() < 1e-3 * sample.pdf, "sampled and evaluated pdfs differ");
+    }
+}
+
+#[test]
+fn light_sampling_has_less_error_at_equal_samples() {
+    let mut scene = load_scene(&bundled_path("scenes/cornell.toml")).expect("the scene loads");
+    assert_eq!(scene.lights.len(), 1, "only the ceiling light emits");
+    scene.settings.width = 32;
+    scene.settings.height = 32;
+
+    let reference = render_with(&scene, &scene.lights, &RenderSettings { samples_per_pixel:256, seed:1, ..scene.settings });
+    let settings = RenderSettings { samples_per_pixel:8, seed:2, ..scene.settings };
+    let sampled_error = mse(&render_with(&scene, &scene.lights, &settings), &reference).expect("the images match in size");
+    let naive_error = mse(&render_with(&scene, &LightList::new(vec![]), &settings), &reference).expect("the images match in size");
+    assert!(sampled_error * 2.0 < naive_error, "light sampling error {} against naive {}", sampled_error, naive_error);
+}
@@ -0,0 +1,147 @@
+// phase functions are normalised and sample what they evaluate, media let through the light beer's law says
+// they should, density grids parse and report mistakes, and a white medium in a white furnace neither gains
+// nor loses energy
+
+use ray_tracer::errors::{GridError, SceneError};
+use ray_tracer::hittable::{HitRecord, Hittable};
+use ray_tracer::light::LightList;
+use ray_tracer::material::{HenyeyGreenstein, Isotropic, Material};
+use ray_tracer::medium::{ConstantMedium, DensityGrid, GridMedium};
+use ray_tracer::primitives::Sphere;
+use ray_tracer::ray::Ray;
+use ray_tracer::renderer::{radiance, Background, RenderSettings};
+use ray_tracer::rng::Pcg32;
+use ray_tracer::scene::{bundled_path, load_scene, load_scene_description, SceneDescription};
+use ray_tracer::vec3::{Colour, Point, Vec3};
+
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+mod common;
+
+use common::expect_error;
+
+
+// any hit record will do for phase functions, they only look at the ray
+fn phase_hit<'m>(ray:&Ray, material:&'m dyn Material) -> HitRecord<'m> {
+    HitRecord::new(ray, 0.0, -ray.direction, 0.0, 0.0, material)
+}
+
+fn isotropic() -> Arc<dyn Material> {
+    Arc::new(Isotropic::new(Colour::splat(1.0)))
+}
+
+fn unit_sphere() -> Arc<Sphere> {
+    Arc::new(Sphere::new(Point::zero(), 1.0, isotropic()))
+}
+
+// the fraction of rays along the x axis through the middle of the box from -1 to 1 that get through,
+// each ray starts somewhere different so each draws its own free path
+fn transmittance(medium:&dyn Hittable, rays:usize) -> f32 {
+    let mut rng = Pcg32::new(3, 0);
+    let passed = (0..rays)
+        .filter(|_| {
+            let origin = Point::new(-5.0, rng.range(-0.01, 0.01), rng.range(-0.01, 0.01));
+            medium.hit(&Ray::new(origin, Vec3::new(1.0, 0.0, 0.0)), 0.001, f32::INFINITY).is_none()
+        })
+        .count();
+    passed as f32 / rays as f32
+}
+
+
+#[test]
+fn phase_functions_integrate_to_one_and_their_mean_cosine_is_the_asymmetry() {
+    let white = Colour::splat(1.0);
+    let incoming = Ray::new(Point::zero(), Vec3::new(0.0, 0.0, -1.0));
+    let mut rng = Pcg32::new(1, 0);
+    for asymmetry in [-0.6, 0.0, 0.5, 0.9] {
+        let phase = HenyeyGreenstein::new(white, asymmetry);
+        let hit = phase_hit(&incoming, &phase);
+        // uniform directions integrate the density, sampled ones estimate its mean cosine
+        let samples = 200_000;
+        let integral = (0..samples).map(|_| phase.pdf(&incoming, &hit, rng.unit_vector())).sum::<f32>() * 4.0 * PI / samples as f32;
+        assert!((integral - 1.0).abs() < 0.03, "g {} integrates to {}", asymmetry, integral);
+        let mut mean_cosine = 0.0;
+        for _ in 0..samples {
+            let scatter = phase.scatter(&incoming, &hit, &mut rng).expect("media always scatter");
+            let direction = scatter.ray.direction;
+            assert!((scatter.pdf.unwrap() - phase.pdf(&incoming, &hit, direction)).abs() < 1e-3 * scatter.pdf.unwrap());
+            assert!((direction.length() - 1.0).abs() < 1e-3);
+            mean_cosine += direction.dot(incoming.direction) / samples as f32;
+        }
+        assert!((mean_cosine - asymmetry).abs() < 0.01, "g {} has mean cosine {}", asymmetry, mean_cosine);
+    }
+    let isotropic = Isotropic::new(white);
+    let pdf = isotropic.pdf(&incoming, &phase_hit(&incoming, &isotropic), Vec3::new(1.0, 0.0, 0.0));
+    assert!((pdf * 4.0 * PI - 1.0).abs() < 1e-5);
+}
+
+// beer's law, a path of 2 through density 0.5 lets through e^-1
+#[test]
+fn media_let_through_what_beers_law_says() {
+    let fog = ConstantMedium::new(unit_sphere(), 0.5, isotropic());
+    let passed = transmittance(&fog, 100_000);
+    assert!((passed - (-1.0f32).exp()).abs() < 0.01, "constant medium lets through {}", passed);
+    let uniform = DensityGrid::new(3, 2, 2, vec![0.5; 12]);
+    let grid = GridMedium::new(uniform, Point::splat(-1.0), Point::splat(1.0), 1.0, isotropic());
+    let passed_grid = transmittance(&grid, 100_000);
+    assert!((passed_grid - (-1.0f32).exp()).abs() < 0.01, "uniform grid lets through {}", passed_grid);
+    let empty = DensityGrid::new(2, 2, 2, vec![0.0; 8]);
+    assert_eq!(transmittance(&GridMedium::new(empty, Point::splat(-1.0), Point::splat(1.0), 5.0, isotropic()), 1000), 1.0);
+}
+
+// density 2 on the right half only, so rays along x see a path of 1 through density 2 and rays along y see nothing
+#[test]
+fn grid_densities_are_interpolated() {
+    let half = DensityGrid::new(2, 2, 2, vec![0.0, 2.0, 0.0, 2.0, 0.0, 2.0, 0.0, 2.0]);
+    let half_grid = GridMedium::new(half, Point::new(-1.5, -1.0, -1.0), Point::new(1.5, 1.0, 1.0), 1.0, isotropic());
+    assert!((half_grid.density_at(Point::new(1.5, 0.0, 0.0)) - 2.0).abs() < 1e-5);
+    assert!((half_grid.density_at(Point::new(0.0, 0.3, 0.0)) - 1.0).abs() < 1e-5);
+    let along_y = Ray::new(Point::new(-1.5, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    assert!(half_grid.hit(&along_y, 0.001, f32::INFINITY).is_none());
+    let t = half_grid.hit(&Ray::new(Point::new(-1.5, 0.0, -5.0), Vec3::new(0.0, 0.0, 2.0)), 0.001, 1.0);
+    assert!(t.is_none(), "t_max stops the ray before the grid");
+}
+
+#[test]
+fn density_grids_parse_and_report_mistakes() {
+    let grid = DensityGrid::parse("# two by one by one\n2 1 1\n0.5 1.5 # densities\n").expect("the grid parses");
+    assert_eq!(grid, DensityGrid::new(2, 1, 1, vec![0.5, 1.5]));
+    expect_error("bad size", DensityGrid::parse("2 x 1\n0 0\n"), |error| matches!(error, GridError::InvalidHeader((1, token)) if token == "x"));
+    expect_error("bad density", DensityGrid::parse("1 1 2\n0.5\nthick\n"), |error| matches!(error, GridError::InvalidNumber((3, token)) if token == "thick"));
+    expect_error("negative density", DensityGrid::parse("1 1 1\n-1\n"), |error| matches!(error, GridError::NegativeDensity((2, _))));
+    expect_error("too few densities", DensityGrid::parse("2 2 2\n1 1 1\n"), |error| matches!(error, GridError::WrongCount((8, 3))));
+}
+
+// headers asking for more densities than memory holds, or than can be counted, are errors rather than allocations
+#[test]
+fn huge_headers_are_errors() {
+    expect_error("huge grid", DensityGrid::parse("100000 100000 100000\n1\n"), |error| matches!(error, GridError::WrongCount((1_000_000_000_000_000, 1))));
+    let overflowing = format!("# sizes\n{} 4\n{}\n1\n", usize::MAX / 2, usize::MAX / 2);
+    expect_error("overflowing grid", DensityGrid::parse(&overflowing), |error| matches!(error, GridError::InvalidHeader((3, _))));
+}
+
+// a white furnace, everything scatters and nothing absorbs, so whatever the paths do every pixel sees the background
+#[test]
+fn a_white_medium_in_a_white_furnace_stays_white() {
+    let white = Colour::splat(1.0);
+    let settings = RenderSettings { max_depth:256, background:Background::Solid(white), ..RenderSettings::new(1, 1) };
+    let mut rng = Pcg32::new(4, 0);
+    let (mut mean, mut rays) = (Colour::zero(), 0);
+    let furnace = ConstantMedium::new(unit_sphere(), 2.0, Arc::new(HenyeyGreenstein::new(white, 0.4)));
+    let samples = 20_000;
+    for _ in 0..samples {
+        let ray = Ray::new(Point::new(0.0, 0.0, 4.0), (Point::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), 0.0) - Point::new(0.0, 0.0, 4.0)).unit());
+        mean += radiance(&furnace, &LightList::new(vec![]), &ray, &settings, &mut rng, &mut rays) / samples as f32;
+    }
+    assert!((mean - white).length() < 1e-3, "the furnace came out as {:?}", mean);
+}
+
+// media and their boundaries survive a round trip through toml, and grid files are found next to the scene
+#[test]
+fn media_scenes_load_their_grids() {
+    let description = load_scene_description(&bundled_path("scenes/media.toml")).expect("the scene loads");
+    assert_eq!(SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"), description);
+    assert!(matches!(description.build_in(&std::env::temp_dir().join("missing")), Err(SceneError::GridError((_, GridError::FileError(_))))));
+    assert_eq!(load_scene(&bundled_path("scenes/media.toml")).expect("the scene builds").world.len(), 6);
+}
@@ -0,0 +1,141 @@
+// camera rays carry times from the open shutter, moving objects are where their keyframes put them, their bounds
+// hold them at every time so the bvh finds them, a moving ball covers a point for the share of the shutter it
+// should, and keyframes survive scene files
+
+use ray_tracer::aabb::Aabb;
+use ray_tracer::bvh::Bvh;
+use ray_tracer::camera::{Camera, CameraSettings};
+use ray_tracer::errors::{RayTracerError, SceneError};
+use ray_tracer::hittable::Hittable;
+use ray_tracer::instance::{Instance, MovingInstance};
+use ray_tracer::primitives::Sphere;
+use ray_tracer::ray::Ray;
+use ray_tracer::rng::Pcg32;
+use ray_tracer::scene::{bundled_path, load_scene_description, SceneDescription, TransformDescription};
+use ray_tracer::transform::Mat4;
+use ray_tracer::vec3::{Point, Vec3};
+
+use std::sync::Arc;
+
+mod common;
+
+use common::{grey, random_ray, unit_box, CAMERA};
+
+
+fn keyframes() -> Vec<(f32, Mat4)> {
+    [
+        (0.0, TransformDescription { translate:[0.0, 0.0, 0.0], rotate:[0.0, 0.0, 0.0], scale:[1.0, 1.0, 1.0] }),
+        (0.3, TransformDescription { translate:[2.0, 1.0, 0.0], rotate:[20.0, 70.0, 0.0], scale:[1.5, 0.5, 1.0] }),
+        (1.0, TransformDescription { translate:[-1.0, 3.0, 2.0], rotate:[0.0, 160.0, -40.0], scale:[0.5, 2.0, 1.0] }),
+    ].into_iter().map(|(time, description)| (time, description.transform().expect("the transform is valid"))).collect()
+}
+
+fn close(a:&Mat4, b:&Mat4) -> bool {
+    (0..4).all(|row| (0..4).all(|column| (a.rows[row][column] - b.rows[row][column]).abs() < 1e-4))
+}
+
+
+// times are spread over the shutter, and a still camera leaves the random stream as it was
+#[test]
+fn camera_rays_are_sent_over_the_shutter() {
+    let mut rng = Pcg32::new(1, 0);
+    let camera = Camera::new(&CameraSettings { shutter_open:0.25, shutter_close:0.75, ..CameraSettings::new() });
+    let times : Vec<f32> = (0..10_000).map(|_| camera.get_ray(0.5, 0.5, &mut rng).time).collect();
+    assert!(times.iter().all(|time| (0.25..=0.75).contains(time)));
+    let mean = times.iter().sum::<f32>() / times.len() as f32;
+    assert!((mean - 0.5).abs() < 0.01, "mean time {}", mean);
+    let still = Camera::new(&CameraSettings { shutter_open:0.4, shutter_close:0.1, ..CameraSettings::new() });
+    let (mut with_still, mut without) = (rng.clone(), rng.clone());
+    assert_eq!(still.get_ray(0.5, 0.5, &mut with_still).time, 0.4, "a shutter closing before it opens is a still");
+    without.in_unit_disk();
+    assert_eq!(with_still.next_u32(), without.next_u32(), "stills take no random number for the time");
+}
+
+// at its keyframes a moving object is an instance with that keyframe's transform
+#[test]
+fn moving_objects_match_instances_at_their_keyframes() {
+    let mut rng = Pcg32::new(2, 0);
+    let keyframes = keyframes();
+    let tumbling = MovingInstance::new(unit_box(), keyframes.clone()).expect("the keyframes are valid");
+    for (time, transform) in &keyframes {
+        assert!(close(&tumbling.transform_at(*time), transform), "at {} the transform is {:?}", time, tumbling.transform_at(*time));
+        let fixed = Instance::new(unit_box(), *transform).expect("the transform inverts");
+        for _ in 0..1000 {
+            let ray = random_ray(&mut rng, transform.transform_point(Point::zero()), 1.0).with_time(*time);
+            match (tumbling.hit(&ray, 0.001, f32::INFINITY), fixed.hit(&ray, 0.001, f32::INFINITY)) {
+                (Some(a), Some(b)) => assert!((a.t - b.t).abs() < 1e-3 && (a.normal - b.normal).length() < 1e-3),
+                (None, None) => {},
+                // rays grazing an edge can fall either side of it
+                (a, b) => assert!(a.or(b).is_some_and(|hit| hit.normal.dot(ray.direction.unit()).abs() < 0.05)),
+            }
+        }
+    }
+    assert!(close(&tumbling.transform_at(-1.0), &keyframes[0].1) && close(&tumbling.transform_at(2.0), &keyframes[2].1), "outside the keyframes it stays put");
+    assert!(matches!(MovingInstance::new(unit_box(), vec![(1.0, Mat4::identity()), (1.0, Mat4::identity())]), Err(RayTracerError::InvalidKeyframes)));
+    assert!(matches!(MovingInstance::new(unit_box(), vec![]), Err(RayTracerError::InvalidKeyframes)));
+    assert!(matches!(MovingInstance::linear(unit_box(), Mat4::identity(), Mat4::scale(Vec3::zero()), 0.0, 1.0), Err(RayTracerError::SingularMatrix)));
+}
+
+// the bounds hold the object at every time, so a bvh of moving boxes finds what testing every box does
+#[test]
+fn moving_objects_stay_in_their_bounds() {
+    let mut rng = Pcg32::new(3, 0);
+    let tumbling = MovingInstance::new(unit_box(), keyframes()).expect("the keyframes are valid");
+    let bounds = tumbling.bounding_box();
+    let bounds = Aabb::new(bounds.min - Vec3::splat(1e-4), bounds.max + Vec3::splat(1e-4));
+    let mut hits = 0;
+    for _ in 0..20_000 {
+        let time = rng.range(-0.2, 1.2);
+        let ray = random_ray(&mut rng, tumbling.transform_at(time).transform_point(Point::zero()), 1.0).with_time(time);
+        if let Some(hit) = tumbling.hit(&ray, 0.001, f32::INFINITY) {
+            hits += 1;
+            assert!(bounds.grow(hit.point) == bounds, "{:?} at {} is out of bounds", hit.point, ray.time);
+        }
+    }
+    assert!(hits > 5000, "only {} hits", hits);
+
+    let boxes : Vec<Arc<dyn Hittable>> = (0..50).map(|_| {
+        let mut placement = || {
+            let angles = Vec3::new(rng.range(0.0, 360.0), rng.range(0.0, 360.0), rng.range(0.0, 360.0));
+            let offset = Vec3::new(rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), rng.range(-5.0, 5.0));
+            Mat4::placement(offset, angles, Point::zero()).expect("the placement is valid")
+        };
+        let (start, end) = (placement(), placement());
+        Arc::new(MovingInstance::linear(unit_box(), start, end, 0.0, 1.0).expect("the keyframes are valid")) as Arc<dyn Hittable>
+    }).collect();
+    let bvh = Bvh::new(boxes.clone());
+    for _ in 0..10_000 {
+        let ray = random_ray(&mut rng, Point::zero(), 5.0).with_time(rng.next_f32());
+        let closest = boxes.iter().filter_map(|object| object.hit(&ray, 0.001, f32::INFINITY)).map(|hit| hit.t).fold(f32::INFINITY, f32::min);
+        let found = bvh.hit(&ray, 0.001, f32::INFINITY).map_or(f32::INFINITY, |hit| hit.t);
+        assert_eq!(closest, found, "the bvh missed a moving box");
+    }
+}
+
+// a ball of radius 0.6 rolling 2 along x covers a point on its path for 1.2 of the 2, so 60% of the shutter
+#[test]
+fn a_rolling_ball_covers_a_point_for_its_share_of_the_shutter() {
+    let mut rng = Pcg32::new(4, 0);
+    let ball = Arc::new(Sphere::new(Point::zero(), 0.6, grey()));
+    let rolling = MovingInstance::linear(ball, Mat4::translate(Vec3::new(-1.0, 0.0, 0.0)), Mat4::translate(Vec3::new(1.0, 0.0, 0.0)), 0.0, 1.0)
+        .expect("the keyframes are valid");
+    let samples = 100_000;
+    let covered = (0..samples)
+        .filter(|_| rolling.hit(&Ray::new(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)).with_time(rng.next_f32()), 0.001, f32::INFINITY).is_some())
+        .count() as f32 / samples as f32;
+    assert!((covered - 0.6).abs() < 0.01, "the ball covered {}", covered);
+}
+
+// keyframes survive a round trip through toml, and ones out of order are reported where they are
+#[test]
+fn keyframes_survive_scene_files() {
+    let description = load_scene_description(&bundled_path("scenes/motion.toml")).expect("the scene loads");
+    assert_eq!(SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"), description);
+    assert_eq!(description.camera.shutter_close, 1.0);
+    let backwards = format!("{}\n[materials.grey]\ntype = \"lambertian\"\ndiffuse_colour = [0.5, 0.5, 0.5]\n\n\
+                             [[objects]]\ntype = \"moving\"\nobject = {{ type = \"sphere\", center = [0.0, 0.0, 0.0], radius = 1.0, material = \"grey\" }}\n\
+                             keyframes = [{{ time = 1.0 }}, {{ time = 0.0 }}]\n", CAMERA);
+    assert!(matches!(SceneDescription::from_toml(&backwards), Err(SceneError::InvalidKeyframes((12, 1)))));
+    let flattened = backwards.replace("{ time = 1.0 }, { time = 0.0 }", "{ time = 0.0 }, { time = 1.0, scale = [1.0, 0.0, 1.0] }");
+    assert!(matches!(SceneDescription::from_toml(&flattened), Err(SceneError::InvalidTransform((12, 1)))));
+}
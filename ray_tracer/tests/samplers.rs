@@ -0,0 +1,48 @@
+// renders with every sampler are the same however they are threaded, tiled or split into progressive passes,
+// and samplers are read from scene files by name
+
+use images::compare::{compare_images, mse, Tolerances};
+
+use ray_tracer::errors::SceneError;
+use ray_tracer::renderer::{render, Accumulator, RenderSettings};
+use ray_tracer::sampler::Sampler;
+use ray_tracer::scene::{bundled_path, load_scene, SamplerDescription, SceneDescription};
+
+use std::sync::atomic::AtomicBool;
+
+
+// golden images depend on renders being the same however they are threaded and tiled
+#[test]
+fn renders_are_identical_across_threads_tiles_and_passes() {
+    let scene = load_scene(&bundled_path("scenes/spheres.toml")).expect("the scene loads");
+    let cancel = AtomicBool::new(false);
+    for sampler in [Sampler::Independent, Sampler::Stratified, Sampler::Halton, Sampler::Sobol, Sampler::BlueNoise] {
+        let settings = RenderSettings { width:48, height:27, samples_per_pixel:6, threads:1, sampler, ..scene.settings };
+        let render_with = |settings:&RenderSettings| render(&scene.world, &scene.lights, &scene.camera, settings, &mut |_| {}, &cancel).expect("the render finishes");
+        let reference = render_with(&settings);
+        for (threads, tile_size) in [(4, 32), (3, 7), (1, 1)] {
+            let image = render_with(&RenderSettings { threads, tile_size, ..settings });
+            let comparison = compare_images(&reference, &image, &Tolerances::exact()).expect("the images match in size");
+            assert!(comparison.passes(&Tolerances::exact()), "{:?} changed with {} threads", sampler, threads);
+        }
+        assert!(render_with(&RenderSettings { seed:1, ..settings }) != reference, "{:?} ignores the seed", sampler);
+
+        // as many passes as samples per pixel sum to what render averages
+        let mut accumulator = Accumulator::new(settings.width, settings.height);
+        for _ in 0..settings.samples_per_pixel {
+            accumulator.sample_pass(&scene.world, &scene.lights, &scene.camera, &RenderSettings { threads:4, ..settings });
+        }
+        assert!(mse(&accumulator.average(), &reference).expect("the images match in size") < 1e-10, "{:?} passes differ from the render", sampler);
+    }
+}
+
+// unknown samplers are reported where they are
+#[test]
+fn samplers_are_read_from_scene_files_by_name() {
+    let source = "[settings]\nwidth = 8\nheight = 8\nsampler = \"blue_noise\"\n\n[camera]\nlook_from = [0.0, 0.0, 1.0]\nlook_at = [0.0, 0.0, 0.0]\n";
+    let description = SceneDescription::from_toml(source).expect("the scene parses");
+    assert_eq!(description.settings.sampler, SamplerDescription::BlueNoise);
+    assert_eq!(description.build().expect("the scene builds").settings.sampler, Sampler::BlueNoise);
+    assert_eq!(SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"), description);
+    assert!(matches!(SceneDescription::from_toml(&source.replace("blue_noise", "pink_noise")), Err(SceneError::ParseError((_, 4, _)))));
+}
@@ -0,0 +1,108 @@
+// scene files load, save and load again to the same description, and mistakes are reported
+// at the line and column they are on
+
+use images::image::{ChannelLayout, Image};
+use images::netpbm::{save_netpbm, NetpbmFormat};
+
+use ray_tracer::errors::SceneError;
+use ray_tracer::hittable::Hittable;
+use ray_tracer::ray::Ray;
+use ray_tracer::scene::{bundled_path, load_scene, load_scene_description, SceneDescription};
+use ray_tracer::vec3::{Point, Vec3};
+
+
+mod common;
+
+use common::{expect_error, CAMERA};
+
+
+#[test]
+fn bundled_scenes_round_trip() {
+    for name in ["spheres", "cornell", "textures", "l_block", "media", "instances", "motion"] {
+        let description = load_scene_description(&bundled_path(&format!("scenes/{}.toml", name))).expect("the scene loads");
+        let saved = description.to_toml().expect("the scene saves");
+        let reloaded = SceneDescription::from_toml(&saved).expect("the saved scene loads");
+        assert_eq!(description, reloaded, "{} changed on a round trip", name);
+        assert_eq!(saved, reloaded.to_toml().expect("the scene saves"), "{} text changed on a second round trip", name);
+    }
+}
+
+#[test]
+fn scenes_build_every_object() {
+    let description = load_scene_description(&bundled_path("scenes/spheres.toml")).expect("the scene loads");
+    let scene = description.build().expect("the scene builds");
+    assert_eq!(scene.world.len(), description.objects.len());
+}
+
+#[test]
+fn mistakes_are_reported_where_they_are() {
+    let unknown_material = format!("{}\n[[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 1.0\nmaterial = \"missing\"\n", CAMERA);
+    expect_error("unknown material", SceneDescription::from_toml(&unknown_material),
+        |error| matches!(error, SceneError::UnknownMaterial((name, 9, 1)) if name == "missing"));
+
+    let unknown_type = format!("{}\n[materials.odd]\ntype = \"velvet\"\n", CAMERA);
+    expect_error("unknown material type", SceneDescription::from_toml(&unknown_type), |error| matches!(error, SceneError::ParseError((_, 6, 8))));
+
+    // values inside a material or object are checked once its type is known, so their errors point at its table
+    let wrong_type = format!("{}\n[materials.red]\ntype = \"lambertian\"\ndiffuse_colour = true\n", CAMERA);
+    expect_error("wrong value type", SceneDescription::from_toml(&wrong_type), |error| matches!(error, SceneError::ParseError((_, 5, 1))));
+
+    let syntax = format!("{}\n[settings\nwidth = 10\n", CAMERA);
+    expect_error("syntax error", SceneDescription::from_toml(&syntax), |error| matches!(error, SceneError::ParseError((_, 5, _))));
+
+    expect_error("missing camera", SceneDescription::from_toml("[settings]\nwidth = 10\nheight = 10\n"), |error| matches!(error, SceneError::ParseError(_)));
+
+    let empty_image = format!("[settings]\nwidth = 0\nheight = 10\n{}", CAMERA);
+    expect_error("empty image", SceneDescription::from_toml(&empty_image), |error| matches!(error, SceneError::InvalidDimensions((0, 10))));
+}
+
+// a transformed primitive is where its transform puts it, and a transformed light is only seen, not sampled
+#[test]
+fn primitives_take_transforms() {
+    let source = format!("{}\n[materials.light]\ntype = \"emissive\"\nemission_colour = [1.0, 1.0, 1.0]\n\n\
+                          [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 1.0\nmaterial = \"light\"\n\
+                          transform = {{ translate = [5.0, 0.0, 0.0], scale = [2.0, 1.0, 1.0] }}\n", CAMERA);
+    let description = SceneDescription::from_toml(&source).expect("the scene parses");
+    assert_eq!(SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"), description);
+    let scene = description.build().expect("the scene builds");
+    assert_eq!(scene.lights.len(), 0);
+    let ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    let hit = scene.world.hit(&ray, 0.001, f32::INFINITY).expect("the stretched sphere is on the x axis");
+    assert!((hit.t - 3.0).abs() < 1e-4, "hit at {}", hit.t);
+
+    let untransformed = source.replace("transform = { translate = [5.0, 0.0, 0.0], scale = [2.0, 1.0, 1.0] }\n", "");
+    assert_eq!(SceneDescription::from_toml(&untransformed).and_then(|description| description.build()).map(|scene| scene.lights.len()).ok(), Some(1));
+
+    let flattened = source.replace("scale = [2.0, 1.0, 1.0]", "scale = [2.0, 0.0, 1.0]");
+    expect_error("flat transform", SceneDescription::from_toml(&flattened), |error| matches!(error, SceneError::InvalidTransform((14, 1))));
+}
+
+// a mesh is one object per triangle alongside the ground
+#[test]
+fn meshes_build_a_triangle_each() {
+    let mesh = meshes::obj::load_obj(&bundled_path("../meshes/models/l_block.obj")).expect("the mesh loads");
+    let scene = load_scene(&bundled_path("scenes/l_block.toml")).expect("the scene builds");
+    assert_eq!(scene.world.len(), 1 + mesh.triangles.len());
+}
+
+#[test]
+fn materials_name_textures() {
+    let unknown_texture = format!("{}\n[materials.red]\ntype = \"ggx\"\ndiffuse_colour = [1.0, 0.0, 0.0]\nroughness = \"missing\"\n", CAMERA);
+    expect_error("unknown texture", SceneDescription::from_toml(&unknown_texture), |error| matches!(error, SceneError::UnknownTexture((name, 8, 1)) if name == "missing"));
+}
+
+// image paths are relative to the scene
+#[test]
+fn image_textures_load_relative_to_the_scene() {
+    let directory = std::env::temp_dir();
+    let mut image = Image::new_u8(2, 1, ChannelLayout::Rgb);
+    image.set_rgb(1, 0, [1.0, 1.0, 1.0]);
+    save_netpbm(&image, &directory.join("ray_tracer_texture.ppm").to_string_lossy(), NetpbmFormat::PixmapBinary).expect("the texture saves");
+    let image_scene = format!("{}\n[textures.picture]\ntype = \"image\"\npath = \"ray_tracer_texture.ppm\"\nwrap = \"clamp\"\n\n\
+        [materials.picture]\ntype = \"lambertian\"\ndiffuse_colour = \"picture\"\n\n\
+        [[objects]]\ntype = \"quad\"\ncorner = [0.0, 0.0, 0.0]\nu = [1.0, 0.0, 0.0]\nv = [0.0, 1.0, 0.0]\nmaterial = \"picture\"\n", CAMERA);
+    let description = SceneDescription::from_toml(&image_scene).expect("the scene parses");
+    assert_eq!(description, SceneDescription::from_toml(&description.to_toml().expect("the scene saves")).expect("the saved scene loads"));
+    assert_eq!(description.build_in(&directory).expect("the scene builds").world.len(), 1);
+    assert!(matches!(description.build_in(&directory.join("missing")), Err(SceneError::ImageError(_))));
+}
@@ -45,6 +45,28 @@ impl Camera {
         orthographic_projection
     }
 
+    // half the width and height of the visible region in view space, as used by the projection
+    pub fn view_extent(&self, width:u32, height:u32) -> (f32, f32) {
+        ((width / height) as f32 * self.zoom, self.zoom)
+    }
+
+    // window cursor position (pixels from the top left) to view space, before pan is undone
+    pub fn screen_to_view(&self, cursor:[f32; 2], width:u32, height:u32) -> (f32, f32) {
+        let (half_width, half_height) = self.view_extent(width, height);
+        let ndc_x = 2.0 * cursor[0] / width as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * cursor[1] / height as f32;
+        (ndc_x * half_width, ndc_y * half_height)
+    }
+
+    // changes the zoom while keeping the point under the cursor fixed on screen
+    pub fn zoom_around(&mut self, new_zoom:f32, cursor:[f32; 2], width:u32, height:u32) {
+        let (view_x, view_y) = self.screen_to_view(cursor, width, height);
+        let ratio = new_zoom / self.zoom;
+        self.pan_xyz.0 += view_x * (ratio - 1.0);
+        self.pan_xyz.1 += view_y * (ratio - 1.0);
+        self.zoom = new_zoom;
+    }
+
     pub fn get_camera_transform(&self) -> Result<Matrix<f32>, MatrixError> {
         let camera_rotation = Matrix::rotate_around_p((0.0, 0.0, 0.0), self.angle_xyz)?;
         let camera_pan = Matrix::translate(self.pan_xyz);
@@ -12,6 +12,7 @@ pub enum RenderError {
     GLError(GlError),
     TryFromIntError(TryFromIntError),
     DataLengthError(usize),
+    EmptyFramebuffer((usize, usize)),
 }
 
 impl From<GlError> for RenderError {
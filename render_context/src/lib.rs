@@ -1,6 +1,6 @@
 extern crate matrices;
 extern crate opengl;
-extern crate glfw;
+pub extern crate glfw;
 
 pub mod errors;
 
@@ -9,4 +9,4 @@ pub mod lighting;
 pub mod render;
 pub mod window;
 
-pub use opengl::enums;
\ No newline at end of file
+pub use opengl::enums;
@@ -2,12 +2,15 @@ use std::time::{Duration, Instant};
 
 use opengl;
 use opengl::enums::{BufferBit, DrawMode, GlError, ProgramSelect, UniformType};
+use opengl::enums::{TextureData, TextureFilter, TextureFormat};
 use opengl::shader_abstractions;
 use opengl::shader_abstractions::{ProgramHolder, WithProgram};
-use opengl::high_level_abstractions::WithVertexObject;
+use opengl::high_level_abstractions::{WithTexture, WithVertexObject};
 //use matrices::_tests::matrix_as_1_array::Matrix;
 use matrices::matrix::Matrix;
 
+use images::tone_map::ToneMapSettings;
+
 use glfw;
 use glfw::{Action, Key};
 use crate::errors::RenderError;
@@ -22,6 +25,7 @@ pub struct Render {
     pub camera:Camera,
     pub lighting:Lighting,
     pub programs:ProgramHolder,
+    pub tone_mapping:ToneMapSettings,
     pub paused:bool,
     pub pause_time:Instant,
     pub current_time:Instant
@@ -34,10 +38,14 @@ impl Render {
 
         let simple_orthographic_shader = shader_abstractions::create_program(&window.opengl, ProgramSelect::SelectSimpleOrthographic)?;
         let blinn_phone_orthographic_shader = shader_abstractions::create_program(&window.opengl, ProgramSelect::SelectBlinnPhongOrthographic)?;
+        let textured_quad_shader = shader_abstractions::create_program(&window.opengl, ProgramSelect::SelectTexturedQuad)?;
+        let tone_mapped_quad_shader = shader_abstractions::create_program(&window.opengl, ProgramSelect::SelectToneMappedQuad)?;
 
-        let programs = ProgramHolder::new(simple_orthographic_shader, blinn_phone_orthographic_shader)?;
+        let programs = ProgramHolder::new(simple_orthographic_shader, blinn_phone_orthographic_shader,
+                                        textured_quad_shader, tone_mapped_quad_shader)?;
 
         Ok(Self { window, camera, lighting, programs:programs,
+            tone_mapping:ToneMapSettings::new(),
             paused:false, pause_time:Instant::now(), current_time:Instant::now() })
     }
     pub fn render_over(&self) -> bool { self.window.window.should_close() }
@@ -45,6 +53,7 @@ impl Render {
 
     pub fn new(window:Window, camera:Camera, lighting:Lighting, programs:ProgramHolder) -> Render {
         Render { window, camera, lighting, programs:programs,
+            tone_mapping:ToneMapSettings::new(),
             paused:false, pause_time:Instant::now(), current_time:Instant::now() }
     }
 
@@ -90,8 +99,10 @@ impl Render {
 
 
 
+    // 7 columns are xyz rgb a, 10 add a normal, 5 are xyz uv for textured quads
     pub fn create_vao_vbo(&self, data:&Matrix<f32>) -> Result<(u32, u32), RenderError> {
         let store_normals = match data.shape[0] {
+            5 => return Ok(WithVertexObject::new_textured_vao_vbo(&self.window.opengl, data)?),
             7 => Ok(false),
             10 => Ok(true),
             n => Err(RenderError::DataLengthError(n)),
@@ -100,12 +111,57 @@ impl Render {
         Ok(WithVertexObject::new_vao_vbo(&self.window.opengl, store_normals, data)?)
     }
 
+    pub fn create_texture(&self, width:usize, height:usize, format:TextureFormat,
+                        data:TextureData<'_>, filter:TextureFilter) -> Result<u32, RenderError> {
+        Ok(WithTexture::new_texture(&self.window.opengl, width, height, format, data, filter)?)
+    }
+
+    pub fn update_texture(&self, texture:u32, width:usize, height:usize,
+                        format:TextureFormat, data:TextureData<'_>) -> Result<(), RenderError> {
+        let with_texture = WithTexture::texture(&self.window.opengl, texture);
+        Ok(with_texture.tex_sub_image_2d(width, height, format, data)?)
+    }
+
+    pub fn delete_texture(&self, texture:u32) {
+        WithTexture::texture(&self.window.opengl, texture).delete();
+    }
+
+    pub fn set_texture_filter(&self, texture:u32, filter:TextureFilter) {
+        let with_texture = WithTexture::texture(&self.window.opengl, texture);
+        with_texture.set_filter(filter);
+    }
+
+    pub fn update_vbo(&self, vbo:u32, data:&Matrix<f32>) {
+        let with_vbo = WithVertexObject::vbo(&self.window.opengl, vbo);
+        with_vbo.update_vbo(data);
+    }
+
+    pub fn draw_textured_vao(&self, mode:DrawMode, vao:u32, texture:u32, data:&Matrix<f32>) -> Result<(), RenderError> {
+        let _with_texture = WithTexture::texture_on_unit(&self.window.opengl, texture, 0)?;
+        let with_vao = WithVertexObject::vao(&self.window.opengl, vao);
+        Ok(with_vao.draw_vao(mode, data)?)
+    }
+
     pub fn draw_vao(&self, mode:DrawMode, vao:u32, data:&Matrix<f32>) -> Result<(), RenderError> {
         let with_vao = WithVertexObject::vao(&self.window.opengl, vao);
         Ok(with_vao.draw_vao(mode, data)?)
     }
 
 
+    // rgba u8 of the frame drawn so far, top row first, for screenshots
+    pub fn read_framebuffer(&self) -> Result<(usize, usize, Vec<u8>), RenderError> {
+        let (width, height) = (self.window.width()? as usize, self.window.height()? as usize);
+        // a minimised window has nothing to read
+        if width == 0 || height == 0 {
+            return Err(RenderError::EmptyFramebuffer((width, height)));
+        }
+        let pixels = opengl::intermediate_opengl::read_pixels_rgba(&self.window.opengl, width as i32, height as i32);
+        let row_len = width * 4;
+        let flipped = pixels.chunks_exact(row_len).rev().flatten().copied().collect();
+        Ok((width, height, flipped))
+    }
+
+
     pub fn use_program(&self, program_type:ProgramSelect) -> Result<(), RenderError> {
 
         let with_program = WithProgram::program(&self.window.opengl, program_type, self.programs);
@@ -118,10 +174,24 @@ impl Render {
                 self.set_orthographic_camera_uniforms(&with_program)?;
                 self.set_blinn_phong_uniforms(&with_program)?;
             },
+            ProgramSelect::SelectTexturedQuad => {
+                self.set_flat_camera_uniforms(&with_program)?;
+            },
+            ProgramSelect::SelectToneMappedQuad => {
+                self.set_flat_camera_uniforms(&with_program)?;
+                self.set_tone_mapping_uniforms(&with_program)?;
+            },
         }
         Ok(())
     }
 
+    // for uniforms a program has beyond what use_program sets, call after use_program
+    pub fn set_uniform(&self, program_type:ProgramSelect, uniform_name:&str,
+                    uniform_type:UniformType, value:Matrix<f32>) -> Result<(), RenderError> {
+        let with_program = WithProgram::program(&self.window.opengl, program_type, self.programs);
+        Ok(with_program.set_uniform(uniform_name, uniform_type, value)?)
+    }
+
     fn set_orthographic_camera_uniforms(&self, with_program:&WithProgram<'_>) -> Result<(), RenderError> {
         with_program.set_uniform("world_transform", UniformType::Mat4, Matrix::opengl_to_right_handed())?;
         with_program.set_uniform("orthographic_projection", UniformType::Mat4,
@@ -137,6 +207,32 @@ impl Render {
 
 
 
+    // images are drawn flat against the screen, so only the pan and zoom of the camera apply
+    fn set_flat_camera_uniforms(&self, with_program:&WithProgram<'_>) -> Result<(), RenderError> {
+        with_program.set_uniform("world_transform", UniformType::Mat4, Matrix::translate((0.0, 0.0, 0.0)))?;
+        with_program.set_uniform("orthographic_projection", UniformType::Mat4,
+            self.camera.get_orthographic_projection(self.window.width()?, self.window.height()?))?;
+        with_program.set_uniform("camera_transformation", UniformType::Mat4,
+            Matrix::translate(self.camera.pan_xyz))?;
+        Ok(())
+    }
+
+
+
+    fn set_tone_mapping_uniforms(&self, with_program:&WithProgram<'_>) -> Result<(), RenderError> {
+        with_program.set_uniform("tone_map_operator", UniformType::Float,
+            Matrix::from_scalar(self.tone_mapping.operator.shader_index()))?;
+        with_program.set_uniform("white_point", UniformType::Float,
+            Matrix::from_scalar(self.tone_mapping.operator.white_point()))?;
+        with_program.set_uniform("exposure", UniformType::Float,
+            Matrix::from_scalar(self.tone_mapping.exposure))?;
+        with_program.set_uniform("gamma", UniformType::Float,
+            Matrix::from_scalar(self.tone_mapping.gamma))?;
+        Ok(())
+    }
+
+
+
     fn set_blinn_phong_uniforms(&self, with_program:&WithProgram<'_>) -> Result<(), RenderError> {
         with_program.set_uniform("ambient_strength", UniformType::Float,
             Matrix::from_scalar(self.lighting.ambient_strength))?;
@@ -213,7 +309,10 @@ impl Render {
                 },
 
                 glfw::WindowEvent::Scroll(_xoffset, yoffset) => {
-                    {self.camera.zoom -= ((0.24*yoffset) as f32) * self.camera.zoom*0.25; Ok(())}
+                    let new_zoom = self.camera.zoom - ((0.24*yoffset) as f32) * self.camera.zoom*0.25;
+                    let cursor = self.window.last_cursor_pos;
+                    self.camera.zoom_around(new_zoom, cursor, self.window.width()?, self.window.height()?);
+                    Ok(())
                 },
 
                 glfw::WindowEvent::CursorPos(xpos, ypos) => {
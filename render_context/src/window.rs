@@ -47,6 +47,7 @@ impl Window {
     pub fn set_polling(&mut self)  { self.window.set_all_polling(true); }
     pub fn swap_buffers(&mut self) { self.window.swap_buffers(); }
     pub fn make_current(&mut self) { self.window.make_current(); }
+    pub fn set_title(&mut self, title:&str) { self.window.set_title(title); }
     pub fn width(&self) -> Result<u32, RenderError> { 
         match self.window.get_size().0.try_into() {
             Ok(u) => Ok(u),
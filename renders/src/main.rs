@@ -9,6 +9,7 @@ use render_context::errors::RenderError;
 use render_context::render::Render;
 use render_context::enums::{GlError, ProgramSelect, DrawMode};
 use matrices::matrix::Matrix;
+use meshes::obj::load_obj;
 
 //use ppm_viewer;
 
@@ -56,6 +57,18 @@ fn main() -> Result<(), RenderError> {
         [0.0, -5.0, 0.0, 0.5, 0.1, 0.9, 1.0, 0.5, 0.5, 0.5],
     ]);
 
+    // renders <model.obj> draws the model in place of the triangle
+    let triangle_normals = match std::env::args().nth(1) {
+        Some(path) => match load_obj(&path) {
+            Ok(mesh) => mesh.vertex_matrix(),
+            Err(error) => {
+                eprintln!("could not load {}: {:?}", path, error);
+                std::process::exit(1);
+            },
+        },
+        None => triangle_normals,
+    };
+
 
 
     //let (t_vao, t_vbo) = WithObject::new_vao_vbo(&render.window.opengl, false, &triangle);